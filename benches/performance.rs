@@ -24,6 +24,13 @@
 //! cargo bench
 //! ```
 
+#![allow(
+    clippy::needless_range_loop,
+    clippy::unit_arg,
+    clippy::unused_unit,
+    clippy::useless_vec
+)]
+
 use std::hint::black_box;
 use std::time::{Duration, Instant};
 
@@ -174,6 +181,41 @@ mod performance_tests {
         }, 100);
     }
 
+    /// Benchmarks compiling the code block regex on every call against
+    /// reusing a lazily compiled static, as `parse_history` does.
+    ///
+    /// The history is parsed on every frame while a response streams, so
+    /// the per-call compilation cost used to be paid up to 20 times per
+    /// second.
+    #[test]
+    fn bench_code_block_regex() {
+        use regex::Regex;
+        use std::sync::LazyLock;
+
+        const PATTERN: &str = r"(?s)```(?P<lang>\w+)?\n(?P<code>.*?)```";
+        static CODE_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(PATTERN).unwrap());
+
+        let history = "YOU: Test\nAI: Here's code:\n\n```rust\nfn main() {}\n```\n".repeat(20);
+
+        let start = Instant::now();
+        bench_fn("code_block_regex_per_call", || {
+            let re = Regex::new(PATTERN).unwrap();
+            black_box(re.captures_iter(&history).count());
+        }, 100);
+        let per_call = start.elapsed();
+
+        let start = Instant::now();
+        bench_fn("code_block_regex_lazy_static", || {
+            black_box(CODE_BLOCK_RE.captures_iter(&history).count());
+        }, 100);
+        let lazy = start.elapsed();
+
+        println!(
+            "  Lazy static speedup: {:.1}x",
+            per_call.as_secs_f64() / lazy.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+
     /// Benchmarks cursor navigation and text position operations.
     ///
     /// This test measures performance of:
@@ -33,15 +33,349 @@
 //! - Streaming response handling
 //! - State persistence across model switches
 
-use anyhow::Result;
-use ollama_rs::{generation::completion::request::GenerationRequest, Ollama};
-use ratatui::{backend::CrosstermBackend, widgets::ListState, Terminal};
-use std::collections::HashMap;
+use crate::client::{
+    self, ChunkStream, ClientFactory, GenerateOptions, GenerateRequest, GenerationTimings, ModelInfo,
+    ModelClient, DEFAULT_HOST_URL,
+};
+use crate::clipboard;
+use crate::error::{LazyLlamaError, Result};
+use crate::utils::{self, Draft};
+use crate::config::{
+    self, apply_template, AutoscrollMode, CodeBlockStyle, Config, Host, Labels, SendKey, Snippet,
+    LogFormat, TextDirection,
+    MAX_SNIPPETS,
+};
+use crate::ui::{
+    exchange_parts, extract_code_blocks, format_size, format_turn_footer, last_answer,
+    split_exchanges, strip_request_debug, Emphasis, ATTACHMENT_PREFIX, ERROR_PREFIX,
+    FOOTER_PREFIX, REQUEST_DEBUG_PREFIX, TURN_SEPARATOR,
+};
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::{
+    backend::Backend,
+    layout::{Position, Rect},
+    widgets::ListState,
+    Terminal,
+};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::env;
-use std::io;
-use std::time::Instant;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TryRecvError, UnboundedReceiver};
+use tokio::time::timeout;
 use tokio_stream::StreamExt;
 
+/// Poll interval used by the event loop when nothing else is configured.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Poll interval used by adaptive polling while a response is streaming.
+pub const ACTIVE_POLL_INTERVAL: Duration = Duration::from_millis(33);
+/// Upper bound for the poll interval used by adaptive polling when idle.
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Time between two cursor blink toggles.
+pub const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Strategy used by the event loop to choose its `event::poll` timeout.
+///
+/// Short intervals make animations (spinner, cursor blink) smoother but wake
+/// the process more often; long intervals save CPU and battery at the cost of
+/// coarser animation timing. Keyboard input is never delayed by the interval,
+/// since `event::poll` returns as soon as an event arrives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollMode {
+    /// Always poll with the same timeout.
+    Fixed(Duration),
+    /// Poll quickly while loading and sleep until the next cursor blink
+    /// (capped at [`IDLE_POLL_INTERVAL`]) otherwise.
+    Adaptive,
+}
+
+impl Default for PollMode {
+    fn default() -> Self {
+        PollMode::Fixed(DEFAULT_POLL_INTERVAL)
+    }
+}
+
+impl PollMode {
+    /// Parses a `LAZYLLAMA_POLL_MS` value.
+    ///
+    /// Accepts `adaptive` or a positive number of milliseconds. Returns `None`
+    /// for anything else so the caller can fall back to the default.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("adaptive") {
+            return Some(PollMode::Adaptive);
+        }
+        match value.parse::<u64>() {
+            Ok(ms) if ms > 0 => Some(PollMode::Fixed(Duration::from_millis(ms))),
+            _ => None,
+        }
+    }
+}
+
+/// Temperature assumed for models without one set (Ollama's default).
+pub const DEFAULT_TEMPERATURE: f32 = 0.8;
+/// Lowest temperature reachable with [`App::lower_temperature`].
+pub const MIN_TEMPERATURE: f32 = 0.0;
+/// Highest temperature reachable with [`App::raise_temperature`].
+pub const MAX_TEMPERATURE: f32 = 2.0;
+/// Step of one temperature adjustment.
+pub const TEMPERATURE_STEP: f32 = 0.1;
+
+/// Answer caps in tokens that `Alt+]` and `Alt+[` step through; above the
+/// last one answers are unlimited.
+pub const NUM_PREDICT_STEPS: [i32; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+
+/// Characters per token assumed by [`estimate_tokens`].
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Roughly estimates the number of tokens `text` takes up in a model's
+/// context.
+///
+/// Uses the common rule of thumb of one token per [`CHARS_PER_TOKEN`]
+/// characters, rounded up; real tokenizers differ per model, so this is
+/// only good for warnings.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::estimate_tokens;
+///
+/// assert_eq!(estimate_tokens(""), 0);
+/// assert_eq!(estimate_tokens("Hello"), 2);
+/// ```
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Longest selection, in characters, that [`explain_prompt`] quotes in full.
+pub const EXPLAIN_MAX_CHARS: usize = 1000;
+
+/// Key under which [`App::drafts`] stores the input with `shared_input`.
+pub const SHARED_DRAFT_KEY: &str = "";
+
+/// Longest wait for the running-models check made alongside a request.
+const RUNNING_MODELS_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// How long a model that was already loaded may take for its first token
+/// before [`NO_TOKENS_HINT`] is shown.
+pub const NO_TOKENS_AFTER: Duration = Duration::from_millis(1500);
+
+/// Hint shown while a model that was already loaded has not answered yet.
+pub const NO_TOKENS_HINT: &str = "model loaded, no tokens yet…";
+
+/// Number of times an interrupted answer is resumed before the error is
+/// shown (see [`App::resume_interrupted`]).
+pub const MAX_RESUME_ATTEMPTS: usize = 2;
+
+/// Note put into the answer where a resumed stream continues.
+pub const RECONNECTED_NOTE: &str = " [reconnected] ";
+
+/// Input command that clears the conversation (see
+/// [`App::clear_conversation`]).
+pub const CLEAR_COMMAND: &str = "/clear";
+
+/// Input command that attaches a file (see [`App::attach_file`]).
+pub const FILE_COMMAND: &str = "/file";
+
+/// Input command that scrolls to a turn (see [`App::goto_turn`]).
+pub const GOTO_COMMAND: &str = "/goto";
+
+/// Input command that pipes the last answer through a shell command (see
+/// [`App::pipe_last_answer`]).
+pub const PIPE_COMMAND: &str = "/pipe";
+
+/// Composes a follow-up prompt asking the model to explain `selection`.
+///
+/// The selection is quoted line by line with `> `. Selections longer than
+/// `max_chars` characters are cut at the last word boundary before the
+/// limit and marked with `…`, so a long answer does not flood the input.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::explain_prompt;
+///
+/// assert_eq!(explain_prompt("a\nb", 100), "Explain this:\n> a\n> b\n");
+/// assert_eq!(explain_prompt("one two three", 9), "Explain this:\n> one two …\n");
+/// ```
+pub fn explain_prompt(selection: &str, max_chars: usize) -> String {
+    let selection = selection.trim();
+    let quoted = match selection.char_indices().nth(max_chars) {
+        Some((limit, _)) => {
+            let cut = selection[..limit]
+                .rfind(char::is_whitespace)
+                .filter(|&i| i > 0)
+                .unwrap_or(limit);
+            format!("{} …", selection[..cut].trim_end())
+        }
+        None => selection.to_string(),
+    };
+    let mut prompt = String::from("Explain this:\n");
+    for line in quoted.lines() {
+        prompt.push_str("> ");
+        prompt.push_str(line);
+        prompt.push('\n');
+    }
+    prompt
+}
+
+/// Fuzzy matcher of the model filter.
+static MODEL_MATCHER: LazyLock<SkimMatcherV2> =
+    LazyLock::new(|| SkimMatcherV2::default().ignore_case());
+
+/// Matches the model filter `query` against `name`, ignoring case.
+///
+/// Returns the match score, higher for better matches, and the character
+/// indices of `name` that matched. With `fuzzy` the query's characters
+/// only have to appear in order; otherwise it has to be a substring.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::model_match;
+///
+/// assert!(model_match("codellama:13b", "cl13", true).is_some());
+/// assert_eq!(model_match("codellama:13b", "cl13", false), None);
+/// assert_eq!(model_match("codellama:13b", "Llama", false).unwrap().1, [4, 5, 6, 7, 8]);
+/// ```
+pub fn model_match(name: &str, query: &str, fuzzy: bool) -> Option<(i64, Vec<usize>)> {
+    if fuzzy {
+        return MODEL_MATCHER.fuzzy_indices(name, query);
+    }
+    let name = name.to_lowercase();
+    let position = name.find(&query.to_lowercase())?;
+    let start = name[..position].chars().count();
+    // Earlier matches rank higher.
+    Some((-(start as i64), (start..start + query.chars().count()).collect()))
+}
+
+/// Returns the indices of the `models` matching `query` (see
+/// [`model_match`]), best match first; equally good matches keep the list
+/// order. An empty query matches every model.
+pub fn filter_models(models: &[String], query: &str, fuzzy: bool) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = models
+        .iter()
+        .enumerate()
+        .filter_map(|(i, model)| model_match(model, query, fuzzy).map(|(score, _)| (score, i)))
+        .collect();
+    scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Returns the trimmed argument if `line` is `command`, alone or followed by
+/// whitespace and an argument.
+fn command_argument<'a>(line: &'a str, command: &str) -> Option<&'a str> {
+    let argument = line.strip_prefix(command)?;
+    if !argument.is_empty() && !argument.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(argument.trim())
+}
+
+/// Composes the prompt that resumes an answer to `prompt` which broke off
+/// after `partial`.
+///
+/// The generate API has no assistant prefix, so the partial answer follows
+/// the original prompt with an instruction to continue it.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::continuation_prompt;
+///
+/// let resumed = continuation_prompt("Count to 5", "1, 2,");
+/// assert!(resumed.starts_with("Count to 5\n\n"));
+/// assert!(resumed.ends_with("\n\n1, 2,"));
+/// ```
+pub fn continuation_prompt(prompt: &str, partial: &str) -> String {
+    format!(
+        "{}\n\nYour answer was cut off. Continue it exactly where it stops, \
+         without repeating any of it:\n\n{}",
+        prompt, partial
+    )
+}
+
+/// Author of a message added with [`App::append_message`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// A `YOU:` turn.
+    User,
+    /// An `AI [model]:` turn, closing the exchange.
+    Assistant,
+}
+
+/// Per-model buffers of a host that is not active, kept while another host
+/// is in use (see [`App::switch_host`]).
+#[derive(Debug, Clone, Default)]
+pub struct HostBuffers {
+    /// Input buffers, like [`App::model_inputs`].
+    pub inputs: HashMap<String, String>,
+    /// Cursor positions, like [`App::model_cursors`].
+    pub cursors: HashMap<String, usize>,
+    /// Conversation histories, like [`App::model_histories`].
+    pub histories: HashMap<String, String>,
+    /// Scroll positions, like [`App::model_scrolls`].
+    pub scrolls: HashMap<String, u16>,
+    /// Collapsed exchanges, like [`App::model_collapsed`].
+    pub collapsed: HashMap<String, BTreeSet<usize>>,
+    /// Bookmarks, like [`App::model_bookmarks`].
+    pub bookmarks: HashMap<String, u16>,
+    /// Query times, like [`App::last_activity`].
+    pub last_activity: HashMap<String, Instant>,
+}
+
+/// Saved logs of one model, as shown in the log manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogGroup {
+    /// Model name as it appears in the file names.
+    pub model: String,
+    /// Number of log files.
+    pub files: usize,
+    /// Total size of the files in bytes.
+    pub bytes: u64,
+}
+
+/// State of the open log manager popup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogManager {
+    /// Models with saved logs, sorted by name.
+    pub groups: Vec<LogGroup>,
+    /// Highlighted entry of `groups`.
+    pub selected: usize,
+    /// Whether deleting the highlighted model's logs awaits confirmation.
+    pub confirm_delete: bool,
+}
+
+/// State of the open model filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelFilter {
+    /// Text typed so far.
+    pub query: String,
+    /// Indices into `App::models` of the matching models, best match first.
+    pub filtered_indices: Vec<usize>,
+    /// Highlighted entry of `filtered_indices`.
+    pub selected: usize,
+}
+
+/// State of the open model info popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelInfoPopup {
+    /// Model the details are about.
+    pub model: String,
+    /// The details, or why they could not be fetched.
+    pub info: std::result::Result<ModelInfo, String>,
+    /// Lines scrolled past at the top.
+    pub scroll: u16,
+}
+
+/// Reads the key presses waiting in the terminal without blocking, so keys
+/// typed while an answer streams are not lost (see [`App::key_source`]).
+pub type KeySource = Box<dyn FnMut() -> Vec<KeyEvent> + Send>;
+
 /// Main application state container for LazyLlama.
 ///
 /// This structure holds all the necessary state for the Terminal UI including
@@ -50,7 +384,8 @@ use tokio_stream::StreamExt;
 /// for input text, conversation history, and scroll position to provide
 /// seamless switching between different models.
 pub struct App {
-    /// List of locally available Ollama models discovered from the API.
+    /// Models shown in the list: the locally available Ollama models,
+    /// filtered and ordered by `model_allowlist` unless all are shown.
     pub models: Vec<String>,
     /// State of the model selection list widget (currently selected index).
     pub list_state: ListState,
@@ -68,14 +403,21 @@ pub struct App {
     pub model_scrolls: HashMap<String, u16>,
     /// Current vertical scroll position in the conversation history.
     pub scroll: u16,
+    /// Bookmarked scroll position in the active conversation, if any.
+    pub bookmark: Option<u16>,
+    /// Separate bookmarks maintained for each LLM model.
+    pub model_bookmarks: HashMap<String, u16>,
     /// Current cursor position in the input field (character index).
     pub cursor_pos: usize,
     /// Flag indicating whether the view should automatically scroll to the bottom.
     pub autoscroll: bool,
+    /// Whether autoscroll follows the bottom or stops at the start of the
+    /// last answer.
+    pub autoscroll_mode: AutoscrollMode,
     /// Indicates whether an AI query is currently being processed.
     pub is_loading: bool,
-    /// Instance of the Ollama client for API communication.
-    pub ollama: Ollama,
+    /// Model backend used for API communication (Ollama in production).
+    pub client: Box<dyn ModelClient>,
     /// Timestamp of application start (used for UI animations like spinner).
     pub start_time: Instant,
     /// Timestamp of last cursor blink toggle.
@@ -84,18 +426,225 @@ pub struct App {
     pub cursor_visible: bool,
     /// Enables on-screen debug info when true.
     pub debug_keys: bool,
+    /// In debug mode, write a `[request debug]` block showing what is sent
+    /// before every exchange (toggled with `F6`). The blocks are never
+    /// saved.
+    pub echo_requests: bool,
     /// Last key event debug string (when enabled).
     pub debug_last_key: Option<String>,
     /// Frame counter for render debugging.
     pub render_count: u64,
+    /// Duration of the last `ui()` call in microseconds (debug only).
+    pub last_render_us: u64,
+    /// Duration of appending the last streamed chunks and redrawing, in
+    /// microseconds (debug only).
+    pub last_append_us: u64,
+    /// Strategy for choosing the event loop poll timeout.
+    pub poll_mode: PollMode,
+    /// Transient feedback shown in the status bar until the next key press.
+    pub status_message: Option<String>,
+    /// Time of the last query sent to each model, used for MRU ordering.
+    pub last_activity: HashMap<String, Instant>,
+    /// Highlighted entry of the open recent-models switcher, if it is open.
+    pub mru_selection: Option<usize>,
+    /// Prompt snippets from the config file, bound to `Alt+1` … `Alt+9`.
+    pub snippets: Vec<Snippet>,
+    /// Whether the help overlay is shown.
+    pub show_help: bool,
+    /// Whether focus mode hides the model list and status bar.
+    pub focus_mode: bool,
+    /// Whether the transcript is rendered without extra blank lines.
+    pub compact: bool,
+    /// How code blocks are drawn in the transcript.
+    pub code_block_style: CodeBlockStyle,
+    /// Emphasis applied over the transcript, cycled with `F7`.
+    pub emphasis: Emphasis,
+    /// Version reported by the model server, if it could be determined.
+    pub server_version: Option<String>,
+    /// `strftime` format of the timestamp inserted with `Ctrl+D`.
+    pub timestamp_format: String,
+    /// Language of the code block template inserted with `Alt+K`: the
+    /// configured one until a prompt with a tagged code block is sent.
+    pub code_language: String,
+    /// Text put before the last answer by [`App::yank_last_answer`].
+    pub yank_lead_in: String,
+    /// Template wrapped around every prompt (see [`crate::config`]).
+    pub prompt_template: Option<String>,
+    /// Per-model prompt templates, taking precedence over `prompt_template`.
+    pub model_templates: HashMap<String, String>,
+    /// Primer put before the first prompt of an empty conversation (see
+    /// [`App::primed_prompt`]).
+    pub greeting: Option<String>,
+    /// Indices of the exchanges of the active conversation that are collapsed.
+    pub collapsed: BTreeSet<usize>,
+    /// Separate collapsed exchanges maintained for each LLM model.
+    pub model_collapsed: HashMap<String, BTreeSet<usize>>,
+    /// Exchange highlighted for folding, if any.
+    pub focused_exchange: Option<usize>,
+    /// Asks the next render to scroll the focused exchange into view.
+    pub scroll_to_focus: bool,
+    /// Keep the replaced answer when regenerating, to show a diff.
+    pub regenerate_diff: bool,
+    /// Answer of the last exchange before it was regenerated.
+    pub previous_answer: Option<String>,
+    /// Directory for archived conversations; `None` uses [`utils::data_dir`].
+    pub data_dir: Option<PathBuf>,
+    /// Every model reported by the server; `models` is the visible subset.
+    pub all_models: Vec<String>,
+    /// Models to show, in display order (from the config file).
+    pub model_allowlist: Vec<String>,
+    /// Whether to ignore `model_allowlist` and show every model.
+    pub show_all_models: bool,
+    /// Custom model order; listed models are shown first, in this order.
+    pub model_order: Vec<String>,
+    /// Config file the custom model order is saved to; `None` keeps it in
+    /// memory only.
+    pub config_path: Option<PathBuf>,
+    /// Whether `Enter` or `Ctrl+Enter` sends the input.
+    pub send_key: SendKey,
+    /// Ephemeral session: no conversation, history or config file is written.
+    pub ephemeral: bool,
+    /// Strip trailing whitespace from each finished answer.
+    pub trim_response: bool,
+    /// Icons shown in front of the turn labels.
+    pub labels: Labels,
+    /// Models used in earlier sessions, most recent first; the first one
+    /// that is installed is selected on startup.
+    pub recent_models: Vec<String>,
+    /// Prompt of the last request if it failed; cleared on success, when
+    /// typing or when switching models.
+    pub last_failed_prompt: Option<String>,
+    /// Error of the last request if it failed; cleared by the next
+    /// successful one.
+    pub last_error: Option<LazyLlamaError>,
+    /// Whether the last request failed by returning an empty answer, so
+    /// [`App::retry`] nudges the temperature first.
+    pub empty_response: bool,
+    /// The request sent last, for [`App::copy_request_as_curl`].
+    pub last_request: Option<GenerateRequest>,
+    /// Byte offset in `history` where the answer being streamed starts;
+    /// `None` when no answer is streaming.
+    pub stream_start: Option<usize>,
+    /// Time the request was sent, until its first token arrives.
+    pub waiting_since: Option<Instant>,
+    /// Delay before `thinking_hint` is shown while waiting; zero never
+    /// shows it.
+    pub thinking_hint_after: Duration,
+    /// Reassurance shown while a slow model has not answered yet.
+    pub thinking_hint: String,
+    /// Whether the model of the pending request was already loaded on the
+    /// server when it was sent.
+    pub model_was_loaded: bool,
+    /// Context window in tokens assumed for models without an entry in
+    /// `context_limits`.
+    pub context_limit: Option<usize>,
+    /// Per-model context windows from the config file.
+    pub context_limits: HashMap<String, usize>,
+    /// Answer cap in tokens for models without an entry in `num_predicts`
+    /// or an adjusted cap in `model_options`.
+    pub num_predict: Option<i32>,
+    /// Per-model answer caps from the config file.
+    pub num_predicts: HashMap<String, i32>,
+    /// Keep the input when switching models instead of using `model_inputs`.
+    pub shared_input: bool,
+    /// Reload the last saved conversation of each model on startup.
+    pub restore_histories: bool,
+    /// Whether saved conversations are still being reloaded.
+    pub histories_loading: bool,
+    /// Ask before quitting once the conversations exceed
+    /// `confirm_quit_after` characters.
+    pub confirm_quit: bool,
+    /// Characters of conversation above which quitting asks first.
+    pub confirm_quit_after: usize,
+    /// Input commands that only run when sent twice in a row.
+    pub confirm_commands: Vec<String>,
+    /// Input command line sent once and awaiting confirmation by sending
+    /// it again.
+    pub pending_command: Option<String>,
+    /// Message of the open quit confirmation, naming where the session
+    /// will be saved.
+    pub quit_confirmation: Option<String>,
+    /// Conversations read by the background reload, as `(model, history)`.
+    pub history_reload: Option<UnboundedReceiver<(String, String)>>,
+    /// Sampling options set for each model; missing models use the server
+    /// defaults.
+    pub model_options: HashMap<String, GenerateOptions>,
+    /// Open log manager popup, if any.
+    pub log_manager: Option<LogManager>,
+    /// Ollama servers from the config file; empty when only the default
+    /// server is used.
+    pub hosts: Vec<Host>,
+    /// Index into `hosts` of the server `client` talks to.
+    pub active_host: usize,
+    /// Highlighted entry of the open host picker, if it is open.
+    pub host_selection: Option<usize>,
+    /// Buffers of the inactive hosts, keyed by host name.
+    pub host_buffers: HashMap<String, HostBuffers>,
+    /// Creates the client for a host when switching hosts.
+    pub client_factory: ClientFactory,
+    /// Save unsent inputs on exit and restore them on startup.
+    pub restore_drafts: bool,
+    /// Most combined chat logs to keep on exit; `None` keeps all.
+    pub max_chat_logs: Option<usize>,
+    /// Format of the logs saved on exit.
+    pub log_format: LogFormat,
+    /// Directory code blocks are exported to with `Ctrl+X`.
+    pub export_dir: PathBuf,
+    /// Whether the status bar shows a clock.
+    pub status_clock: bool,
+    /// Whether the status bar shows the session duration.
+    pub status_session: bool,
+    /// Unix second the clock showed when it was last drawn.
+    pub clock_second: i64,
+    /// Prompts waiting to be sent, in order, once the current answer is
+    /// complete (see [`App::queue_prompt`]).
+    pub pending_prompts: VecDeque<String>,
+    /// Polled while an answer streams: typed text goes into the input and
+    /// the send key queues it. `None` ignores keys until the answer is
+    /// complete.
+    pub key_source: Option<KeySource>,
+    /// Keys read while an answer streamed that only the event loop
+    /// handles, replayed in order once the answer is complete.
+    pub deferred_keys: VecDeque<KeyEvent>,
+    /// Re-issue requests whose stream broke off, up to
+    /// [`MAX_RESUME_ATTEMPTS`] times (see [`continuation_prompt`]).
+    pub resume_interrupted: bool,
+    /// Models tried in turn when a request fails before any text arrived;
+    /// each is tried at most once per prompt.
+    pub fallback_models: Vec<String>,
+    /// Screen area of every answer's copy button with the index of its
+    /// exchange, recorded by each frame (see [`App::click`]).
+    pub copy_regions: Vec<(Rect, usize)>,
+    /// Number the exchanges in the transcript.
+    pub number_turns: bool,
+    /// Whether turns are right-aligned for right-to-left languages.
+    pub text_direction: TextDirection,
+    /// Model details fetched so far on the active host, by model name.
+    pub model_info_cache: HashMap<String, ModelInfo>,
+    /// The open model info popup, if any.
+    pub model_info_popup: Option<ModelInfoPopup>,
+    /// The open model filter, if any.
+    pub model_filter: Option<ModelFilter>,
+    /// Whether the model filter matches fuzzily instead of by substring.
+    pub fuzzy_filter: bool,
+    /// Keep the buffers of models that are no longer installed when the
+    /// model list is refreshed.
+    pub keep_unavailable_models: bool,
+    /// Plain rendering for screen readers (see [`RenderOptions::plain`]).
+    ///
+    /// [`RenderOptions::plain`]: crate::ui::RenderOptions::plain
+    pub plain: bool,
+    /// Whether animations are off: a static cursor and loading title, and
+    /// no redraws without an event.
+    pub quiet: bool,
 }
 
 impl App {
     /// Creates a new instance of the application and initializes the model list.
     ///
     /// This constructor performs the following initialization steps:
-    /// 1. Creates a default Ollama client instance
-    /// 2. Initializes all application state with default values
+    /// 1. Reads settings from the environment and the config file
+    /// 2. Builds the application around a default Ollama client (see [`App::builder`])
     /// 3. Sets up empty HashMaps for per-model buffer management
     /// 4. Automatically discovers and caches available models
     /// 5. Selects the first model if any are available
@@ -116,35 +665,84 @@ impl App {
     /// }
     /// ```
     pub async fn new() -> Self {
-        let ollama = Ollama::default();
         let debug_keys = env::var("LAZYLLAMA_DEBUG_KEYS")
             .map(|v| v != "0" && v.to_lowercase() != "false")
             .unwrap_or(false);
-        let mut app = App {
-            models: Vec::new(),
-            list_state: ListState::default(),
-            input: String::new(),
-            cursor_pos: 0,
-            history: String::new(),
-            model_inputs: HashMap::new(),
-            model_cursors: HashMap::new(),
-            model_histories: HashMap::new(),
-            model_scrolls: HashMap::new(),
-            scroll: 0,
-            autoscroll: true,
-            is_loading: false,
-            ollama,
-            start_time: Instant::now(),
-            last_cursor_blink: Instant::now(),
-            cursor_visible: true,
-            debug_keys,
-            debug_last_key: None,
-            render_count: 0,
+        let ephemeral = env::var("LAZYLLAMA_EPHEMERAL")
+            .map(|v| v != "0" && v.to_lowercase() != "false")
+            .unwrap_or(false);
+        let plain = env::var("LAZYLLAMA_PLAIN")
+            .map(|v| v != "0" && v.to_lowercase() != "false")
+            .unwrap_or(false);
+        let poll_mode = env::var("LAZYLLAMA_POLL_MS")
+            .ok()
+            .and_then(|v| PollMode::parse(&v))
+            .unwrap_or_default();
+        let (config, mut config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("Config error: {}", e))),
         };
-        app.refresh_models().await;
+        if let Err(e) = config.validate() {
+            config_error = Some(format!("Config warning: {}", e));
+        }
+        let restore_histories = config.restore_histories;
+        let mut builder = App::builder()
+            .debug_keys(debug_keys)
+            .ephemeral(ephemeral)
+            .poll_mode(poll_mode)
+            .recent_models(utils::load_mru_order())
+            .config(config);
+        if let Some(path) = config::config_path() {
+            builder = builder.config_path(path);
+        }
+        let mut app = builder.build();
+        app.plain |= plain;
+        app.status_message = config_error;
+        // Bookmarks point into the saved conversations, so they are only
+        // useful when those are reloaded.
+        if restore_histories {
+            app.model_bookmarks = utils::load_bookmarks();
+        }
+        if app.restore_drafts {
+            app.apply_drafts(utils::load_drafts());
+        }
+        // An unreachable server shows up as an empty model list.
+        app.refresh_models().await.ok();
+        app
+    }
+
+    /// Creates an application that talks to `client` and loads its model list.
+    ///
+    /// Unlike [`App::new`], no environment variables or config file are read.
+    /// Use [`App::builder`] for more control, e.g. to skip the model refresh.
+    pub async fn new_with_client(client: impl ModelClient + 'static) -> Self {
+        let mut app = App::builder().client(client).build();
+        app.refresh_models().await.ok();
         app
     }
 
+    /// Returns a builder for an `App` with injected dependencies.
+    ///
+    /// The builder never contacts the model server; call
+    /// [`App::refresh_models`] on the result to load the model list from the
+    /// client, or preset the list with [`AppBuilder::models`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::app::App;
+    /// use lazyllama::client::ScriptedClient;
+    ///
+    /// let app = App::builder()
+    ///     .client(ScriptedClient::new(vec!["mock".to_string()]))
+    ///     .models(vec!["mock".to_string()])
+    ///     .build();
+    /// assert_eq!(app.list_state.selected(), Some(0));
+    /// ```
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
     /// Refreshes the list of locally available AI models from Ollama.
     ///
     /// This method queries the Ollama API to discover all locally installed models
@@ -158,29 +756,229 @@ impl App {
     /// - Queries Ollama's `/api/tags` endpoint for local models
     /// - Preserves existing buffer data for known models
     /// - Initializes empty buffers for newly discovered models
-    /// - Auto-selects first model if no selection exists
+    /// - Drops the buffers of models that are no longer installed, unless
+    ///   `keep_unavailable_models` is set
+    /// - Selects the most recently used model (see `recent_models`), or the
+    ///   first model if none of them is installed
     /// - Loads buffers for the currently selected model
+    /// - Stores the server version in `server_version`, or `None` if the
+    ///   server does not report one
     ///
-    /// # Error Handling
+    /// # Errors
     ///
-    /// Silently handles Ollama API errors by leaving the model list unchanged.
-    /// This prevents the application from crashing if Ollama is temporarily
-    /// unavailable or returns an error.
-    pub async fn refresh_models(&mut self) {
-        if let Ok(models) = self.ollama.list_local_models().await {
-            self.models = models.into_iter().map(|m| m.name).collect::<Vec<String>>();
-            
-            // Initialisiere Buffer für neue Modelle
-            for model in &self.models {
-                self.model_inputs.entry(model.clone()).or_insert_with(String::new);
-                self.model_cursors.entry(model.clone()).or_insert(0);
-                self.model_histories.entry(model.clone()).or_insert_with(String::new);
-                self.model_scrolls.entry(model.clone()).or_insert(0);
+    /// Returns [`LazyLlamaError::Connection`] if the server cannot be reached.
+    /// The model list is left unchanged in that case, so a temporarily
+    /// unavailable server never empties it.
+    pub async fn refresh_models(&mut self) -> Result<()> {
+        let models = self
+            .client
+            .list_models()
+            .await
+            .map_err(|e| LazyLlamaError::Connection(e.into()))?;
+        self.set_models(models);
+        if !self.keep_unavailable_models {
+            self.prune_model_buffers();
+        }
+        self.server_version = self.client.server_version().await.ok();
+        Ok(())
+    }
+
+    /// Drops the buffers and settings of models that are not installed, so
+    /// stale conversations are neither kept in memory nor saved on exit.
+    ///
+    /// Models hidden by the allowlist are installed and keep theirs.
+    fn prune_model_buffers(&mut self) {
+        let installed = &self.all_models;
+        let keep = |model: &String| installed.contains(model);
+        self.model_inputs.retain(|m, _| keep(m));
+        self.model_cursors.retain(|m, _| keep(m));
+        self.model_histories.retain(|m, _| keep(m));
+        self.model_scrolls.retain(|m, _| keep(m));
+        self.model_collapsed.retain(|m, _| keep(m));
+        self.model_bookmarks.retain(|m, _| keep(m));
+        self.model_options.retain(|m, _| keep(m));
+        self.model_info_cache.retain(|m, _| keep(m));
+        self.last_activity.retain(|m, _| keep(m));
+    }
+
+    /// Reloads the model list from the server while the app is running, so
+    /// models pulled or removed in the meantime show up.
+    ///
+    /// New models get empty buffers and the selected model stays selected
+    /// if it is still installed. Removed models are handled as in
+    /// [`App::refresh_models`]. The result is reported in `status_message`;
+    /// if the server cannot be reached the list stays as it is.
+    pub async fn reload_models(&mut self) {
+        self.save_current_model_buffers();
+        let current = self.list_state.selected().and_then(|i| self.models.get(i)).cloned();
+        if let Err(e) = self.refresh_models().await {
+            self.status_message = Some(format!("Cannot refresh models: {}", e));
+            return;
+        }
+        if let Some(current) = current {
+            self.select_model(&current);
+        }
+        self.status_message = Some(format!("{} models (refreshed)", self.models.len()));
+    }
+
+    /// Checks that the server is at least version `minimum` before using
+    /// `feature`.
+    ///
+    /// An unknown server version passes, so servers that do not report one
+    /// are not locked out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LazyLlamaError::UnsupportedServer`] if the server is older,
+    /// with a message naming both versions.
+    pub fn require_server_version(&self, feature: &str, minimum: &str) -> Result<()> {
+        match &self.server_version {
+            Some(version) if !client::version_at_least(version, minimum) => {
+                Err(LazyLlamaError::UnsupportedServer {
+                    feature: feature.to_string(),
+                    required: minimum.to_string(),
+                    found: version.clone(),
+                })
             }
-            
-            if !self.models.is_empty() {
-                self.list_state.select(Some(0));
-                self.load_current_model_buffers();
+            _ => Ok(()),
+        }
+    }
+
+    /// Replaces the model list, initializing buffers for new models and
+    /// selecting the most recently used visible model, or the first one.
+    ///
+    /// Duplicate names are dropped: buffers are keyed by name and requests
+    /// address models by name, so a second entry could only ever share the
+    /// first one's buffers. In debug mode the duplicates are reported.
+    fn set_models(&mut self, models: Vec<String>) {
+        let mut duplicates = Vec::new();
+        self.all_models = Vec::with_capacity(models.len());
+        for model in models {
+            if self.all_models.contains(&model) {
+                duplicates.push(model);
+            } else {
+                self.all_models.push(model);
+            }
+        }
+        if self.debug_keys && !duplicates.is_empty() {
+            self.status_message =
+                Some(format!("Ignored duplicate models: {}", duplicates.join(", ")));
+        }
+
+        // Initialisiere Buffer für neue Modelle
+        for model in &self.all_models {
+            self.model_inputs.entry(model.clone()).or_default();
+            self.model_cursors.entry(model.clone()).or_insert(0);
+            self.model_histories.entry(model.clone()).or_default();
+            self.model_scrolls.entry(model.clone()).or_insert(0);
+        }
+
+        self.models = self.visible_models();
+        if !self.models.is_empty() {
+            let index = self
+                .recent_models
+                .iter()
+                .find_map(|recent| self.models.iter().position(|m| m == recent))
+                .unwrap_or(0);
+            self.list_state.select(Some(index));
+            self.load_current_model_buffers();
+        }
+    }
+
+    /// Returns the models to show in the list.
+    ///
+    /// With an allowlist configured (and "show all" off), these are the
+    /// allowlisted models that are installed, in allowlist order. If none of
+    /// them is installed, every model is shown instead of an empty list.
+    /// Models in `model_order` are moved to the front in that order.
+    fn visible_models(&self) -> Vec<String> {
+        let mut models = if self.show_all_models || self.model_allowlist.is_empty() {
+            self.all_models.clone()
+        } else {
+            let allowed: Vec<String> = self
+                .model_allowlist
+                .iter()
+                .filter(|m| self.all_models.contains(m))
+                .cloned()
+                .collect();
+            if allowed.is_empty() {
+                self.all_models.clone()
+            } else {
+                allowed
+            }
+        };
+        // Stable sort: models without a custom position keep their order.
+        models.sort_by_key(|m| {
+            self.model_order
+                .iter()
+                .position(|o| o == m)
+                .unwrap_or(usize::MAX)
+        });
+        models
+    }
+
+    /// Toggles between the allowlisted models and all installed models.
+    ///
+    /// The selected model stays selected if it is still visible; otherwise
+    /// the first model is selected.
+    pub fn toggle_show_all_models(&mut self) {
+        if self.model_allowlist.is_empty() {
+            self.status_message = Some("No model list configured".to_string());
+            return;
+        }
+        let current = self.list_state.selected().and_then(|i| self.models.get(i)).cloned();
+        self.save_current_model_buffers();
+        self.show_all_models = !self.show_all_models;
+        self.models = self.visible_models();
+        let index = current
+            .and_then(|name| self.models.iter().position(|m| *m == name))
+            .unwrap_or(0);
+        self.list_state.select((!self.models.is_empty()).then_some(index));
+        self.load_current_model_buffers();
+    }
+
+    /// Moves the selected model one place up in the list.
+    pub fn move_model_up(&mut self) {
+        self.move_selected_model(true);
+    }
+
+    /// Moves the selected model one place down in the list.
+    pub fn move_model_down(&mut self) {
+        self.move_selected_model(false);
+    }
+
+    /// Swaps the selected model with its neighbour and saves the new order,
+    /// unless the session is ephemeral.
+    ///
+    /// The selection follows the moved model. Moving the first model up or
+    /// the last one down does nothing.
+    fn move_selected_model(&mut self, up: bool) {
+        let Some(index) = self.list_state.selected() else {
+            return;
+        };
+        let target = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < self.models.len())
+        };
+        let Some(target) = target else {
+            return;
+        };
+        self.models.swap(index, target);
+        self.list_state.select(Some(target));
+
+        // Hidden models keep their custom position behind the visible ones.
+        let mut order = self.models.clone();
+        order.extend(
+            self.model_order
+                .iter()
+                .filter(|m| !self.models.contains(m))
+                .cloned(),
+        );
+        self.model_order = order;
+        if let Some(path) = self.config_path.as_ref().filter(|_| !self.ephemeral) {
+            if let Err(e) = config::save_model_order(path, &self.model_order) {
+                self.status_message = Some(format!("Could not save model order: {}", e));
             }
         }
     }
@@ -194,9 +992,12 @@ impl App {
     /// # Behavior
     ///
     /// - Retrieves the currently selected model from `list_state`
-    /// - Stores current `input` text in `model_inputs` HashMap
+    /// - Stores current `input` text in `model_inputs` HashMap (unless
+    ///   `shared_input` is set)
     /// - Stores current `history` string in `model_histories` HashMap
     /// - Stores current `scroll` position in `model_scrolls` HashMap
+    /// - Stores the collapsed exchanges in `model_collapsed` HashMap
+    /// - Stores the bookmark in `model_bookmarks` HashMap
     /// - Does nothing if no model is currently selected
     ///
     /// # Usage
@@ -208,14 +1009,36 @@ impl App {
     pub fn save_current_model_buffers(&mut self) {
         if let Some(index) = self.list_state.selected() {
             if let Some(model) = self.models.get(index) {
-                self.model_inputs.insert(model.clone(), self.input.clone());
-                self.model_cursors.insert(model.clone(), self.cursor_pos);
+                if !self.shared_input {
+                    self.model_inputs.insert(model.clone(), self.input.clone());
+                    self.model_cursors.insert(model.clone(), self.cursor_pos);
+                }
                 self.model_histories.insert(model.clone(), self.history.clone());
                 self.model_scrolls.insert(model.clone(), self.scroll);
+                self.model_collapsed.insert(model.clone(), self.collapsed.clone());
+                match self.bookmark {
+                    Some(line) => self.model_bookmarks.insert(model.clone(), line),
+                    None => self.model_bookmarks.remove(model),
+                };
             }
         }
     }
 
+    /// Whether the live input or history differs from what
+    /// [`App::save_current_model_buffers`] last stored for the selected
+    /// model.
+    ///
+    /// The model list marks such a model with `*`. With `shared_input` only
+    /// the history is compared, as the input is not stored per model.
+    pub fn has_unsaved_buffer(&self) -> bool {
+        let Some(model) = self.list_state.selected().and_then(|i| self.models.get(i)) else {
+            return false;
+        };
+        let stored_history = self.model_histories.get(model).map(String::as_str).unwrap_or("");
+        let stored_input = self.model_inputs.get(model).map(String::as_str).unwrap_or("");
+        self.history != stored_history || (!self.shared_input && self.input != stored_input)
+    }
+
     /// Loads the stored state for the currently selected model.
     ///
     /// This method restores the application state (input text, conversation history,
@@ -225,9 +1048,12 @@ impl App {
     /// # Behavior
     ///
     /// - Retrieves the currently selected model from `list_state`
-    /// - Loads stored `input` text from `model_inputs` HashMap (empty if not found)
+    /// - Loads stored `input` text from `model_inputs` HashMap (empty if not
+    ///   found); with `shared_input` the current input is kept
     /// - Loads stored `history` from `model_histories` HashMap (empty if not found)
     /// - Loads stored `scroll` position from `model_scrolls` HashMap (0 if not found)
+    /// - Loads the collapsed exchanges from `model_collapsed` and clears the focus
+    /// - Loads the bookmark from `model_bookmarks`
     /// - Updates current application state with the loaded values
     /// - Does nothing if no model is currently selected
     ///
@@ -240,88 +1066,388 @@ impl App {
     pub fn load_current_model_buffers(&mut self) {
         if let Some(index) = self.list_state.selected() {
             if let Some(model) = self.models.get(index) {
-                self.input = self.model_inputs.get(model).cloned().unwrap_or_default();
-                self.cursor_pos = *self.model_cursors.get(model).unwrap_or(&0);
+                if !self.shared_input {
+                    self.input = self.model_inputs.get(model).cloned().unwrap_or_default();
+                    self.cursor_pos = *self.model_cursors.get(model).unwrap_or(&0);
+                }
                 self.history = self.model_histories.get(model).cloned().unwrap_or_default();
                 self.scroll = *self.model_scrolls.get(model).unwrap_or(&0);
+                self.collapsed = self.model_collapsed.get(model).cloned().unwrap_or_default();
+                self.bookmark = self.model_bookmarks.get(model).copied();
+                self.focused_exchange = None;
+                self.previous_answer = None;
+                self.last_failed_prompt = None;
                 self.clamp_cursor();
             }
         }
     }
 
+    /// Replaces the conversation history of `model`, e.g. to set up a
+    /// conversation in tests or demos without sending prompts.
+    ///
+    /// If `model` is selected, the visible history is replaced too and the
+    /// folding state, which refers to the old exchanges, is reset.
+    pub fn set_model_history(&mut self, model: &str, history: String) {
+        let selected = self.list_state.selected().and_then(|i| self.models.get(i));
+        if selected.is_some_and(|m| m == model) {
+            self.history = history.clone();
+            self.collapsed.clear();
+            self.focused_exchange = None;
+            self.previous_answer = None;
+        }
+        self.model_collapsed.remove(model);
+        self.model_histories.insert(model.to_string(), history);
+    }
+
+    /// Appends a message to the selected model's conversation, in the
+    /// format `send_query` writes.
+    ///
+    /// A [`Role::User`] message starts an exchange and a
+    /// [`Role::Assistant`] message ends it with a turn separator, labeled
+    /// with the selected model. Without a selected model only the visible
+    /// history changes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::app::{App, Role};
+    ///
+    /// let mut app = App::builder().models(vec!["llama3".to_string()]).build();
+    /// app.append_message(Role::User, "Hi");
+    /// app.append_message(Role::Assistant, "Hello!");
+    /// assert_eq!(app.history, "\nYOU: Hi\n\nAI [llama3]: Hello!\n---\n");
+    /// assert_eq!(app.model_histories["llama3"], app.history);
+    /// ```
+    pub fn append_message(&mut self, role: Role, content: &str) {
+        let model = self.list_state.selected().and_then(|i| self.models.get(i)).cloned();
+        match role {
+            Role::User => self.history.push_str(&format!("\nYOU: {}\n", content)),
+            Role::Assistant => {
+                let label = model.as_deref().map_or("AI".to_string(), |m| format!("AI [{}]", m));
+                self.history.push_str(&format!("\n{}: {}\n{}\n", label, content, TURN_SEPARATOR));
+            }
+        }
+        if let Some(model) = model {
+            self.model_histories.insert(model, self.history.clone());
+        }
+    }
+
     /// Inserts a character at the current cursor position.
     ///
     /// This method performs a character-aware insertion (not byte-based),
     /// advances the cursor by one character, and resets the blink timer
     /// so the caret remains visible after input.
     pub fn insert_char(&mut self, c: char) {
+        let c = if c == '\r' { '\n' } else { c };
+        self.last_failed_prompt = None;
         let byte_idx = self.char_index_to_byte_index(self.cursor_pos);
         self.input.insert(byte_idx, c);
         self.cursor_pos = self.cursor_pos.saturating_add(1);
         self.reset_cursor_blink();
     }
 
-    /// Deletes the character immediately before the cursor.
+    /// Inserts a string at the current cursor position.
     ///
-    /// This is the standard Backspace behavior: it removes one character to
-    /// the left of the caret, shifts the cursor left by one, and resets the
-    /// blink timer.
-    pub fn backspace(&mut self) {
-        if self.cursor_pos == 0 {
-            return;
-        }
-        let remove_idx = self.cursor_pos - 1;
-        let byte_idx = self.char_index_to_byte_index(remove_idx);
-        self.input.remove(byte_idx);
-        self.cursor_pos = self.cursor_pos.saturating_sub(1);
+    /// Like [`App::insert_char`], but for a whole string: the cursor ends up
+    /// after the inserted text. Line endings are normalized with
+    /// [`utils::normalize_line_endings`].
+    pub fn insert_str(&mut self, text: &str) {
+        let text = &utils::normalize_line_endings(text);
+        self.last_failed_prompt = None;
+        let byte_idx = self.char_index_to_byte_index(self.cursor_pos);
+        self.input.insert_str(byte_idx, text);
+        self.cursor_pos = self.cursor_pos.saturating_add(text.chars().count());
         self.reset_cursor_blink();
     }
 
-    /// Deletes the word immediately before the cursor.
+    /// Inserts the current local time, formatted with `timestamp_format`,
+    /// at the cursor.
+    pub fn insert_timestamp(&mut self) {
+        self.insert_timestamp_at(Local::now());
+    }
+
+    /// Inserts `time`, formatted with `timestamp_format`, at the cursor.
     ///
-    /// Word boundaries use `is_word_char` rules, treating non-alphanumeric
-    /// characters (except underscore) as separators. Leading separators to
-    /// the left of the caret are removed along with the word.
-    pub fn delete_word_left(&mut self) {
-        if self.cursor_pos == 0 {
+    /// An invalid format inserts nothing and is reported through
+    /// `status_message` instead.
+    pub fn insert_timestamp_at(&mut self, time: DateTime<Local>) {
+        if !config::is_valid_timestamp_format(&self.timestamp_format) {
+            self.status_message =
+                Some(format!("Invalid timestamp_format {:?}", self.timestamp_format));
             return;
         }
-        let chars: Vec<char> = self.input.chars().collect();
-        let mut i = self.cursor_pos.min(chars.len());
+        let text = time.format(&self.timestamp_format).to_string();
+        self.insert_str(&text);
+    }
 
-        while i > 0 && !Self::is_word_char(chars[i - 1]) {
-            i -= 1;
+    /// Inserts an empty fenced code block tagged with `code_language` at
+    /// the cursor and puts the cursor on the empty line inside it.
+    ///
+    /// The fences get lines of their own: a line break is added before the
+    /// block if the cursor is not at the start of a line, and after it if
+    /// text follows the cursor on its line.
+    pub fn insert_code_block(&mut self) {
+        let byte_idx = self.char_index_to_byte_index(self.cursor_pos);
+        let before = &self.input[..byte_idx];
+        let after = &self.input[byte_idx..];
+        let lead = if before.is_empty() || before.ends_with('\n') { "" } else { "\n" };
+        let trail = if after.is_empty() || after.starts_with('\n') { "" } else { "\n" };
+        let opening = format!("{}```{}\n", lead, self.code_language);
+        let inner = self.cursor_pos + opening.chars().count();
+        self.insert_str(&format!("{}\n```{}", opening, trail));
+        self.cursor_pos = inner;
+    }
+
+    /// Appends the file at `path` to the input as a fenced code block,
+    /// headed by an [`ATTACHMENT_PREFIX`] line naming it.
+    ///
+    /// The fence language is inferred from the extension and a leading
+    /// `~/` refers to the home directory. Files over
+    /// [`utils::MAX_ATTACHMENT_BYTES`] are cut, with a warning in
+    /// `status_message`. Unreadable files only produce a status message and
+    /// `false` is returned.
+    pub fn attach_file(&mut self, path: &str) -> bool {
+        let expanded = expand_home(path);
+        let attachment = match utils::read_attachment(&expanded, utils::MAX_ATTACHMENT_BYTES) {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                self.status_message = Some(format!("Cannot attach {}: {}", path, e));
+                return false;
+            }
+        };
+        if !self.input.is_empty() && !self.input.ends_with('\n') {
+            self.input.push('\n');
         }
-        while i > 0 && Self::is_word_char(chars[i - 1]) {
-            i -= 1;
+        self.input.push_str(&format!(
+            "{}{}\n```{}\n{}\n```\n",
+            ATTACHMENT_PREFIX,
+            path,
+            utils::language_for_path(&expanded),
+            utils::normalize_line_endings(&attachment.content).trim_end_matches('\n'),
+        ));
+        self.cursor_pos = self.input.chars().count();
+        self.last_failed_prompt = None;
+        self.reset_cursor_blink();
+        if attachment.truncated {
+            self.status_message = Some(format!(
+                "{} is {}, attached only the first {}",
+                path,
+                format_size(attachment.size),
+                format_size(attachment.content.len() as u64)
+            ));
         }
+        true
+    }
 
-        if i != self.cursor_pos {
-            let start = self.char_index_to_byte_index(i);
-            let end = self.char_index_to_byte_index(self.cursor_pos);
-            self.input.replace_range(start..end, "");
-            self.cursor_pos = i;
-            self.reset_cursor_blink();
+    /// Runs the last answer through the shell command line `command` and
+    /// returns its output, for post-processing such as formatting code.
+    ///
+    /// The UI waits for the command, which is killed after
+    /// [`utils::PIPE_TIMEOUT`]. Returns `None` with an error in
+    /// `status_message` if there is no answer or the command fails.
+    pub fn pipe_last_answer(&mut self, command: &str) -> Option<String> {
+        let Some(answer) = last_answer(&self.history) else {
+            self.status_message = Some("No answer to pipe".to_string());
+            return None;
+        };
+        match utils::pipe_through(command, &answer, utils::PIPE_TIMEOUT) {
+            Ok(output) => {
+                self.status_message = Some(format!("Piped the last answer through {}", command));
+                Some(output)
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Pipe failed: {}", e));
+                None
+            }
         }
     }
 
-    /// Deletes the character at the cursor position.
+    /// Scrolls to turn `number`, counted from 1 as with `number_turns`,
+    /// and highlights it.
     ///
-    /// This is the standard Delete behavior: it removes the character under
-    /// the caret (to the right), leaving the cursor position unchanged.
-    pub fn delete_forward(&mut self) {
-        let len = self.input.chars().count();
-        if self.cursor_pos >= len {
-            return;
+    /// The scroll position is resolved by the next frame, which knows where
+    /// the exchange is rendered, and autoscroll is turned off. Returns
+    /// `false` with an error in `status_message` if there is no such turn.
+    pub fn goto_turn(&mut self, number: usize) -> bool {
+        let count = split_exchanges(&self.history).len();
+        if count == 0 {
+            self.status_message = Some("No turns to jump to".to_string());
+            return false;
         }
-        let byte_idx = self.char_index_to_byte_index(self.cursor_pos);
-        self.input.remove(byte_idx);
-        self.reset_cursor_blink();
+        if number == 0 || number > count {
+            self.status_message = Some(format!("No turn {}, turns are 1 to {}", number, count));
+            return false;
+        }
+        self.focused_exchange = Some(number - 1);
+        self.scroll_to_focus = true;
+        true
     }
 
-    /// Deletes the word immediately after the cursor.
+    /// Returns the input command typed on the last line of the input, if
+    /// any (see [`App::run_input_command`]).
+    fn input_command(&self) -> Option<&'static str> {
+        let line_start = self.input.rfind('\n').map_or(0, |i| i + 1);
+        let line = self.input[line_start..].trim();
+        [CLEAR_COMMAND, FILE_COMMAND, GOTO_COMMAND, PIPE_COMMAND]
+            .into_iter()
+            .find(|c| command_argument(line, c).is_some())
+    }
+
+    /// Runs a command typed on the last line of the input instead of
+    /// sending it, returning whether there was one.
     ///
-    /// Word boundaries use `is_word_char` rules, treating non-alphanumeric
+    /// `/file <path>` replaces its line with the file's contents (see
+    /// [`App::attach_file`]); the line is kept if the file cannot be read,
+    /// so the path can be corrected. `/goto <n>` removes its line and
+    /// scrolls to turn `n` (see [`App::goto_turn`]), keeping the line if
+    /// there is no such turn. `/pipe <command>` replaces its line with the
+    /// last answer run through `command` (see [`App::pipe_last_answer`]),
+    /// keeping the line if the command fails. `/clear` removes its line and
+    /// clears the conversation.
+    ///
+    /// Commands listed in `confirm_commands` are only held in
+    /// `pending_command` the first time; sending the same line again right
+    /// away runs them.
+    fn run_input_command(&mut self) -> bool {
+        let line_start = self.input.rfind('\n').map_or(0, |i| i + 1);
+        let line = self.input[line_start..].trim();
+        // A command in `confirm_commands` runs only if it is sent again
+        // right after the first time.
+        let pending = self.pending_command.take();
+        if let Some(command) = self.input_command() {
+            if self.confirm_commands.iter().any(|c| c == command) && pending.as_deref() != Some(line) {
+                self.status_message = Some(format!("Send {} again to confirm", command));
+                self.pending_command = Some(line.to_string());
+                return true;
+            }
+        }
+        if command_argument(line, CLEAR_COMMAND).is_some() {
+            self.input.truncate(line_start);
+            self.cursor_pos = self.input.chars().count();
+            self.clear_conversation();
+            self.status_message = Some("Conversation cleared".to_string());
+            return true;
+        }
+        if let Some(command) = command_argument(line, PIPE_COMMAND) {
+            if command.is_empty() {
+                self.status_message = Some(format!("Usage: {} <command>", PIPE_COMMAND));
+                return true;
+            }
+            let command = command.to_string();
+            if let Some(output) = self.pipe_last_answer(&command) {
+                self.input.truncate(line_start);
+                self.input.push_str(&output);
+                self.cursor_pos = self.input.chars().count();
+                self.reset_cursor_blink();
+            }
+            return true;
+        }
+        if let Some(argument) = command_argument(line, GOTO_COMMAND) {
+            match argument.parse::<usize>() {
+                Ok(number) => {
+                    if self.goto_turn(number) {
+                        self.input.truncate(line_start);
+                        self.cursor_pos = self.input.chars().count();
+                    }
+                }
+                Err(_) => self.status_message = Some(format!("Usage: {} <turn number>", GOTO_COMMAND)),
+            }
+            return true;
+        }
+        let Some(argument) = command_argument(line, FILE_COMMAND) else {
+            return false;
+        };
+        let path = argument.to_string();
+        if path.is_empty() {
+            self.status_message = Some(format!("Usage: {} <path>", FILE_COMMAND));
+            return true;
+        }
+        let line = self.input.split_off(line_start);
+        self.cursor_pos = self.input.chars().count();
+        if !self.attach_file(&path) {
+            self.input.push_str(&line);
+            self.cursor_pos = self.input.chars().count();
+        }
+        true
+    }
+
+    /// Inserts the snippet at `index` (0 for `Alt+1`) at the cursor.
+    ///
+    /// Reports through `status_message` if no snippet is configured for
+    /// that slot.
+    pub fn insert_snippet(&mut self, index: usize) {
+        match self.snippets.get(index).filter(|_| index < MAX_SNIPPETS) {
+            Some(snippet) => {
+                let text = snippet.text.clone();
+                self.insert_str(&text);
+            }
+            None => {
+                self.status_message = Some(format!("No snippet on Alt+{}", index + 1));
+            }
+        }
+    }
+
+    /// Deletes the character immediately before the cursor.
+    ///
+    /// This is the standard Backspace behavior: it removes one character to
+    /// the left of the caret, shifts the cursor left by one, and resets the
+    /// blink timer.
+    pub fn backspace(&mut self) {
+        if self.cursor_pos == 0 {
+            return;
+        }
+        let remove_idx = self.cursor_pos - 1;
+        let byte_idx = self.char_index_to_byte_index(remove_idx);
+        self.input.remove(byte_idx);
+        self.cursor_pos = self.cursor_pos.saturating_sub(1);
+        self.reset_cursor_blink();
+    }
+
+    /// Deletes the word immediately before the cursor.
+    ///
+    /// Word boundaries use `is_word_char` rules, treating non-alphanumeric
+    /// characters (except underscore) as separators. Leading separators to
+    /// the left of the caret are removed along with the word.
+    pub fn delete_word_left(&mut self) {
+        if self.cursor_pos == 0 {
+            return;
+        }
+        let chars: Vec<char> = self.input.chars().collect();
+        let mut i = self.cursor_pos.min(chars.len());
+
+        while i > 0 && !Self::is_word_char(chars[i - 1]) {
+            i -= 1;
+        }
+        while i > 0 && Self::is_word_char(chars[i - 1]) {
+            i -= 1;
+        }
+
+        if i != self.cursor_pos {
+            let start = self.char_index_to_byte_index(i);
+            let end = self.char_index_to_byte_index(self.cursor_pos);
+            self.input.replace_range(start..end, "");
+            self.cursor_pos = i;
+            self.reset_cursor_blink();
+        }
+    }
+
+    /// Deletes the character at the cursor position.
+    ///
+    /// This is the standard Delete behavior: it removes the character under
+    /// the caret (to the right), leaving the cursor position unchanged.
+    pub fn delete_forward(&mut self) {
+        let len = self.input.chars().count();
+        if self.cursor_pos >= len {
+            return;
+        }
+        let byte_idx = self.char_index_to_byte_index(self.cursor_pos);
+        self.input.remove(byte_idx);
+        self.reset_cursor_blink();
+    }
+
+    /// Deletes the word immediately after the cursor.
+    ///
+    /// Word boundaries use `is_word_char` rules, treating non-alphanumeric
     /// characters (except underscore) as separators. Leading separators to
     /// the right of the caret are removed along with the word.
     pub fn delete_word_right(&mut self) {
@@ -370,29 +1496,57 @@ impl App {
         }
     }
 
-    /// Moves the cursor to the start of the input.
+    /// Moves the cursor towards the start of the input.
     ///
-    /// This is the Home key behavior. Resets the blink timer if the cursor
-    /// position changes.
+    /// This is the Home key behavior. Each press goes one step further: to
+    /// the start of the line, then to the start of the input. Resets the
+    /// blink timer if the cursor position changes.
     pub fn move_cursor_home(&mut self) {
-        if self.cursor_pos != 0 {
-            self.cursor_pos = 0;
+        let (line_start, _) = self.cursor_line_bounds();
+        let target = [line_start, 0]
+            .into_iter()
+            .find(|&pos| pos < self.cursor_pos)
+            .unwrap_or(0);
+        if self.cursor_pos != target {
+            self.cursor_pos = target;
             self.reset_cursor_blink();
         }
     }
 
-    /// Moves the cursor to the end of the input.
+    /// Moves the cursor towards the end of the input.
     ///
-    /// This is the End key behavior. Resets the blink timer if the cursor
-    /// position changes.
+    /// This is the End key behavior, mirroring [`App::move_cursor_home`]:
+    /// first to the end of the line, then to the end of the input. Resets
+    /// the blink timer if the cursor position changes.
     pub fn move_cursor_end(&mut self) {
         let len = self.input.chars().count();
-        if self.cursor_pos != len {
-            self.cursor_pos = len;
+        let (_, line_end) = self.cursor_line_bounds();
+        let target = [line_end, len]
+            .into_iter()
+            .find(|&pos| pos > self.cursor_pos)
+            .unwrap_or(len);
+        if self.cursor_pos != target {
+            self.cursor_pos = target;
             self.reset_cursor_blink();
         }
     }
 
+    /// Char positions of the start and end of the input line the cursor is
+    /// on.
+    fn cursor_line_bounds(&self) -> (usize, usize) {
+        let chars: Vec<char> = self.input.chars().collect();
+        let cursor = self.cursor_pos.min(chars.len());
+        let start = chars[..cursor]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |i| i + 1);
+        let end = chars[cursor..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(chars.len(), |i| cursor + i);
+        (start, end)
+    }
+
     /// Moves the cursor one word to the left.
     ///
     /// Word boundaries use `is_word_char` rules, treating non-alphanumeric
@@ -441,12 +1595,55 @@ impl App {
         }
     }
 
+    /// Applies a cursor motion or deletion key to the input, returning
+    /// whether `key` was one.
+    ///
+    /// Handles `Left`/`Right` (with `Ctrl` by word), `Home`/`End`,
+    /// `Backspace`/`Delete` (with `Ctrl` by word) and `Ctrl+H`, which Kitty
+    /// sends for `Ctrl+Backspace`. Typed characters are left to the caller,
+    /// since plain letters can be shortcuts too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    /// use lazyllama::app::App;
+    ///
+    /// let mut app = App::builder().build();
+    /// app.input = "Hello World".to_string();
+    /// app.cursor_pos = 11;
+    /// assert!(app.handle_editing_key(KeyEvent::new(KeyCode::Left, KeyModifiers::CONTROL)));
+    /// assert_eq!(app.cursor_pos, 6);
+    /// ```
+    pub fn handle_editing_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match (key.code, ctrl) {
+            (KeyCode::Left, true) => self.move_cursor_word_left(),
+            (KeyCode::Right, true) => self.move_cursor_word_right(),
+            (KeyCode::Left, false) => self.move_cursor_left(),
+            (KeyCode::Right, false) => self.move_cursor_right(),
+            (KeyCode::Home, _) => self.move_cursor_home(),
+            (KeyCode::End, _) => self.move_cursor_end(),
+            (KeyCode::Backspace, true) | (KeyCode::Char('h'), true) => self.delete_word_left(),
+            (KeyCode::Backspace, false) => self.backspace(),
+            (KeyCode::Delete, true) => self.delete_word_right(),
+            (KeyCode::Delete, false) => self.delete_forward(),
+            _ => return false,
+        }
+        true
+    }
+
     /// Toggles cursor blink state when enough time has elapsed.
     ///
     /// Returns `true` when a toggle occurs so the caller can trigger a
-    /// redraw; otherwise returns `false` to avoid unnecessary updates.
+    /// redraw; otherwise returns `false` to avoid unnecessary updates. With
+    /// `quiet` the cursor stays visible and never toggles.
     pub fn update_cursor_blink(&mut self) -> bool {
-        if self.last_cursor_blink.elapsed().as_millis() >= 500 {
+        if self.quiet {
+            self.cursor_visible = true;
+            return false;
+        }
+        if self.last_cursor_blink.elapsed() >= CURSOR_BLINK_INTERVAL {
             self.cursor_visible = !self.cursor_visible;
             self.last_cursor_blink = Instant::now();
             return true;
@@ -454,6 +1651,54 @@ impl App {
         false
     }
 
+    /// Advances the status bar clock and session duration when a new second
+    /// has started.
+    ///
+    /// Returns `true` when they changed so the caller can redraw; with
+    /// `status_clock` and `status_session` off it never does, so an idle UI
+    /// stays static.
+    pub fn update_status_clock(&mut self) -> bool {
+        if !self.status_clock && !self.status_session {
+            return false;
+        }
+        let second = Local::now().timestamp();
+        if second == self.clock_second {
+            return false;
+        }
+        self.clock_second = second;
+        true
+    }
+
+    /// Returns the timeout the event loop should pass to `event::poll`.
+    ///
+    /// In [`PollMode::Fixed`] mode this is the configured interval. In
+    /// [`PollMode::Adaptive`] mode the loop polls every
+    /// [`ACTIVE_POLL_INTERVAL`] while a response is streaming (keeping the
+    /// spinner smooth) and otherwise sleeps until the next cursor blink or,
+    /// with `status_clock` or `status_session`, the next clock tick is due,
+    /// never longer than [`IDLE_POLL_INTERVAL`]. With `quiet` there is no spinner or blink to
+    /// wait for.
+    pub fn poll_interval(&self) -> Duration {
+        match self.poll_mode {
+            PollMode::Fixed(interval) => interval,
+            PollMode::Adaptive if self.is_loading && !self.quiet => ACTIVE_POLL_INTERVAL,
+            PollMode::Adaptive => {
+                let blink = if self.quiet {
+                    IDLE_POLL_INTERVAL
+                } else {
+                    CURSOR_BLINK_INTERVAL.saturating_sub(self.last_cursor_blink.elapsed())
+                };
+                let tick = if self.status_clock || self.status_session {
+                    let elapsed = Local::now().timestamp_subsec_millis().min(999);
+                    Duration::from_millis(1000 - u64::from(elapsed))
+                } else {
+                    IDLE_POLL_INTERVAL
+                };
+                blink.min(tick).min(IDLE_POLL_INTERVAL)
+            }
+        }
+    }
+
     pub fn reset_cursor_blink(&mut self) {
         self.cursor_visible = true;
         self.last_cursor_blink = Instant::now();
@@ -478,163 +1723,2066 @@ impl App {
         c.is_alphanumeric() || c == '_'
     }
 
-    /// Switches to the next model in the list (Down arrow key behavior).
-    ///
-    /// This method implements circular navigation through the model list,
-    /// wrapping from the last model back to the first. It preserves the
-    /// current model's state before switching and loads the target model's
-    /// stored state.
-    ///
-    /// # Behavior
-    ///
-    /// 1. Saves current model's state via `save_current_model_buffers()`
-    /// 2. Calculates next index with wraparound (last → first)
-    /// 3. Updates `list_state` selection to new index
-    /// 4. Loads the new model's state via `load_current_model_buffers()`
+    /// Discards the draft in the input field and leaves the conversation
+    /// alone.
     ///
-    /// # Model Selection Logic
+    /// This is the Ctrl+L counterpart to [`App::clear_conversation`].
+    pub fn clear_input(&mut self) {
+        self.input.clear();
+        self.cursor_pos = 0;
+        self.save_current_model_buffers();
+        self.reset_cursor_blink();
+    }
+
+    /// Discards the active model's conversation.
     ///
-    /// - If at last model: wraps to index 0 (first model)
-    /// - Otherwise: increments to next index
-    /// - If no model selected: selects index 0
-    /// - Handles empty model list gracefully
-    pub fn select_next_model(&mut self) {
-        if self.models.is_empty() {
-            return;
+    /// This is the destructive Ctrl+C clear; see [`App::new_conversation`]
+    /// for a variant that keeps a copy.
+    pub fn clear_conversation(&mut self) {
+        // Lösche nur den aktuellen Modell-Buffer
+        self.history.clear();
+        self.collapsed.clear();
+        self.bookmark = None;
+        self.focused_exchange = None;
+        self.previous_answer = None;
+        self.last_failed_prompt = None;
+        self.scroll = 0;
+        self.autoscroll = true;
+        self.save_current_model_buffers();
+    }
+
+    /// Bookmarks the current scroll position, or removes the bookmark if it
+    /// is already at this position.
+    pub fn toggle_bookmark(&mut self) {
+        if self.bookmark == Some(self.scroll) {
+            self.bookmark = None;
+            self.status_message = Some("Bookmark removed".to_string());
+        } else {
+            self.bookmark = Some(self.scroll);
+            self.status_message = Some("Bookmark set, Ctrl+G jumps back".to_string());
         }
-        
         self.save_current_model_buffers();
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i >= self.models.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
+    }
+
+    /// Scrolls to the bookmark and disables autoscroll.
+    pub fn jump_to_bookmark(&mut self) {
+        match self.bookmark {
+            Some(line) => {
+                self.autoscroll = false;
+                self.scroll = line;
             }
-            None => 0,
-        };
-        self.list_state.select(Some(i));
-        self.load_current_model_buffers();
+            None => self.status_message = Some("No bookmark set (Ctrl+B)".to_string()),
+        }
     }
 
-    /// Switches to the previous model in the list (Up arrow key behavior).
+    /// Archives the active model's conversation to a file, then clears it.
     ///
-    /// This method implements circular navigation through the model list,
-    /// wrapping from the first model back to the last. It preserves the
-    /// current model's state before switching and loads the target model's
-    /// stored state.
-    ///
-    /// # Behavior
-    ///
-    /// 1. Saves current model's state via `save_current_model_buffers()`
-    /// 2. Calculates previous index with wraparound (first → last)
-    /// 3. Updates `list_state` selection to new index
-    /// 4. Loads the new model's state via `load_current_model_buffers()`
-    ///
-    /// # Model Selection Logic
-    ///
-    /// - If at first model (index 0): wraps to last model
-    /// - Otherwise: decrements to previous index
-    /// - If no model selected: selects index 0
-    /// - Handles empty model list gracefully
-    pub fn select_previous_model(&mut self) {
-        if self.models.is_empty() {
+    /// The conversation is only cleared if it was saved; the outcome is
+    /// reported through `status_message`. In an ephemeral session it is
+    /// cleared without saving.
+    pub fn new_conversation(&mut self) {
+        if self.history.is_empty() {
+            self.status_message = Some("Conversation is already empty".to_string());
             return;
         }
-        
-        self.save_current_model_buffers();
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.models.len() - 1
-                } else {
-                    i - 1
-                }
+        if self.ephemeral {
+            self.clear_conversation();
+            self.status_message = Some("Conversation cleared (not saved)".to_string());
+            return;
+        }
+        let model = self
+            .list_state
+            .selected()
+            .and_then(|i| self.models.get(i))
+            .cloned()
+            .unwrap_or_else(|| "conversation".to_string());
+        let dir = self.log_dir();
+        let history = strip_request_debug(&self.history);
+        match dir.and_then(|dir| utils::archive_conversation_in(&dir, &model, &history)) {
+            Ok(_) => {
+                self.clear_conversation();
+                self.status_message = Some("Previous conversation saved".to_string());
             }
-            None => 0,
-        };
-        self.list_state.select(Some(i));
-        self.load_current_model_buffers();
+            Err(e) => {
+                self.status_message = Some(format!("Could not save conversation: {}", e));
+            }
+        }
     }
 
-    /// Sends the current input to the selected model and streams the response.
-    ///
-    /// This method handles the complete query lifecycle including prompt formatting,
-    /// API communication, real-time response streaming, and UI updates. The response
-    /// is written directly to `self.history` as tokens are received, providing
-    /// immediate visual feedback to the user.
-    ///
-    /// # Arguments
-    ///
-    /// * `terminal` - Mutable reference to the terminal backend for real-time UI updates
-    ///
-    /// # Returns
-    ///
-    /// Returns `Ok(())` on successful completion or an `anyhow::Error` if the API
-    /// request fails, streaming encounters an error, or terminal drawing fails.
-    ///
-    /// # Behavior
-    ///
-    /// 1. **Validation**: Ensures a model is selected before proceeding
-    /// 2. **Formatting**: Adds user prompt to conversation history with "YOU:" label
-    /// 3. **State Management**: Clears input field and saves current buffers
-    /// 4. **UI Updates**: Sets loading state and enables autoscroll
-    /// 5. **Streaming**: Sends request to Ollama and processes response tokens
-    /// 6. **Real-time Display**: Updates terminal display for each received token
-    /// 7. **Completion**: Adds separator and saves final state
-    ///
-    /// # Error Handling
-    ///
-    /// - Gracefully handles API connection errors
-    /// - Continues processing partial responses if streaming is interrupted
-    /// - Ensures loading state is cleared even on errors
-    /// - Preserves conversation history even if request fails
-    ///
-    /// # Side Effects
-    ///
-    /// - Modifies `self.history` with formatted conversation
-    /// - Clears `self.input` field
-    /// - Updates `self.is_loading` state
-    /// - Triggers terminal redraws for real-time display
-    /// - Saves state to model-specific buffers
-    pub async fn send_query(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    ) -> Result<()> {
-        if let Some(i) = self.list_state.selected() {
-            let model = self.models[i].clone();
-            let prompt = self.input.clone();
-
-            self.history.push_str(&format!("\nYOU: {}\n\nAI: ", prompt));
-            self.input.clear();
-            self.cursor_pos = 0;
-            
-            // Speichere die aktualisierten Buffer für das aktuelle Modell
-            self.save_current_model_buffers();
-            
-            self.is_loading = true;
-            self.autoscroll = true;
+    /// Returns the directory conversations are saved to.
+    fn log_dir(&self) -> Result<PathBuf> {
+        match &self.data_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => utils::data_dir(),
+        }
+    }
 
-            let request = GenerationRequest::new(model.clone(), prompt);
-            let mut stream = self.ollama.generate_stream(request).await?;
+    /// Reads the saved logs per model from the data directory.
+    fn load_log_groups(&self) -> Vec<LogGroup> {
+        let grouped = match self.log_dir() {
+            Ok(dir) => utils::list_logs_grouped_in(&dir),
+            Err(_) => HashMap::new(),
+        };
+        let mut groups: Vec<LogGroup> = grouped
+            .into_iter()
+            .map(|(model, files)| LogGroup {
+                model,
+                files: files.len(),
+                bytes: files.iter().map(|f| f.size).sum(),
+            })
+            .collect();
+        groups.sort_by(|a, b| a.model.cmp(&b.model));
+        groups
+    }
 
-            while let Some(res) = stream.next().await {
-                if let Ok(responses) = res {
-                    for resp in responses {
-                        self.history.push_str(&resp.response);
+    /// Starts reloading the last saved conversation of every installed model
+    /// in the background.
+    ///
+    /// The files are read on a blocking thread so the UI stays interactive;
+    /// [`App::poll_history_reload`] moves the results into
+    /// `model_histories`. Must be called from within a Tokio runtime.
+    pub fn start_history_reload(&mut self) {
+        let Ok(dir) = self.log_dir() else {
+            return;
+        };
+        // Files of a configured host carry its name, see
+        // `all_model_histories`.
+        let prefix = self
+            .active_host_name()
+            .map(|host| format!("{}/", host))
+            .unwrap_or_default();
+        let models: Vec<(String, String)> = self
+            .all_models
+            .iter()
+            .map(|model| (model.clone(), utils::safe_model_name(&format!("{}{}", prefix, model))))
+            .collect();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            let mut groups = utils::list_logs_grouped_in(&dir);
+            for (model, key) in models {
+                let Some(latest) = groups.remove(&key).and_then(|mut files| files.pop()) else {
+                    continue;
+                };
+                if let Ok(log) = std::fs::read_to_string(&latest.path) {
+                    if sender.send((model, utils::history_from_log(&latest.path, &log))).is_err() {
+                        break;
                     }
-                    terminal.draw(|f| crate::ui::ui(f, self))?;
                 }
             }
-            self.history.push_str("\n---\n");
-            self.is_loading = false;
-            
-            // Speichere die finale History für dieses Modell
-            self.save_current_model_buffers();
-        }
-        Ok(())
+        });
+        self.history_reload = Some(receiver);
+        self.histories_loading = true;
     }
-}
-
 
+    /// Applies the conversations read by the background reload so far.
+    ///
+    /// A reloaded conversation never replaces one that was started in the
+    /// meantime. Returns whether anything changed, so the caller knows to
+    /// redraw.
+    pub fn poll_history_reload(&mut self) -> bool {
+        let Some(receiver) = &mut self.history_reload else {
+            return false;
+        };
+        let mut reloaded = Vec::new();
+        let finished = loop {
+            match receiver.try_recv() {
+                Ok(entry) => reloaded.push(entry),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        let changed = finished || !reloaded.is_empty();
+        if finished {
+            self.history_reload = None;
+            self.histories_loading = false;
+        }
+        let selected = self.list_state.selected().and_then(|i| self.models.get(i)).cloned();
+        for (model, history) in reloaded {
+            if selected.as_ref() == Some(&model) {
+                if self.history.is_empty() {
+                    self.history = history;
+                    self.save_current_model_buffers();
+                }
+            } else {
+                let entry = self.model_histories.entry(model).or_default();
+                if entry.is_empty() {
+                    *entry = history;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Opens the log manager listing the saved logs of every model.
+    pub fn open_log_manager(&mut self) {
+        self.log_manager = Some(LogManager {
+            groups: self.load_log_groups(),
+            ..LogManager::default()
+        });
+    }
+
+    /// Closes the log manager.
+    pub fn close_log_manager(&mut self) {
+        self.log_manager = None;
+    }
+
+    /// Moves the log manager highlight one entry down, wrapping around.
+    pub fn log_manager_next(&mut self) {
+        if let Some(manager) = &mut self.log_manager {
+            manager.selected = (manager.selected + 1) % manager.groups.len().max(1);
+            manager.confirm_delete = false;
+        }
+    }
+
+    /// Moves the log manager highlight one entry up, wrapping around.
+    pub fn log_manager_previous(&mut self) {
+        if let Some(manager) = &mut self.log_manager {
+            let len = manager.groups.len().max(1);
+            manager.selected = (manager.selected + len - 1) % len;
+            manager.confirm_delete = false;
+        }
+    }
+
+    /// Asks for confirmation to delete the highlighted model's logs.
+    pub fn request_log_deletion(&mut self) {
+        if let Some(manager) = &mut self.log_manager {
+            manager.confirm_delete = !manager.groups.is_empty();
+        }
+    }
+
+    /// Answers the deletion question: deletes the highlighted model's logs
+    /// if `confirmed`, and refreshes the list.
+    pub fn confirm_log_deletion(&mut self, confirmed: bool) {
+        let Some(manager) = &mut self.log_manager else {
+            return;
+        };
+        manager.confirm_delete = false;
+        if !confirmed {
+            return;
+        }
+        let Some(model) = manager.groups.get(manager.selected).map(|g| g.model.clone()) else {
+            return;
+        };
+        let result = self
+            .log_dir()
+            .and_then(|dir| utils::delete_logs_for_model_in(&dir, &model));
+        self.status_message = Some(match result {
+            Ok(n) => format!("Deleted {} log{} of {}", n, if n == 1 { "" } else { "s" }, model),
+            Err(e) => format!("Could not delete logs of {}: {}", model, e),
+        });
+        let groups = self.load_log_groups();
+        if let Some(manager) = &mut self.log_manager {
+            manager.selected = manager.selected.min(groups.len().saturating_sub(1));
+            manager.groups = groups;
+        }
+    }
+
+    /// Returns the details of `model`, from the cache if they were fetched
+    /// before.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LazyLlamaError::ModelNotFound`] or
+    /// [`LazyLlamaError::Connection`] if the server cannot provide them.
+    pub async fn fetch_model_info(&self, model: &str) -> Result<ModelInfo> {
+        if let Some(info) = self.model_info_cache.get(model) {
+            return Ok(info.clone());
+        }
+        self.client
+            .model_info(model)
+            .await
+            .map_err(|e| LazyLlamaError::from_client(model, e))
+    }
+
+    /// Opens the info popup for the selected model, fetching its details
+    /// unless they are cached. A failed fetch is shown in the popup and
+    /// tried again next time.
+    pub async fn open_model_info(&mut self) {
+        let Some(model) = self.list_state.selected().and_then(|i| self.models.get(i)).cloned()
+        else {
+            return;
+        };
+        let info = self.fetch_model_info(&model).await.map_err(|e| e.to_string());
+        if let Ok(info) = &info {
+            self.model_info_cache.insert(model.clone(), info.clone());
+        }
+        self.model_info_popup = Some(ModelInfoPopup { model, info, scroll: 0 });
+    }
+
+    /// Closes the model info popup.
+    pub fn close_model_info(&mut self) {
+        self.model_info_popup = None;
+    }
+
+    /// Scrolls the model info popup by `lines`, up if negative.
+    pub fn scroll_model_info(&mut self, lines: i16) {
+        if let Some(popup) = &mut self.model_info_popup {
+            popup.scroll = popup.scroll.saturating_add_signed(lines);
+        }
+    }
+
+    /// Opens the directory conversations are saved to in the file manager,
+    /// reporting failures in the status bar.
+    pub fn open_data_dir(&mut self) {
+        let result = self.log_dir().and_then(|dir| {
+            utils::open_in_file_manager(&dir)?;
+            Ok(dir)
+        });
+        self.status_message = Some(match result {
+            Ok(dir) => format!("Opened {}", dir.display()),
+            Err(e) => format!("Could not open the data directory: {}", e),
+        });
+    }
+
+    /// Copies the active model's conversation to the clipboard as clean Markdown.
+    ///
+    /// Works on the raw `history` rather than the rendered text, so no
+    /// frames, gutters or bullets end up on the clipboard. Reports the
+    /// outcome through `status_message`.
+    pub fn copy_conversation(&mut self) {
+        if self.history.trim().is_empty() {
+            self.status_message = Some("Nothing to copy".to_string());
+            return;
+        }
+        let text = clipboard::clean_markdown(&strip_request_debug(&self.history));
+        self.copy_with_feedback("conversation", &text);
+    }
+
+    /// Copies the most recent code block of the conversation to the clipboard.
+    ///
+    /// Only the code between the fences is copied. Reports the outcome
+    /// through `status_message`.
+    pub fn copy_last_code_block(&mut self) {
+        match extract_code_blocks(&self.history).last() {
+            Some(block) => {
+                let text = clipboard::clean_code(&block.code);
+                self.copy_with_feedback("code block", &text);
+            }
+            None => self.status_message = Some("No code block to copy".to_string()),
+        }
+    }
+
+    /// Writes the code blocks of the focused exchange, or of the last
+    /// answer, to files in `export_dir` (see [`utils::export_code_blocks`])
+    /// and reports how many were written.
+    pub fn export_code_blocks(&mut self) {
+        let source = match self.focused_exchange {
+            Some(i) => split_exchanges(&self.history)
+                .get(i)
+                .and_then(|exchange| exchange_parts(exchange))
+                .map(|(_, answer)| answer.to_string()),
+            None => last_answer(&self.history),
+        };
+        let Some(source) = source.filter(|s| !extract_code_blocks(s).is_empty()) else {
+            self.status_message = Some("No code blocks to export".to_string());
+            return;
+        };
+        self.status_message = Some(match utils::export_code_blocks(&source, &self.export_dir) {
+            Ok(paths) => format!(
+                "Exported {} code block{} to {}",
+                paths.len(),
+                if paths.len() == 1 { "" } else { "s" },
+                self.export_dir.display()
+            ),
+            Err(e) => format!("Could not export code blocks: {}", e),
+        });
+    }
+
+    /// Appends the most recent AI answer, preceded by `yank_lead_in`, to
+    /// the input, so it can be edited and sent as the next prompt.
+    ///
+    /// The cursor ends up after the answer. Reports through
+    /// `status_message` if there is no answer yet.
+    pub fn yank_last_answer(&mut self) {
+        match last_answer(&self.history) {
+            Some(answer) => {
+                self.cursor_pos = self.input.chars().count();
+                let text = format!("{}{}", self.yank_lead_in, answer);
+                self.insert_str(&text);
+            }
+            None => self.status_message = Some("No answer to yank".to_string()),
+        }
+    }
+
+    /// Copies the most recent AI answer, without its label, to the clipboard.
+    ///
+    /// Reports the outcome through `status_message`.
+    pub fn copy_last_answer(&mut self) {
+        match last_answer(&self.history) {
+            Some(answer) => {
+                let text = clipboard::clean_markdown(&answer);
+                self.copy_with_feedback("last answer", &text);
+            }
+            None => self.status_message = Some("No answer to copy".to_string()),
+        }
+    }
+
+    /// Copies a `curl` command that repeats the last request against the
+    /// active host to the clipboard (see [`GenerateRequest::curl_command`]).
+    ///
+    /// Reports the outcome through `status_message`.
+    pub fn copy_request_as_curl(&mut self) {
+        let Some(request) = &self.last_request else {
+            self.status_message = Some("No request to copy".to_string());
+            return;
+        };
+        let host = self
+            .hosts
+            .get(self.active_host)
+            .map_or(DEFAULT_HOST_URL, |h| h.url.as_str());
+        let command = request.curl_command(host);
+        self.copy_with_feedback("request as curl", &command);
+    }
+
+    /// Copies the answer of the exchange at `index`, without its label and
+    /// timing footer, to the clipboard.
+    ///
+    /// Reports the outcome through `status_message`.
+    pub fn copy_exchange_answer(&mut self, index: usize) {
+        let answer = split_exchanges(&self.history)
+            .get(index)
+            .and_then(|exchange| exchange_parts(exchange))
+            .map(|(_, answer)| clipboard::clean_markdown(answer));
+        match answer.filter(|a| !a.is_empty()) {
+            Some(text) => self.copy_with_feedback("answer", &text),
+            None => self.status_message = Some("No answer to copy".to_string()),
+        }
+    }
+
+    /// Handles a left click at the screen cell `column`, `row`: a click on
+    /// a copy button copies its answer.
+    ///
+    /// Returns whether the click hit something, so the caller knows to
+    /// redraw.
+    pub fn click(&mut self, column: u16, row: u16) -> bool {
+        let hit = self
+            .copy_regions
+            .iter()
+            .find(|(rect, _)| rect.contains(Position::new(column, row)))
+            .map(|&(_, index)| index);
+        match hit {
+            Some(index) => {
+                self.copy_exchange_answer(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn copy_with_feedback(&mut self, what: &str, text: &str) {
+        self.status_message = Some(match clipboard::copy_to_clipboard(text) {
+            Ok(()) => format!("Copied {} to clipboard", what),
+            Err(e) => format!("Copy failed: {}", e),
+        });
+    }
+
+    /// Opens the model filter, listing all models until something is typed.
+    pub fn open_model_filter(&mut self) {
+        if self.models.is_empty() {
+            self.status_message = Some("No models to filter".to_string());
+            return;
+        }
+        self.model_filter = Some(ModelFilter {
+            filtered_indices: (0..self.models.len()).collect(),
+            selected: self.list_state.selected().unwrap_or(0),
+            ..ModelFilter::default()
+        });
+    }
+
+    /// Adds `c` to the filter query and highlights the best match.
+    pub fn model_filter_insert(&mut self, c: char) {
+        if let Some(filter) = &mut self.model_filter {
+            filter.query.push(c);
+        }
+        self.refilter_models();
+    }
+
+    /// Removes the last character of the filter query.
+    pub fn model_filter_backspace(&mut self) {
+        if let Some(filter) = &mut self.model_filter {
+            filter.query.pop();
+        }
+        self.refilter_models();
+    }
+
+    /// Recomputes the matches of the filter query.
+    fn refilter_models(&mut self) {
+        if let Some(filter) = &mut self.model_filter {
+            filter.filtered_indices = filter_models(&self.models, &filter.query, self.fuzzy_filter);
+            filter.selected = 0;
+        }
+    }
+
+    /// Moves the filter highlight one match down, wrapping around.
+    pub fn model_filter_next(&mut self) {
+        if let Some(filter) = &mut self.model_filter {
+            let len = filter.filtered_indices.len().max(1);
+            filter.selected = (filter.selected + 1) % len;
+        }
+    }
+
+    /// Moves the filter highlight one match up, wrapping around.
+    pub fn model_filter_previous(&mut self) {
+        if let Some(filter) = &mut self.model_filter {
+            let len = filter.filtered_indices.len().max(1);
+            filter.selected = (filter.selected + len - 1) % len;
+        }
+    }
+
+    /// Switches to the highlighted match and closes the filter; without a
+    /// match the filter stays open.
+    pub fn confirm_model_filter(&mut self) {
+        let Some(filter) = &self.model_filter else {
+            return;
+        };
+        let Some(model) = filter
+            .filtered_indices
+            .get(filter.selected)
+            .and_then(|&i| self.models.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        self.model_filter = None;
+        self.select_model(&model);
+    }
+
+    /// Closes the filter without changing the selected model.
+    pub fn cancel_model_filter(&mut self) {
+        self.model_filter = None;
+    }
+
+    /// Selects the model with the given name, preserving buffers like the
+    /// arrow-key navigation does.
+    ///
+    /// Returns `false` (and leaves the selection unchanged) if no model with
+    /// that name is in the list.
+    pub fn select_model(&mut self, name: &str) -> bool {
+        match self.models.iter().position(|m| m == name) {
+            Some(index) => {
+                self.save_current_model_buffers();
+                self.list_state.select(Some(index));
+                self.load_current_model_buffers();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the models ordered by most recent activity.
+    ///
+    /// The currently selected model always comes first, followed by every
+    /// other model that has received a query, newest first. Models that were
+    /// never used in this session are left out.
+    pub fn mru_order(&self) -> Vec<String> {
+        let current = self.list_state.selected().and_then(|i| self.models.get(i));
+        let mut recent: Vec<(&String, &Instant)> = self
+            .last_activity
+            .iter()
+            .filter(|(model, _)| Some(*model) != current && self.models.contains(model))
+            .collect();
+        recent.sort_by(|a, b| b.1.cmp(a.1));
+        current
+            .into_iter()
+            .chain(recent.into_iter().map(|(model, _)| model))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the order to persist for the next session: this session's
+    /// [`App::mru_order`] followed by the remaining `recent_models`.
+    pub fn persistent_mru_order(&self) -> Vec<String> {
+        let mut order = self.mru_order();
+        for model in &self.recent_models {
+            if !order.contains(model) {
+                order.push(model.clone());
+            }
+        }
+        order
+    }
+
+    /// Opens the recent-models switcher or advances its highlight.
+    ///
+    /// Like Alt+Tab, opening the switcher highlights the previously used
+    /// model; each further call moves one entry down, wrapping around.
+    pub fn cycle_mru_switcher(&mut self) {
+        let len = self.mru_order().len();
+        if len < 2 {
+            self.mru_selection = None;
+            self.status_message = Some("No other recent conversations".to_string());
+            return;
+        }
+        self.mru_selection = Some(match self.mru_selection {
+            Some(i) => (i + 1) % len,
+            None => 1,
+        });
+    }
+
+    /// Moves the switcher highlight one entry up, wrapping around.
+    pub fn cycle_mru_switcher_back(&mut self) {
+        let len = self.mru_order().len();
+        if let Some(i) = self.mru_selection {
+            self.mru_selection = Some((i + len - 1) % len.max(1));
+        }
+    }
+
+    /// Switches to the highlighted model and closes the switcher.
+    pub fn confirm_mru_switcher(&mut self) {
+        if let Some(i) = self.mru_selection.take() {
+            if let Some(model) = self.mru_order().get(i).cloned() {
+                self.select_model(&model);
+            }
+        }
+    }
+
+    /// Closes the switcher without changing the selected model.
+    pub fn cancel_mru_switcher(&mut self) {
+        self.mru_selection = None;
+    }
+
+    /// Returns the name of the active host, if hosts are configured.
+    pub fn active_host_name(&self) -> Option<&str> {
+        self.hosts.get(self.active_host).map(|h| h.name.as_str())
+    }
+
+    /// Opens the host picker with the active host highlighted.
+    pub fn open_host_picker(&mut self) {
+        if self.hosts.len() < 2 {
+            self.status_message = Some("No other hosts configured".to_string());
+            return;
+        }
+        self.host_selection = Some(self.active_host);
+    }
+
+    /// Moves the host picker highlight one entry down, wrapping around.
+    pub fn host_picker_next(&mut self) {
+        let len = self.hosts.len().max(1);
+        if let Some(i) = self.host_selection {
+            self.host_selection = Some((i + 1) % len);
+        }
+    }
+
+    /// Moves the host picker highlight one entry up, wrapping around.
+    pub fn host_picker_previous(&mut self) {
+        let len = self.hosts.len().max(1);
+        if let Some(i) = self.host_selection {
+            self.host_selection = Some((i + len - 1) % len);
+        }
+    }
+
+    /// Switches to the highlighted host and closes the picker.
+    pub async fn confirm_host_picker(&mut self) {
+        if let Some(i) = self.host_selection.take() {
+            self.switch_host(i).await;
+        }
+    }
+
+    /// Closes the host picker without switching.
+    pub fn cancel_host_picker(&mut self) {
+        self.host_selection = None;
+    }
+
+    /// Makes `hosts[index]` the active host.
+    ///
+    /// Creates a client for the host with `client_factory` and reloads the
+    /// model list from it. The per-model buffers of the previous host are
+    /// put aside and restored when switching back, so conversations of
+    /// equally named models on different hosts never mix.
+    ///
+    /// Does nothing while a response is streaming. If the client cannot be
+    /// created, the active host is kept and the error is shown.
+    pub async fn switch_host(&mut self, index: usize) {
+        if self.is_loading || index == self.active_host {
+            return;
+        }
+        let Some(host) = self.hosts.get(index).cloned() else {
+            return;
+        };
+        let client = match (self.client_factory)(&host.url) {
+            Ok(client) => client,
+            Err(e) => {
+                self.status_message = Some(format!("Could not use host {}: {}", host.name, e));
+                return;
+            }
+        };
+
+        self.save_current_model_buffers();
+        let previous = HostBuffers {
+            inputs: std::mem::take(&mut self.model_inputs),
+            cursors: std::mem::take(&mut self.model_cursors),
+            histories: std::mem::take(&mut self.model_histories),
+            scrolls: std::mem::take(&mut self.model_scrolls),
+            collapsed: std::mem::take(&mut self.model_collapsed),
+            bookmarks: std::mem::take(&mut self.model_bookmarks),
+            last_activity: std::mem::take(&mut self.last_activity),
+        };
+        if let Some(name) = self.active_host_name().map(str::to_string) {
+            self.host_buffers.insert(name, previous);
+        }
+        let next = self.host_buffers.remove(&host.name).unwrap_or_default();
+        self.model_inputs = next.inputs;
+        self.model_cursors = next.cursors;
+        self.model_histories = next.histories;
+        self.model_scrolls = next.scrolls;
+        self.model_collapsed = next.collapsed;
+        self.model_bookmarks = next.bookmarks;
+        self.last_activity = next.last_activity;
+
+        self.client = client;
+        self.active_host = index;
+        self.model_info_cache.clear();
+        self.mru_selection = None;
+        self.list_state.select(None);
+        if !self.shared_input {
+            self.input.clear();
+            self.cursor_pos = 0;
+        }
+        self.history.clear();
+        self.scroll = 0;
+        self.collapsed.clear();
+        self.bookmark = None;
+        self.focused_exchange = None;
+        self.previous_answer = None;
+        self.last_failed_prompt = None;
+        self.server_version = None;
+        self.set_models(Vec::new());
+        self.status_message = Some(match self.refresh_models().await {
+            Err(e) => format!("Switched to {}, but it is unreachable: {}", host.name, e),
+            Ok(()) if self.all_models.is_empty() => {
+                format!("Switched to {}, no models found", host.name)
+            }
+            Ok(()) => format!("Switched to {}", host.name),
+        });
+    }
+
+    /// Returns the conversation histories of every host, for saving on exit.
+    ///
+    /// Without configured hosts these are just `model_histories`. Otherwise
+    /// each key is prefixed with the host name, as in `gpu-box/llama3:8b`.
+    /// `[request debug]` blocks are left out.
+    pub fn all_model_histories(&self) -> HashMap<String, String> {
+        let Some(active) = self.active_host_name() else {
+            return self
+                .model_histories
+                .iter()
+                .map(|(model, history)| (model.clone(), strip_request_debug(history)))
+                .collect();
+        };
+        let mut histories = HashMap::new();
+        let hosts = self
+            .host_buffers
+            .iter()
+            .map(|(host, buffers)| (host.as_str(), &buffers.histories))
+            .chain(std::iter::once((active, &self.model_histories)));
+        for (host, host_histories) in hosts {
+            for (model, history) in host_histories {
+                histories.insert(format!("{}/{}", host, model), strip_request_debug(history));
+            }
+        }
+        histories
+    }
+
+    /// Highlights the previous exchange, starting from the most recent one.
+    pub fn focus_previous_exchange(&mut self) {
+        let count = split_exchanges(&self.history).len();
+        if count == 0 {
+            return;
+        }
+        self.focused_exchange = Some(match self.focused_exchange {
+            Some(i) => i.saturating_sub(1).min(count - 1),
+            None => count - 1,
+        });
+        self.scroll_to_focus = true;
+    }
+
+    /// Highlights the next exchange; moving past the last one clears the
+    /// highlight and returns to autoscroll.
+    pub fn focus_next_exchange(&mut self) {
+        let count = split_exchanges(&self.history).len();
+        match self.focused_exchange {
+            Some(i) if i + 1 < count => {
+                self.focused_exchange = Some(i + 1);
+                self.scroll_to_focus = true;
+            }
+            Some(_) => self.clear_exchange_focus(),
+            None => {}
+        }
+    }
+
+    /// Removes the exchange highlight and returns to autoscroll.
+    pub fn clear_exchange_focus(&mut self) {
+        self.focused_exchange = None;
+        self.autoscroll = true;
+    }
+
+    /// Collapses or expands the highlighted exchange.
+    ///
+    /// Reports through `status_message` if no exchange is highlighted.
+    pub fn toggle_focused_exchange(&mut self) {
+        match self.focused_exchange {
+            Some(i) => {
+                if !self.collapsed.remove(&i) {
+                    self.collapsed.insert(i);
+                }
+                self.scroll_to_focus = true;
+                self.save_current_model_buffers();
+            }
+            None => {
+                self.status_message = Some("Select an exchange with Alt+↑/↓ first".to_string());
+            }
+        }
+    }
+
+    /// Inserts a prompt asking to explain the highlighted exchange's answer
+    /// at the cursor (see [`explain_prompt`]).
+    ///
+    /// Reports through `status_message` if no exchange is highlighted or
+    /// the highlighted one has no answer.
+    pub fn explain_focused_exchange(&mut self) {
+        let Some(i) = self.focused_exchange else {
+            self.status_message = Some("Select an exchange with Alt+↑/↓ first".to_string());
+            return;
+        };
+        let answer = split_exchanges(&self.history)
+            .get(i)
+            .and_then(|exchange| exchange_parts(exchange))
+            .map(|(_, answer)| answer)
+            .filter(|answer| !answer.is_empty());
+        match answer {
+            Some(answer) => {
+                let prompt = explain_prompt(answer, EXPLAIN_MAX_CHARS);
+                self.insert_str(&prompt);
+            }
+            None => self.status_message = Some("No answer to explain".to_string()),
+        }
+    }
+
+    /// Returns the unsent inputs for [`utils::save_drafts`], with their
+    /// cursor positions.
+    ///
+    /// These are the active host's per-model inputs, or the one input under
+    /// [`SHARED_DRAFT_KEY`] with `shared_input`. Call
+    /// [`App::save_current_model_buffers`] first to include the selected
+    /// model's input.
+    pub fn drafts(&self) -> HashMap<String, Draft> {
+        if self.shared_input {
+            let draft = Draft {
+                text: self.input.clone(),
+                cursor: self.cursor_pos,
+            };
+            return HashMap::from([(SHARED_DRAFT_KEY.to_string(), draft)]);
+        }
+        self.model_inputs
+            .iter()
+            .map(|(model, text)| {
+                let cursor = self.model_cursors.get(model).copied().unwrap_or(0);
+                let draft = Draft {
+                    text: text.clone(),
+                    cursor,
+                };
+                (model.clone(), draft)
+            })
+            .collect()
+    }
+
+    /// Restores inputs saved by [`App::drafts`].
+    ///
+    /// Per-model drafts go to `model_inputs` and `model_cursors`, to be
+    /// loaded when their model is selected; with `shared_input` the
+    /// [`SHARED_DRAFT_KEY`] draft becomes the input.
+    pub fn apply_drafts(&mut self, mut drafts: HashMap<String, Draft>) {
+        if self.shared_input {
+            if let Some(draft) = drafts.remove(SHARED_DRAFT_KEY) {
+                self.input = draft.text;
+                self.cursor_pos = draft.cursor;
+                self.clamp_cursor();
+            }
+            return;
+        }
+        for (model, draft) in drafts {
+            if model != SHARED_DRAFT_KEY {
+                self.model_inputs.insert(model.clone(), draft.text);
+                self.model_cursors.insert(model, draft.cursor);
+            }
+        }
+        self.load_current_model_buffers();
+    }
+
+    /// Switches to the next [`Emphasis`] and names it in the status bar.
+    pub fn cycle_emphasis(&mut self) {
+        self.emphasis = self.emphasis.next();
+        self.status_message = Some(format!("Text emphasis: {}", self.emphasis.name()));
+    }
+
+    /// Re-reads the config file and applies its settings to the running
+    /// session, reporting the outcome in the status bar.
+    ///
+    /// A file that fails to parse leaves the current settings untouched.
+    /// The model list, hosts and startup-only settings such as
+    /// `restore_histories` and `shared_input` still need a restart.
+    pub fn reload_config(&mut self) {
+        let Some(path) = self.config_path.clone() else {
+            self.status_message = Some("No config file to reload".to_string());
+            return;
+        };
+        let config = match Config::load_from(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                self.status_message = Some(format!("Config error: {}", e));
+                return;
+            }
+        };
+        self.status_message = Some(match config.validate() {
+            Ok(()) => "Config reloaded".to_string(),
+            Err(e) => format!("Config reloaded, warning: {}", e),
+        });
+        self.snippets = config.snippets;
+        self.code_block_style = config.code_block_style;
+        self.autoscroll_mode = config.autoscroll_mode;
+        self.fuzzy_filter = config.fuzzy_filter;
+        self.keep_unavailable_models = config.keep_unavailable_models;
+        self.timestamp_format = config.timestamp_format;
+        self.code_language = config.code_language;
+        self.yank_lead_in = config.yank_lead_in;
+        self.prompt_template = config.prompt_template;
+        self.greeting = config.greeting;
+        self.model_templates = config.model_templates;
+        self.regenerate_diff = config.regenerate_diff;
+        self.send_key = config.send_key;
+        self.trim_response = config.trim_response;
+        self.labels = config.labels;
+        self.thinking_hint_after = Duration::from_secs(config.thinking_hint_after);
+        self.thinking_hint = config.thinking_hint;
+        self.context_limit = config.context_limit;
+        self.context_limits = config.context_limits;
+        self.num_predict = config.num_predict;
+        self.num_predicts = config.num_predicts;
+        self.confirm_quit = config.confirm_quit;
+        self.confirm_quit_after = config.confirm_quit_after;
+        self.confirm_commands = config.confirm_commands;
+        self.restore_drafts = config.restore_drafts;
+        self.max_chat_logs = config.max_chat_logs;
+        self.log_format = config.log_format;
+        self.export_dir = expand_home(&config.export_dir);
+        self.status_clock = config.status_clock;
+        self.status_session = config.status_session;
+        self.resume_interrupted = config.resume_interrupted;
+        self.fallback_models = config.fallback_models;
+        self.number_turns = config.number_turns;
+        self.text_direction = config.text_direction;
+        self.quiet = config.quiet;
+        // `LAZYLLAMA_PLAIN` keeps plain rendering on.
+        self.plain = config.plain
+            || env::var("LAZYLLAMA_PLAIN")
+                .map(|v| v != "0" && v.to_lowercase() != "false")
+                .unwrap_or(false);
+    }
+
+    /// Toggles `echo_requests`; outside debug mode only explains how to
+    /// enable it.
+    pub fn toggle_echo_requests(&mut self) {
+        if !self.debug_keys {
+            self.status_message =
+                Some("Request debug needs LAZYLLAMA_DEBUG_KEYS=1".to_string());
+            return;
+        }
+        self.echo_requests = !self.echo_requests;
+        self.status_message = Some(format!(
+            "Request debug {}",
+            if self.echo_requests { "on" } else { "off" }
+        ));
+    }
+
+    /// Handles a request to quit, returning whether to quit right away.
+    ///
+    /// With `confirm_quit` set and more than `confirm_quit_after`
+    /// characters of conversation across all models, the quit confirmation
+    /// is opened instead; it names the directory the session will be saved
+    /// to.
+    pub fn request_quit(&mut self) -> bool {
+        self.save_current_model_buffers();
+        let chars: usize = self
+            .all_model_histories()
+            .values()
+            .map(|history| history.chars().count())
+            .sum();
+        if !self.confirm_quit || chars <= self.confirm_quit_after {
+            return true;
+        }
+        self.quit_confirmation = Some(if self.ephemeral {
+            "This session will not be saved.".to_string()
+        } else {
+            match utils::data_dir() {
+                Ok(dir) => format!("The session will be saved to {}", dir.display()),
+                Err(e) => format!("The session cannot be saved: {}", e),
+            }
+        });
+        false
+    }
+
+    /// Answers the quit confirmation, returning whether to quit.
+    pub fn answer_quit_confirmation(&mut self, confirmed: bool) -> bool {
+        self.quit_confirmation = None;
+        confirmed
+    }
+
+    /// Switches to the next model in the list (Down arrow key behavior).
+    ///
+    /// This method implements circular navigation through the model list,
+    /// wrapping from the last model back to the first. It preserves the
+    /// current model's state before switching and loads the target model's
+    /// stored state.
+    ///
+    /// # Behavior
+    ///
+    /// 1. Saves current model's state via `save_current_model_buffers()`
+    /// 2. Calculates next index with wraparound (last → first)
+    /// 3. Updates `list_state` selection to new index
+    /// 4. Loads the new model's state via `load_current_model_buffers()`
+    ///
+    /// # Model Selection Logic
+    ///
+    /// - If at last model: wraps to index 0 (first model)
+    /// - Otherwise: increments to next index
+    /// - If no model selected: selects index 0
+    /// - Handles empty model list gracefully
+    pub fn select_next_model(&mut self) {
+        if self.models.is_empty() {
+            return;
+        }
+        
+        self.save_current_model_buffers();
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i >= self.models.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+        self.load_current_model_buffers();
+    }
+
+    /// Switches to the previous model in the list (Up arrow key behavior).
+    ///
+    /// This method implements circular navigation through the model list,
+    /// wrapping from the first model back to the last. It preserves the
+    /// current model's state before switching and loads the target model's
+    /// stored state.
+    ///
+    /// # Behavior
+    ///
+    /// 1. Saves current model's state via `save_current_model_buffers()`
+    /// 2. Calculates previous index with wraparound (first → last)
+    /// 3. Updates `list_state` selection to new index
+    /// 4. Loads the new model's state via `load_current_model_buffers()`
+    ///
+    /// # Model Selection Logic
+    ///
+    /// - If at first model (index 0): wraps to last model
+    /// - Otherwise: decrements to previous index
+    /// - If no model selected: selects index 0
+    /// - Handles empty model list gracefully
+    pub fn select_previous_model(&mut self) {
+        if self.models.is_empty() {
+            return;
+        }
+        
+        self.save_current_model_buffers();
+        let i = match self.list_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.models.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+        self.load_current_model_buffers();
+    }
+
+    /// Raises the selected model's temperature by [`TEMPERATURE_STEP`].
+    pub fn raise_temperature(&mut self) {
+        self.adjust_temperature(TEMPERATURE_STEP);
+    }
+
+    /// Lowers the selected model's temperature by [`TEMPERATURE_STEP`].
+    pub fn lower_temperature(&mut self) {
+        self.adjust_temperature(-TEMPERATURE_STEP);
+    }
+
+    /// Changes the selected model's temperature by `delta`, clamped to
+    /// [`MIN_TEMPERATURE`]..=[`MAX_TEMPERATURE`], and shows the new value.
+    ///
+    /// Starts from [`DEFAULT_TEMPERATURE`] if none is set yet. Values are
+    /// rounded to one decimal so repeated steps do not drift.
+    fn adjust_temperature(&mut self, delta: f32) {
+        let Some(model) = self.list_state.selected().and_then(|i| self.models.get(i)).cloned()
+        else {
+            return;
+        };
+        let options = self.model_options.entry(model.clone()).or_default();
+        let current = options.temperature.unwrap_or(DEFAULT_TEMPERATURE);
+        let next = ((current + delta) * 10.0).round() / 10.0;
+        let next = next.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE);
+        options.temperature = Some(next);
+        self.status_message = Some(format!("Temperature for {}: {:.1}", model, next));
+    }
+
+    /// Raises the selected model's answer cap to the next of
+    /// [`NUM_PREDICT_STEPS`], or lifts it above the last.
+    pub fn raise_num_predict(&mut self) {
+        self.adjust_num_predict(true);
+    }
+
+    /// Lowers the selected model's answer cap to the previous of
+    /// [`NUM_PREDICT_STEPS`]; an unlimited model gets the largest cap.
+    pub fn lower_num_predict(&mut self) {
+        self.adjust_num_predict(false);
+    }
+
+    /// Steps the selected model's answer cap, starting from its effective
+    /// cap (see [`App::num_predict_for`]), and shows the new value.
+    ///
+    /// The result is kept in `model_options`, so lifting the cap also
+    /// overrides a configured one.
+    fn adjust_num_predict(&mut self, raise: bool) {
+        let Some(model) = self.list_state.selected().and_then(|i| self.models.get(i)).cloned()
+        else {
+            return;
+        };
+        let current = self.num_predict_for(&model).filter(|&n| n >= 0);
+        let next = match (current, raise) {
+            (None, true) => None,
+            (None, false) => NUM_PREDICT_STEPS.last().copied(),
+            (Some(n), true) => NUM_PREDICT_STEPS.iter().copied().find(|&step| step > n),
+            (Some(n), false) => Some(
+                NUM_PREDICT_STEPS
+                    .iter()
+                    .copied()
+                    .rev()
+                    .find(|&step| step < n)
+                    .unwrap_or(NUM_PREDICT_STEPS[0]),
+            ),
+        };
+        self.model_options.entry(model.clone()).or_default().num_predict = Some(next.unwrap_or(-1));
+        self.status_message = Some(match next {
+            Some(n) => format!("Answer cap for {}: {} tokens", model, n),
+            None => format!("Answer cap for {}: unlimited", model),
+        });
+    }
+
+    /// Returns the answer cap sent for `model`: the one adjusted with
+    /// `Alt+[`/`Alt+]`, the model's configured one or the global one, in
+    /// that order. `-1` means no limit.
+    pub fn num_predict_for(&self, model: &str) -> Option<i32> {
+        self.model_options
+            .get(model)
+            .and_then(|options| options.num_predict)
+            .or_else(|| self.num_predicts.get(model).copied())
+            .or(self.num_predict)
+    }
+
+    /// Applies the prompt template configured for `model` to `prompt`.
+    ///
+    /// A per-model template takes precedence over the global one; without a
+    /// valid template the prompt is returned unchanged.
+    pub fn wrap_prompt(&self, model: &str, prompt: &str) -> String {
+        let template = self
+            .model_templates
+            .get(model)
+            .or(self.prompt_template.as_ref());
+        apply_template(template.map(String::as_str), prompt)
+    }
+
+    /// Puts the configured `greeting` before `prompt` if the active
+    /// conversation has no exchanges yet.
+    ///
+    /// The primer is part of the visible prompt, so a prompt that already
+    /// starts with it, e.g. when retrying the first message, is left as it
+    /// is.
+    pub fn primed_prompt(&self, prompt: String) -> String {
+        match self.greeting.as_deref().map(str::trim) {
+            Some(greeting)
+                if !greeting.is_empty()
+                    && split_exchanges(&self.history).is_empty()
+                    && !prompt.starts_with(greeting) =>
+            {
+                format!("{}\n\n{}", greeting, prompt)
+            }
+            _ => prompt,
+        }
+    }
+
+    /// Returns the configured context window of `model` in tokens, if any.
+    pub fn context_limit_for(&self, model: &str) -> Option<usize> {
+        self.context_limits.get(model).copied().or(self.context_limit)
+    }
+
+    /// Checks whether the current input may not fit into the selected
+    /// model's context window.
+    ///
+    /// Each request is sent on its own, so only the input wrapped in its
+    /// prompt template counts, not the conversation history. Returns the
+    /// estimated and allowed number of tokens if the estimate is over the
+    /// limit, and `None` if it fits or no limit is configured.
+    pub fn context_overflow(&self) -> Option<(usize, usize)> {
+        let model = self.models.get(self.list_state.selected()?)?;
+        let limit = self.context_limit_for(model)?;
+        let estimate = estimate_tokens(&self.wrap_prompt(model, &self.input));
+        (estimate > limit).then_some((estimate, limit))
+    }
+
+    /// Sends the current input to the selected model and streams the response.
+    ///
+    /// This method handles the complete query lifecycle including prompt formatting,
+    /// API communication, real-time response streaming, and UI updates. The response
+    /// is written directly to `self.history` as tokens are received, providing
+    /// immediate visual feedback to the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `terminal` - Mutable reference to the terminal for real-time UI updates. Any
+    ///   ratatui backend works, including `TestBackend` for headless tests
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` once the turn is complete, also if the model request
+    /// failed (see below), or [`LazyLlamaError::Terminal`] if terminal
+    /// drawing fails.
+    ///
+    /// # Behavior
+    ///
+    /// 1. **Validation**: Runs a `/file` command on the last input line
+    ///    instead of sending (see [`App::attach_file`]) and ensures a model is
+    ///    selected before proceeding
+    /// 2. **Formatting**: Adds user prompt to conversation history with "YOU:" label
+    ///    and opens the answer with an `AI [model]:` label naming the producing model
+    /// 3. **State Management**: Clears input field and saves current buffers
+    /// 4. **UI Updates**: Sets loading state and enables autoscroll
+    /// 5. **Streaming**: Sends request to Ollama and processes response tokens
+    /// 6. **Real-time Display**: Updates terminal display for each received token
+    /// 7. **Completion**: Adds a timing footer and separator and saves final state
+    ///
+    /// # Error Handling
+    ///
+    /// - API and streaming errors end the turn with an [`ERROR_PREFIX`] line
+    ///   instead of a timing footer
+    /// - The failed prompt is kept in `last_failed_prompt` for [`App::retry`]
+    ///   and the error in `last_error`
+    /// - An answer that is empty or only whitespace is treated as failed
+    ///   too, with `empty_response` set
+    /// - Ensures loading state is cleared even on errors
+    /// - Preserves conversation history even if request fails
+    ///
+    /// # Side Effects
+    ///
+    /// - Modifies `self.history` with formatted conversation
+    /// - Clears `self.input` field
+    /// - Updates `self.is_loading` state
+    /// - Triggers terminal redraws for real-time display
+    /// - Saves state to model-specific buffers
+    pub async fn send_query<B>(&mut self, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        if self.run_input_command() {
+            return Ok(());
+        }
+        if self.list_state.selected().is_none() {
+            return Ok(());
+        }
+        let prompt = std::mem::take(&mut self.input);
+        self.cursor_pos = 0;
+        self.previous_answer = None;
+        self.submit_prompt(prompt, terminal).await?;
+        self.send_pending_prompts(terminal).await
+    }
+
+    /// Moves the input into `pending_prompts`, to be sent after the answer
+    /// that is being generated.
+    ///
+    /// While an answer streams, input commands are refused and stay in the
+    /// input, as `/clear` would empty the history under it; otherwise a
+    /// command is run instead, as with [`App::send_query`].
+    pub fn queue_prompt(&mut self) {
+        // A command such as `/clear` must not change the history under an
+        // answer that is still streaming.
+        if self.is_loading {
+            if let Some(command) = self.input_command() {
+                self.status_message = Some(format!("{} can be sent once the answer is complete", command));
+                return;
+            }
+        }
+        if self.run_input_command() || self.input.trim().is_empty() {
+            return;
+        }
+        self.pending_prompts.push_back(std::mem::take(&mut self.input));
+        self.cursor_pos = 0;
+    }
+
+    /// Sends the queued prompts one after the other.
+    ///
+    /// Stops at the first failed request, so a server that went away does
+    /// not fail every prompt in turn; the prompts still queued are put back
+    /// into the input, ahead of anything typed since.
+    async fn send_pending_prompts<B>(&mut self, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        while self.last_failed_prompt.is_none() {
+            let Some(prompt) = self.pending_prompts.pop_front() else {
+                return Ok(());
+            };
+            self.submit_prompt(prompt, terminal).await?;
+        }
+        if !self.pending_prompts.is_empty() {
+            let count = self.pending_prompts.len();
+            let mut restored: Vec<String> = self.pending_prompts.drain(..).collect();
+            if !self.input.is_empty() {
+                restored.push(std::mem::take(&mut self.input));
+            }
+            self.input = restored.join("\n\n");
+            self.cursor_pos = self.input.chars().count();
+            self.status_message = Some(format!("Request failed, {} queued prompt(s) moved to the input", count));
+        }
+        Ok(())
+    }
+
+    /// Handles the keys typed while an answer streams, as read from
+    /// [`App::key_source`].
+    ///
+    /// Text and cursor keys edit the input and the send key queues it with
+    /// [`App::queue_prompt`]; every other key is kept in `deferred_keys`
+    /// for the event loop. Returns `true` if any key was read.
+    fn read_keys_while_streaming(&mut self) -> bool {
+        let Some(source) = self.key_source.as_mut() else {
+            return false;
+        };
+        let keys = source();
+        for &key in &keys {
+            if !self.deferred_keys.is_empty() {
+                // Keep the order once a key had to wait for the event loop.
+                self.deferred_keys.push_back(key);
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+            let alt = key.modifiers.contains(KeyModifiers::ALT);
+            match (key.code, ctrl) {
+                // Many terminals report Ctrl+Enter as Ctrl+J.
+                (KeyCode::Enter, _) | (KeyCode::Char('j'), true) => {
+                    if self.send_key.sends(ctrl, shift) {
+                        self.queue_prompt();
+                    } else {
+                        self.insert_char('\n');
+                    }
+                }
+                (KeyCode::Char(c), false) if !alt => self.insert_char(c),
+                _ if self.handle_editing_key(key) => {}
+                _ => self.deferred_keys.push_back(key),
+            }
+        }
+        !keys.is_empty()
+    }
+
+    /// Asks the model to answer the last prompt again.
+    ///
+    /// The last exchange is removed from the history and its prompt is sent
+    /// again; the input field is left untouched. With `regenerate_diff`
+    /// enabled the replaced answer is kept in `previous_answer`, so the UI
+    /// can show what changed.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`App::send_query`].
+    pub async fn regenerate<B>(&mut self, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        let exchanges = split_exchanges(&self.history);
+        let Some((last, earlier)) = exchanges.split_last() else {
+            self.status_message = Some("Nothing to regenerate".to_string());
+            return Ok(());
+        };
+        let Some((prompt, answer)) = exchange_parts(last) else {
+            self.status_message = Some("Nothing to regenerate".to_string());
+            return Ok(());
+        };
+        let prompt = prompt.to_string();
+        let answer = answer.to_string();
+        let start: usize = earlier.iter().map(|e| e.len()).sum();
+
+        self.history.truncate(start);
+        self.previous_answer = self.regenerate_diff.then_some(answer);
+        self.submit_prompt(prompt, terminal).await?;
+        self.send_pending_prompts(terminal).await
+    }
+
+    /// Whether a failed request can be retried with [`App::retry`]: the last
+    /// request failed and nothing new has been typed since.
+    pub fn can_retry(&self) -> bool {
+        self.last_failed_prompt.is_some() && self.input.is_empty() && !self.is_loading
+    }
+
+    /// Removes the failed exchange from the history and sends its prompt
+    /// again.
+    ///
+    /// After an empty answer the temperature of the selected model is
+    /// raised by [`TEMPERATURE_STEP`] first, as a slightly more varied
+    /// answer is less likely to end right away.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`App::send_query`].
+    pub async fn retry<B>(&mut self, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        let Some(prompt) = self.last_failed_prompt.take() else {
+            self.status_message = Some("Nothing to retry".to_string());
+            return Ok(());
+        };
+        let exchanges = split_exchanges(&self.history);
+        if let Some((_, earlier)) = exchanges.split_last() {
+            let start: usize = earlier.iter().map(|e| e.len()).sum();
+            self.history.truncate(start);
+        }
+        self.previous_answer = None;
+        if self.empty_response {
+            self.raise_temperature();
+        }
+        self.submit_prompt(prompt, terminal).await?;
+        self.send_pending_prompts(terminal).await
+    }
+
+    /// Returns the text for the status bar: the status message, or a retry
+    /// hint after a failed request.
+    pub fn status_text(&self) -> Option<String> {
+        self.status_message
+            .clone()
+            .or_else(|| {
+                let hint = if self.empty_response {
+                    "Empty response, press r to retry with a higher temperature"
+                } else {
+                    "Request failed, press r to retry"
+                };
+                self.can_retry().then(|| hint.to_string())
+            })
+    }
+
+    /// Returns the thinking hint once the model has been silent for longer
+    /// than `thinking_hint_after`.
+    ///
+    /// A model that was already loaded needs no time to load, so if it has
+    /// not answered within [`NO_TOKENS_AFTER`], [`NO_TOKENS_HINT`] is shown
+    /// instead. The hint does not guess why: the model may be reading a
+    /// long prompt or serving another client first.
+    pub fn active_thinking_hint(&self) -> Option<&str> {
+        let waiting = self.waiting_since?.elapsed();
+        if self.is_loading && self.model_was_loaded && waiting >= NO_TOKENS_AFTER {
+            return Some(NO_TOKENS_HINT);
+        }
+        let show = self.is_loading
+            && !self.thinking_hint_after.is_zero()
+            && waiting >= self.thinking_hint_after;
+        show.then_some(self.thinking_hint.as_str())
+    }
+
+    /// Whether `model` is loaded on the server.
+    ///
+    /// If the running models cannot be listed within
+    /// [`RUNNING_MODELS_TIMEOUT`], it counts as not loaded.
+    async fn model_loaded(&self, model: &str) -> bool {
+        match tokio::time::timeout(RUNNING_MODELS_TIMEOUT, self.client.running_models()).await {
+            Ok(Ok(running)) => running.iter().any(|m| m == model),
+            _ => false,
+        }
+    }
+
+    /// Draws the UI, for redraws while a response streams.
+    fn redraw<B>(&mut self, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        terminal
+            .draw(|f| crate::ui::ui(f, self))
+            .map_err(|e| LazyLlamaError::Terminal(Box::new(e)))?;
+        Ok(())
+    }
+
+    /// Builds the request for `prompt` to `model`.
+    ///
+    /// Only the model sees the templated prompt; the history keeps the text
+    /// the user typed.
+    fn request_for(&self, model: &str, prompt: &str) -> GenerateRequest {
+        let options = GenerateOptions {
+            num_predict: self.num_predict_for(model),
+            ..self.model_options.get(model).copied().unwrap_or_default()
+        };
+        GenerateRequest::new(model.to_string(), self.wrap_prompt(model, prompt))
+            .with_options(options)
+    }
+
+    /// Streams `model`'s answer to `prompt` into `sink` and returns the
+    /// full answer.
+    ///
+    /// `sink` is called with the text of every received chunk. The prompt
+    /// template and temperature of `model` apply as for
+    /// [`App::send_query`], but no UI state is touched: the history, input
+    /// and loading state stay as they are, so embedders can send answers to
+    /// a GUI, a socket or a file instead.
+    ///
+    /// [`App::send_query`] is not built on this method: its sink would have
+    /// to change the history and redraw the terminal while `self` is
+    /// borrowed here, which the borrow checker rejects. Both build their
+    /// request with `request_for` and drive the stream with `pump_stream`,
+    /// so chunks arrive the same way on both paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LazyLlamaError::ModelNotFound`] or
+    /// [`LazyLlamaError::Connection`] if the request is rejected and
+    /// [`LazyLlamaError::Stream`] if the stream breaks off. `sink` has
+    /// received the text streamed until then.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::app::App;
+    /// use lazyllama::client::ScriptedClient;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["Hel", "lo"]);
+    /// let app = App::builder().client(client).build();
+    /// let mut chunks = Vec::new();
+    /// let answer = app.generate_into("mock", "Hi", |c| chunks.push(c.to_string())).await.unwrap();
+    /// assert_eq!(answer, "Hello");
+    /// assert_eq!(chunks, ["Hel", "lo"]);
+    /// assert!(app.history.is_empty());
+    /// # });
+    /// ```
+    pub async fn generate_into<W>(&self, model: &str, prompt: &str, mut sink: W) -> Result<String>
+    where
+        W: FnMut(&str),
+    {
+        let stream = self
+            .client
+            .generate_stream(self.request_for(model, prompt))
+            .await
+            .map_err(|e| LazyLlamaError::from_client(model, e))?;
+        let mut answer = String::new();
+        pump_stream(stream, |batch| {
+            if let Some(text) = batch.filter(|text| !text.is_empty()) {
+                sink(text);
+                answer.push_str(text);
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(answer)
+    }
+
+    /// Appends `prompt` to the history and streams the selected model's answer.
+    ///
+    /// Streams with `pump_stream` directly rather than through
+    /// [`App::generate_into`], as appending to the history, redrawing and
+    /// reading keys need `&mut self` while the answer streams.
+    async fn submit_prompt<B>(&mut self, prompt: String, terminal: &mut Terminal<B>) -> Result<()>
+    where
+        B: Backend,
+        B::Error: Send + Sync + 'static,
+    {
+        if let Some(i) = self.list_state.selected() {
+            let mut model = self.models[i].clone();
+            let prompt = self.primed_prompt(prompt);
+            if let Some(lang) = extract_code_blocks(&prompt).into_iter().rev().find_map(|b| b.lang) {
+                self.code_language = lang;
+            }
+
+            let mut request = self.request_for(&model, &prompt);
+            if self.debug_keys && self.echo_requests {
+                self.history.push_str(&request_debug_block(&request));
+            }
+            // Record the producing model in the label so mixed-model
+            // histories can show who answered each turn.
+            self.history.push_str(&format!("\nYOU: {}\n\n", prompt));
+            let label_start = self.history.len();
+            self.history.push_str(&format!("AI [{}]: ", model));
+            let mut answer_start = self.history.len();
+            self.stream_start = Some(answer_start);
+            
+            // Speichere die aktualisierten Buffer für das aktuelle Modell
+            self.save_current_model_buffers();
+            
+            self.is_loading = true;
+            self.autoscroll = true;
+            self.focused_exchange = None;
+            self.model_was_loaded = false;
+            self.last_activity.insert(model.clone(), Instant::now());
+
+            let started = Instant::now();
+            self.waiting_since = Some(started);
+
+            // The answer without reconnection notes, for resuming it.
+            let mut partial = String::new();
+            let mut resumed = 0;
+            let mut hint_shown = false;
+            // Models asked so far, so no fallback is tried twice.
+            let mut tried = vec![model.clone()];
+            let mut notes = String::new();
+            let result = loop {
+                self.last_request = Some(request.clone());
+                let stream = self.client.generate_stream(request);
+                // Ollama does not report running requests; whether the
+                // model was loaded is checked alongside the first request,
+                // so sending is never delayed (see `active_thinking_hint`).
+                let stream = if tried.len() == 1 && resumed == 0 {
+                    let (loaded, stream) = tokio::join!(self.model_loaded(&model), stream);
+                    self.model_was_loaded = loaded;
+                    stream
+                } else {
+                    stream.await
+                };
+                let result = match stream {
+                    Ok(stream) => {
+                        pump_stream(stream, |batch| {
+                            // Redraw while the model is silent too, so the
+                            // spinner keeps turning and the thinking hint can
+                            // appear. Without the spinner only the hint needs
+                            // a redraw.
+                            let typed = self.read_keys_while_streaming();
+                            let Some(text) = batch else {
+                                if self.quiet && !typed {
+                                    if hint_shown || self.active_thinking_hint().is_none() {
+                                        return Ok(());
+                                    }
+                                    hint_shown = true;
+                                }
+                                return self.redraw(terminal);
+                            };
+                            let append_started = self.debug_keys.then(Instant::now);
+                            if !text.is_empty() {
+                                self.waiting_since = None;
+                            }
+                            self.history.push_str(text);
+                            partial.push_str(text);
+                            self.redraw(terminal)?;
+                            if let Some(started) = append_started {
+                                self.last_append_us = started.elapsed().as_micros() as u64;
+                            }
+                            Ok(())
+                        })
+                        .await
+                    }
+                    Err(e) => Err(LazyLlamaError::from_client(&model, e)),
+                };
+                // A broken stream is resumed; once resuming, a server that
+                // is still unreachable uses up an attempt too.
+                let resumable = match &result {
+                    Err(LazyLlamaError::Stream(_)) => true,
+                    Err(LazyLlamaError::Connection(_)) => resumed > 0,
+                    _ => false,
+                };
+                if !(resumable && self.resume_interrupted && resumed < MAX_RESUME_ATTEMPTS) {
+                    // A model that fails before answering hands the prompt
+                    // on to the next untried fallback.
+                    let failed_early = partial.is_empty()
+                        && matches!(&result, Err(e) if !matches!(e, LazyLlamaError::Terminal(_)));
+                    let fallback = self
+                        .fallback_models
+                        .iter()
+                        .find(|m| !tried.contains(m))
+                        .filter(|_| failed_early)
+                        .cloned();
+                    let Some(fallback) = fallback else {
+                        break result;
+                    };
+                    // The label names the model that gives the answer, and
+                    // the notes the ones that failed before it.
+                    notes.push_str(&fallback_note(&model));
+                    self.history.truncate(label_start);
+                    self.history.push_str(&format!("AI [{}]: {}", fallback, notes));
+                    answer_start = self.history.len();
+                    self.stream_start = Some(answer_start);
+                    self.waiting_since = Some(Instant::now());
+                    self.model_was_loaded = false;
+                    resumed = 0;
+                    tried.push(fallback.clone());
+                    model = fallback;
+                    request = self.request_for(&model, &prompt);
+                    continue;
+                }
+                resumed += 1;
+                if !self.history.ends_with(RECONNECTED_NOTE) {
+                    self.history.push_str(RECONNECTED_NOTE);
+                }
+                self.waiting_since = Some(Instant::now());
+                request = self.request_for(&model, &continuation_prompt(&prompt, &partial));
+            };
+            self.stream_start = None;
+            self.waiting_since = None;
+
+            let timings = match result {
+                Ok(timings) => timings,
+                Err(e @ LazyLlamaError::Terminal(_)) => return Err(e),
+                Err(e) => {
+                    self.history
+                        .push_str(&format!("\n{}{}\n{}\n", ERROR_PREFIX, e, TURN_SEPARATOR));
+                    self.last_failed_prompt = Some(prompt);
+                    self.last_error = Some(e);
+                    self.empty_response = false;
+                    self.is_loading = false;
+                    self.save_current_model_buffers();
+                    return Ok(());
+                }
+            };
+            self.last_error = None;
+            self.empty_response = self
+                .history
+                .get(answer_start..)
+                .is_none_or(|answer| answer.trim().is_empty());
+            if self.empty_response {
+                self.history.truncate(answer_start);
+                self.history
+                    .push_str(&format!("\n{}empty response\n{}\n", ERROR_PREFIX, TURN_SEPARATOR));
+                self.last_failed_prompt = Some(prompt);
+                self.is_loading = false;
+                self.save_current_model_buffers();
+                return Ok(());
+            }
+            self.last_failed_prompt = None;
+            // Trim only the finished answer: trimming while streaming would
+            // make the text jump whenever a chunk ends in a line break.
+            if self.trim_response {
+                let end = self.history.trim_end().len().max(answer_start);
+                self.history.truncate(end);
+            }
+            let mut footer = format_turn_footer(timings.as_ref(), started.elapsed());
+            // Ollama does not say why it stopped, but an answer as long as
+            // the cap was almost certainly cut off.
+            let generated = timings.as_ref().and_then(|t| t.eval_count);
+            if let (Some(cap), Some(count)) = (self.num_predict_for(&model), generated) {
+                if cap >= 0 && count >= cap as u64 {
+                    footer.push_str(&format!(" [truncated at {} tokens]", cap));
+                }
+            }
+            self.history
+                .push_str(&format!("\n{}{}\n{}\n", FOOTER_PREFIX, footer, TURN_SEPARATOR));
+            self.is_loading = false;
+            
+            // Speichere die finale History für dieses Modell
+            self.save_current_model_buffers();
+        }
+        Ok(())
+    }
+}
+
+/// Returns the note put at the start of an answer given by a fallback model
+/// in place of one `model` failed to give (see [`App::fallback_models`]).
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::fallback_note;
+///
+/// assert_eq!(fallback_note("llama3"), "[llama3 failed] ");
+/// ```
+pub fn fallback_note(model: &str) -> String {
+    format!("[{} failed] ", model)
+}
+
+/// Resolves a leading `~/` in `path` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/").zip(dirs::home_dir()) {
+        Some((rest, home)) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Formats the `[request debug]` block showing exactly what `request`
+/// sends, each line starting with [`REQUEST_DEBUG_PREFIX`].
+///
+/// Code fences in the prompt are broken up with zero-width spaces, so they
+/// do not open a code block in the transcript.
+fn request_debug_block(request: &GenerateRequest) -> String {
+    let temperature = request
+        .options
+        .temperature
+        .map_or("server default".to_string(), |t| format!("{:.1}", t));
+    let mut block = format!(
+        "{}[request debug] model: {}, temperature: {}\n",
+        REQUEST_DEBUG_PREFIX, request.model, temperature
+    );
+    for line in request.prompt.replace("```", "`\u{200B}`\u{200B}`").lines() {
+        block.push_str(REQUEST_DEBUG_PREFIX);
+        block.push_str(line);
+        block.push('\n');
+    }
+    block
+}
+
+/// Drives a response stream, the core of [`App::generate_into`] and
+/// [`App::send_query`].
+///
+/// `on_batch` receives the text of each received batch of chunks, and
+/// `None` whenever the model has been silent for [`ACTIVE_POLL_INTERVAL`].
+/// Line endings are normalized with [`utils::normalize_line_endings`],
+/// also when a `\r\n` is split between two batches.
+/// Returns the timings reported with the final chunk; errors of
+/// `on_batch` end the stream early.
+async fn pump_stream<F>(mut stream: ChunkStream, mut on_batch: F) -> Result<Option<GenerationTimings>>
+where
+    F: FnMut(Option<&str>) -> Result<()>,
+{
+    let mut timings = None;
+    // The last batch ended in `\r`, already passed on as `\n`.
+    let mut after_cr = false;
+    loop {
+        let res = match timeout(ACTIVE_POLL_INTERVAL, stream.next()).await {
+            Ok(Some(res)) => res,
+            Ok(None) => return Ok(timings),
+            Err(_) => {
+                on_batch(None)?;
+                continue;
+            }
+        };
+        let chunks = res.map_err(|e| LazyLlamaError::Stream(e.into()))?;
+        let mut text = String::new();
+        for chunk in chunks {
+            text.push_str(&chunk.text);
+            timings = chunk.timings.or(timings);
+        }
+        let raw = match text.strip_prefix('\n') {
+            Some(rest) if after_cr => rest,
+            _ => &text,
+        };
+        if !raw.is_empty() {
+            after_cr = raw.ends_with('\r');
+        }
+        on_batch(Some(&utils::normalize_line_endings(raw)))?;
+    }
+}
+
+/// Builder for [`App`], created with [`App::builder`].
+///
+/// Every setting has the same default as a fresh [`App::new`] session
+/// without environment variables or config file, using a default
+/// [`Ollama`] client.
+#[derive(Default)]
+pub struct AppBuilder {
+    client: Option<Box<dyn ModelClient>>,
+    models: Vec<String>,
+    poll_mode: PollMode,
+    debug_keys: bool,
+    ephemeral: bool,
+    config: Config,
+    data_dir: Option<PathBuf>,
+    config_path: Option<PathBuf>,
+    recent_models: Vec<String>,
+    client_factory: Option<ClientFactory>,
+}
+
+impl AppBuilder {
+    /// Sets the model backend.
+    pub fn client(mut self, client: impl ModelClient + 'static) -> Self {
+        self.client = Some(Box::new(client));
+        self
+    }
+
+    /// Sets how clients are created when switching hosts; defaults to
+    /// [`client::ollama_client`].
+    pub fn client_factory(
+        mut self,
+        factory: impl Fn(&str) -> anyhow::Result<Box<dyn ModelClient>> + Send + Sync + 'static,
+    ) -> Self {
+        self.client_factory = Some(Box::new(factory));
+        self
+    }
+
+    /// Presets the model list instead of querying the client; the first
+    /// model is selected.
+    pub fn models(mut self, models: Vec<String>) -> Self {
+        self.models = models;
+        self
+    }
+
+    /// Sets the event loop poll strategy.
+    pub fn poll_mode(mut self, poll_mode: PollMode) -> Self {
+        self.poll_mode = poll_mode;
+        self
+    }
+
+    /// Enables on-screen key debugging.
+    pub fn debug_keys(mut self, debug_keys: bool) -> Self {
+        self.debug_keys = debug_keys;
+        self
+    }
+
+    /// Makes the session ephemeral, so no files are written.
+    pub fn ephemeral(mut self, ephemeral: bool) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
+    /// Sets the directory conversations are archived to.
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the config file the custom model order is saved to.
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Sets the models used in earlier sessions, most recent first.
+    pub fn recent_models(mut self, models: Vec<String>) -> Self {
+        self.recent_models = models;
+        self
+    }
+
+    /// Applies settings from a loaded configuration file.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Builds the application without contacting the model server.
+    ///
+    /// Without an explicit client, the first configured host is used, or
+    /// the default local Ollama server.
+    pub fn build(self) -> App {
+        let client_factory = self
+            .client_factory
+            .unwrap_or_else(|| Box::new(client::ollama_client));
+        let client = self
+            .client
+            .unwrap_or_else(|| client::startup_client(&self.config.hosts, &client_factory));
+        let mut app = App {
+            models: Vec::new(),
+            list_state: ListState::default(),
+            input: String::new(),
+            cursor_pos: 0,
+            history: String::new(),
+            model_inputs: HashMap::new(),
+            model_cursors: HashMap::new(),
+            model_histories: HashMap::new(),
+            model_scrolls: HashMap::new(),
+            scroll: 0,
+            bookmark: None,
+            model_bookmarks: HashMap::new(),
+            autoscroll: true,
+            autoscroll_mode: self.config.autoscroll_mode,
+            is_loading: false,
+            client,
+            start_time: Instant::now(),
+            last_cursor_blink: Instant::now(),
+            cursor_visible: true,
+            debug_keys: self.debug_keys,
+            echo_requests: false,
+            debug_last_key: None,
+            render_count: 0,
+            last_render_us: 0,
+            last_append_us: 0,
+            poll_mode: self.poll_mode,
+            status_message: None,
+            last_activity: HashMap::new(),
+            mru_selection: None,
+            snippets: self.config.snippets,
+            show_help: false,
+            focus_mode: false,
+            compact: self.config.compact,
+            code_block_style: self.config.code_block_style,
+            emphasis: Emphasis::default(),
+            server_version: None,
+            timestamp_format: self.config.timestamp_format.clone(),
+            code_language: self.config.code_language.clone(),
+            yank_lead_in: self.config.yank_lead_in.clone(),
+            prompt_template: self.config.prompt_template,
+            greeting: self.config.greeting,
+            model_templates: self.config.model_templates,
+            collapsed: BTreeSet::new(),
+            model_collapsed: HashMap::new(),
+            focused_exchange: None,
+            scroll_to_focus: false,
+            regenerate_diff: self.config.regenerate_diff,
+            previous_answer: None,
+            data_dir: self.data_dir,
+            all_models: Vec::new(),
+            model_allowlist: self.config.models,
+            show_all_models: false,
+            model_order: self.config.model_order,
+            config_path: self.config_path,
+            send_key: self.config.send_key,
+            ephemeral: self.ephemeral,
+            trim_response: self.config.trim_response,
+            labels: self.config.labels,
+            recent_models: self.recent_models,
+            last_failed_prompt: None,
+            last_error: None,
+            empty_response: false,
+            last_request: None,
+            stream_start: None,
+            waiting_since: None,
+            thinking_hint_after: Duration::from_secs(self.config.thinking_hint_after),
+            thinking_hint: self.config.thinking_hint,
+            model_was_loaded: false,
+            context_limit: self.config.context_limit,
+            context_limits: self.config.context_limits,
+            num_predict: self.config.num_predict,
+            num_predicts: self.config.num_predicts,
+            shared_input: self.config.shared_input,
+            restore_histories: self.config.restore_histories,
+            histories_loading: false,
+            confirm_quit: self.config.confirm_quit,
+            confirm_quit_after: self.config.confirm_quit_after,
+            confirm_commands: self.config.confirm_commands.clone(),
+            pending_command: None,
+            quit_confirmation: None,
+            history_reload: None,
+            model_options: HashMap::new(),
+            log_manager: None,
+            hosts: self.config.hosts,
+            active_host: 0,
+            host_selection: None,
+            host_buffers: HashMap::new(),
+            client_factory,
+            restore_drafts: self.config.restore_drafts,
+            max_chat_logs: self.config.max_chat_logs,
+            log_format: self.config.log_format,
+            export_dir: expand_home(&self.config.export_dir),
+            status_clock: self.config.status_clock,
+            status_session: self.config.status_session,
+            clock_second: 0,
+            plain: self.config.plain,
+            quiet: self.config.quiet,
+            pending_prompts: VecDeque::new(),
+            key_source: None,
+            deferred_keys: VecDeque::new(),
+            resume_interrupted: self.config.resume_interrupted,
+            fallback_models: self.config.fallback_models.clone(),
+            copy_regions: Vec::new(),
+            number_turns: self.config.number_turns,
+            text_direction: self.config.text_direction,
+            model_info_cache: HashMap::new(),
+            model_info_popup: None,
+            model_filter: None,
+            fuzzy_filter: self.config.fuzzy_filter,
+            keep_unavailable_models: self.config.keep_unavailable_models,
+        };
+        app.set_models(self.models);
+        app
+    }
+}
@@ -0,0 +1,638 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+//! Model backend abstraction.
+//!
+//! [`App`](crate::app::App) talks to models exclusively through the
+//! [`ModelClient`] trait. The production implementation is the `ollama-rs`
+//! [`Ollama`] client; [`ScriptedClient`] replays canned responses so the
+//! streaming path can be exercised in tests without a running server.
+
+use crate::config::Host;
+use anyhow::Result;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use ollama_rs::{
+    generation::completion::request::GenerationRequest, models::ModelOptions, Ollama,
+};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+
+/// Boxed future returned by [`ModelClient`] methods.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Stream of response chunks produced by [`ModelClient::generate_stream`].
+///
+/// Each item carries all chunks that arrived together over the network.
+pub type ChunkStream = Pin<Box<dyn Stream<Item = Result<Vec<GenerationChunk>>> + Send>>;
+
+/// Creates the client for a host URL when switching hosts.
+pub type ClientFactory = Box<dyn Fn(&str) -> Result<Box<dyn ModelClient>> + Send + Sync>;
+
+/// Base URL of the local Ollama server, used when no host is configured.
+pub const DEFAULT_HOST_URL: &str = "http://localhost:11434";
+
+/// Sampling options sent with a request; `None` keeps the server default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerateOptions {
+    /// Sampling temperature; higher values give more varied answers.
+    pub temperature: Option<f32>,
+    /// Most tokens to generate (`num_predict`); `-1` means no limit.
+    pub num_predict: Option<i32>,
+}
+
+/// A generation request sent to a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateRequest {
+    /// Name of the model that should answer.
+    pub model: String,
+    /// Prompt text as sent to the model.
+    pub prompt: String,
+    /// Sampling options for this request.
+    pub options: GenerateOptions,
+}
+
+impl GenerateRequest {
+    /// Creates a request for `model` with the given prompt and the server's
+    /// default options.
+    pub fn new(model: impl Into<String>, prompt: impl Into<String>) -> Self {
+        GenerateRequest {
+            model: model.into(),
+            prompt: prompt.into(),
+            options: GenerateOptions::default(),
+        }
+    }
+
+    /// Sets the sampling options.
+    pub fn with_options(mut self, options: GenerateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns a `curl` command that sends this request to the Ollama
+    /// server at `host`, e.g. to reproduce it in a bug report.
+    ///
+    /// The full prompt is included as is. The command is quoted for POSIX
+    /// shells.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::client::GenerateRequest;
+    ///
+    /// let request = GenerateRequest::new("llama3", "It's me");
+    /// assert_eq!(
+    ///     request.curl_command("http://localhost:11434/"),
+    ///     r#"curl -N http://localhost:11434/api/generate -d '{"model":"llama3","prompt":"It'\''s me"}'"#
+    /// );
+    /// ```
+    pub fn curl_command(&self, host: &str) -> String {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "prompt": self.prompt,
+        });
+        let GenerateOptions { temperature, num_predict } = self.options;
+        if temperature.is_some() || num_predict.is_some() {
+            let mut options = serde_json::Map::new();
+            if let Some(temperature) = temperature {
+                options.insert("temperature".to_string(), temperature.into());
+            }
+            if let Some(num_predict) = num_predict {
+                options.insert("num_predict".to_string(), num_predict.into());
+            }
+            body["options"] = options.into();
+        }
+        format!(
+            "curl -N {}/api/generate -d '{}'",
+            host.trim_end_matches('/'),
+            body.to_string().replace('\'', "'\\''")
+        )
+    }
+}
+
+/// Server-side timing breakdown of a finished generation.
+///
+/// Every field is optional because servers only report what they measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GenerationTimings {
+    /// Time spent loading the model into memory.
+    pub load: Option<Duration>,
+    /// Time spent evaluating the prompt.
+    pub prompt_eval: Option<Duration>,
+    /// Time spent generating the answer.
+    pub eval: Option<Duration>,
+    /// Number of generated tokens.
+    pub eval_count: Option<u64>,
+}
+
+impl GenerationTimings {
+    /// Whether no timing was reported at all.
+    pub fn is_empty(&self) -> bool {
+        self.load.is_none() && self.prompt_eval.is_none() && self.eval.is_none()
+    }
+}
+
+/// A piece of a streamed model response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenerationChunk {
+    /// Text produced by the model in this chunk.
+    pub text: String,
+    /// Whether this is the final chunk of the response.
+    pub done: bool,
+    /// Timing breakdown, usually only present on the final chunk.
+    pub timings: Option<GenerationTimings>,
+}
+
+impl GenerationChunk {
+    /// Creates an intermediate chunk carrying `text`.
+    pub fn text(text: impl Into<String>) -> Self {
+        GenerationChunk {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Details of an installed model, as shown in the model info popup.
+///
+/// Every field is optional, as servers and model files leave some out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// Model family, e.g. `llama`.
+    pub family: Option<String>,
+    /// Parameter count as reported, e.g. `8.0B`.
+    pub parameter_size: Option<String>,
+    /// Quantization level, e.g. `Q4_0`.
+    pub quantization: Option<String>,
+    /// Longest context the model was trained for, in tokens.
+    pub context_length: Option<u64>,
+    /// First line of the model's license.
+    pub license: Option<String>,
+}
+
+/// Operations LazyLlama needs from a model server.
+pub trait ModelClient: Send + Sync {
+    /// Lists the names of all locally available models.
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>>;
+
+    /// Starts a streaming generation for `request`.
+    fn generate_stream(&self, request: GenerateRequest) -> BoxFuture<'_, Result<ChunkStream>>;
+
+    /// Returns the server's version, e.g. `0.5.7`.
+    ///
+    /// The default implementation reports that the version is unknown.
+    fn server_version(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async { Err(anyhow::anyhow!("server version unknown")) })
+    }
+
+    /// Lists the models currently loaded on the server.
+    ///
+    /// The default implementation reports that this is unknown.
+    fn running_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async { Err(anyhow::anyhow!("running models unknown")) })
+    }
+
+    /// Returns the details of `model`.
+    ///
+    /// The default implementation reports that details are unavailable.
+    fn model_info<'a>(&'a self, model: &'a str) -> BoxFuture<'a, Result<ModelInfo>> {
+        let _ = model;
+        Box::pin(async { Err(anyhow::anyhow!("model details unavailable")) })
+    }
+}
+
+/// Body of Ollama's `/api/version` response.
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
+/// Body of Ollama's `/api/ps` response.
+#[derive(Deserialize)]
+struct RunningResponse {
+    models: Vec<RunningModel>,
+}
+
+/// A loaded model in Ollama's `/api/ps` response.
+#[derive(Deserialize)]
+struct RunningModel {
+    name: String,
+}
+
+/// Body of an Ollama `/api/show` request.
+#[derive(Serialize)]
+struct ShowRequest<'a> {
+    model: &'a str,
+}
+
+/// Body of Ollama's `/api/show` response, as far as [`ModelInfo`] needs it.
+#[derive(Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    license: String,
+    #[serde(default)]
+    details: ShowDetails,
+    /// GGUF metadata; the context length is under `<architecture>.context_length`.
+    #[serde(default)]
+    model_info: HashMap<String, MetadataValue>,
+}
+
+/// The `details` object of Ollama's `/api/show` response.
+#[derive(Deserialize, Default)]
+struct ShowDetails {
+    #[serde(default)]
+    family: String,
+    #[serde(default)]
+    parameter_size: String,
+    #[serde(default)]
+    quantization_level: String,
+}
+
+/// A GGUF metadata value; only numbers are of interest.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MetadataValue {
+    Number(u64),
+    #[allow(dead_code)]
+    Other(IgnoredAny),
+}
+
+impl From<ShowResponse> for ModelInfo {
+    fn from(response: ShowResponse) -> Self {
+        let non_empty = |s: String| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        let context_length = response.model_info.iter().find_map(|(key, value)| match value {
+            MetadataValue::Number(n) if key.ends_with(".context_length") => Some(*n),
+            _ => None,
+        });
+        let license = response.license.lines().map(str::trim).find(|l| !l.is_empty()).map(String::from);
+        ModelInfo {
+            family: non_empty(response.details.family),
+            parameter_size: non_empty(response.details.parameter_size),
+            quantization: non_empty(response.details.quantization_level),
+            context_length,
+            license,
+        }
+    }
+}
+
+/// Whether `version` is at least `minimum`, comparing dot-separated
+/// numbers.
+///
+/// Missing components count as zero and anything after the numbers (like
+/// `-rc1`) is ignored, so pre-releases count as the release.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::client::version_at_least;
+///
+/// assert!(version_at_least("0.5.7", "0.5"));
+/// assert!(version_at_least("0.10.0", "0.9.2"));
+/// assert!(!version_at_least("0.4.9", "0.5.0"));
+/// ```
+pub fn version_at_least(version: &str, minimum: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    };
+    let (version, minimum) = (parse(version), parse(minimum));
+    let len = version.len().max(minimum.len());
+    let component = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| component(&version, i).cmp(&component(&minimum, i)))
+        .find(|ordering| ordering.is_ne())
+        .is_none_or(|ordering| ordering.is_gt())
+}
+
+impl ModelClient for Ollama {
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move {
+            let models = self.list_local_models().await?;
+            Ok(models.into_iter().map(|m| m.name).collect())
+        })
+    }
+
+    fn generate_stream(&self, request: GenerateRequest) -> BoxFuture<'_, Result<ChunkStream>> {
+        Box::pin(async move {
+            let mut generation = GenerationRequest::new(request.model, request.prompt);
+            let GenerateOptions { temperature, num_predict } = request.options;
+            if temperature.is_some() || num_predict.is_some() {
+                let mut options = ModelOptions::default();
+                if let Some(temperature) = temperature {
+                    options = options.temperature(temperature);
+                }
+                if let Some(num_predict) = num_predict {
+                    options = options.num_predict(num_predict);
+                }
+                generation = generation.options(options);
+            }
+            let stream = Ollama::generate_stream(self, generation).await?;
+            let chunks = stream.map(|res| {
+                res.map(|responses| {
+                    responses
+                        .into_iter()
+                        .map(|resp| {
+                            let timings = resp.done.then(|| GenerationTimings {
+                                load: resp.load_duration.map(Duration::from_nanos),
+                                prompt_eval: resp.prompt_eval_duration.map(Duration::from_nanos),
+                                eval: resp.eval_duration.map(Duration::from_nanos),
+                                eval_count: resp.eval_count,
+                            });
+                            GenerationChunk {
+                                text: resp.response,
+                                done: resp.done,
+                                timings,
+                            }
+                        })
+                        .collect()
+                })
+                .map_err(anyhow::Error::from)
+            });
+            Ok(Box::pin(chunks) as ChunkStream)
+        })
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            let url = self.url().join("api/version")?;
+            let response: VersionResponse = reqwest::get(url)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(response.version)
+        })
+    }
+
+    fn running_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move {
+            let url = self.url().join("api/ps")?;
+            let response: RunningResponse = reqwest::get(url)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(response.models.into_iter().map(|m| m.name).collect())
+        })
+    }
+
+    fn model_info<'a>(&'a self, model: &'a str) -> BoxFuture<'a, Result<ModelInfo>> {
+        Box::pin(async move {
+            let url = self.url().join("api/show")?;
+            let response: ShowResponse = reqwest::Client::new()
+                .post(url)
+                .json(&ShowRequest { model })
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(response.into())
+        })
+    }
+}
+
+/// Creates an [`Ollama`] client for the server at `url`.
+///
+/// # Errors
+///
+/// Returns an error if `url` is not a valid URL.
+pub fn ollama_client(url: &str) -> Result<Box<dyn ModelClient>> {
+    Ok(Box::new(Ollama::try_new(url)?))
+}
+
+/// Creates the client a session starts with: one made by `factory` for the
+/// first of `hosts`, or one for the local Ollama server if no host is
+/// configured or the first one's URL is invalid.
+pub fn startup_client(
+    hosts: &[Host],
+    factory: &dyn Fn(&str) -> Result<Box<dyn ModelClient>>,
+) -> Box<dyn ModelClient> {
+    hosts
+        .first()
+        .and_then(|host| factory(&host.url).ok())
+        .unwrap_or_else(|| Box::new(Ollama::default()))
+}
+
+/// Writes the names of all models installed on `client` to `out`, one per
+/// line, as printed by `lazyllama --list-models`.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached or `out` fails.
+pub async fn write_model_list(client: &dyn ModelClient, out: &mut dyn Write) -> Result<()> {
+    for model in client.list_models().await? {
+        writeln!(out, "{}", model)?;
+    }
+    Ok(())
+}
+
+/// Outcome of one scripted generation.
+#[derive(Debug, Clone)]
+enum Script {
+    Respond(Vec<String>, Option<GenerationTimings>),
+    Fail(String),
+    Interrupt(Vec<String>, String),
+}
+
+/// A [`ModelClient`] that replays scripted responses, for tests and demos.
+///
+/// Each call to [`ModelClient::generate_stream`] consumes the next scripted
+/// outcome in order; when the script is exhausted an empty response is
+/// streamed. Every request is recorded and can be inspected through the
+/// shared log returned by [`ScriptedClient::request_log`].
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::client::ScriptedClient;
+///
+/// let client = ScriptedClient::new(vec!["mock".to_string()])
+///     .with_response(vec!["Hel", "lo"])
+///     .with_error("connection refused");
+/// let requests = client.request_log();
+/// assert!(requests.lock().unwrap().is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct ScriptedClient {
+    models: Arc<Mutex<Vec<String>>>,
+    script: Mutex<VecDeque<Script>>,
+    requests: Arc<Mutex<Vec<GenerateRequest>>>,
+    version: Option<String>,
+    running: Option<Vec<String>>,
+    info: HashMap<String, ModelInfo>,
+}
+
+impl ScriptedClient {
+    /// Creates a client that reports `models` as installed.
+    pub fn new(models: Vec<String>) -> Self {
+        ScriptedClient {
+            models: Arc::new(Mutex::new(models)),
+            ..Default::default()
+        }
+    }
+
+    /// Appends a response streamed as the given chunks.
+    pub fn with_response<S: Into<String>>(self, chunks: Vec<S>) -> Self {
+        let chunks = chunks.into_iter().map(Into::into).collect();
+        self.script.lock().unwrap().push_back(Script::Respond(chunks, None));
+        self
+    }
+
+    /// Appends a response whose final chunk reports `timings`.
+    pub fn with_timed_response<S: Into<String>>(
+        self,
+        chunks: Vec<S>,
+        timings: GenerationTimings,
+    ) -> Self {
+        let chunks = chunks.into_iter().map(Into::into).collect();
+        self.script
+            .lock()
+            .unwrap()
+            .push_back(Script::Respond(chunks, Some(timings)));
+        self
+    }
+
+    /// Appends a failed request with the given error message.
+    pub fn with_error(self, message: impl Into<String>) -> Self {
+        self.script.lock().unwrap().push_back(Script::Fail(message.into()));
+        self
+    }
+
+    /// Appends a response that breaks off with the given error message
+    /// after streaming `chunks`.
+    pub fn with_interrupted_response<S: Into<String>>(
+        self,
+        chunks: Vec<S>,
+        message: impl Into<String>,
+    ) -> Self {
+        let chunks = chunks.into_iter().map(Into::into).collect();
+        self.script
+            .lock()
+            .unwrap()
+            .push_back(Script::Interrupt(chunks, message.into()));
+        self
+    }
+
+    /// Makes the client report `version` as its server version.
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Makes the client report `models` as loaded on the server.
+    pub fn with_running_models(mut self, models: Vec<String>) -> Self {
+        self.running = Some(models);
+        self
+    }
+
+    /// Makes the client report `info` as the details of `model`; other
+    /// models have none.
+    pub fn with_model_info(mut self, model: impl Into<String>, info: ModelInfo) -> Self {
+        self.info.insert(model.into(), info);
+        self
+    }
+
+    /// Returns a handle to the installed models, to simulate models being
+    /// pulled or removed while the client is in use.
+    pub fn installed_models(&self) -> Arc<Mutex<Vec<String>>> {
+        Arc::clone(&self.models)
+    }
+
+    /// Returns a handle to the log of all requests received so far.
+    pub fn request_log(&self) -> Arc<Mutex<Vec<GenerateRequest>>> {
+        Arc::clone(&self.requests)
+    }
+}
+
+impl ModelClient for ScriptedClient {
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move { Ok(self.models.lock().unwrap().clone()) })
+    }
+
+    fn server_version(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            self.version
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("server version unknown"))
+        })
+    }
+
+    fn running_models(&self) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(async move {
+            self.running
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("running models unknown"))
+        })
+    }
+
+    fn model_info<'a>(&'a self, model: &'a str) -> BoxFuture<'a, Result<ModelInfo>> {
+        Box::pin(async move {
+            self.info
+                .get(model)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("model \"{}\" not found", model))
+        })
+    }
+
+    fn generate_stream(&self, request: GenerateRequest) -> BoxFuture<'_, Result<ChunkStream>> {
+        Box::pin(async move {
+            self.requests.lock().unwrap().push(request);
+            let next = self.script.lock().unwrap().pop_front();
+            match next.unwrap_or(Script::Respond(Vec::new(), None)) {
+                Script::Respond(chunks, timings) => {
+                    let mut items: Vec<Result<Vec<GenerationChunk>>> = chunks
+                        .into_iter()
+                        .map(|text| Ok(vec![GenerationChunk::text(text)]))
+                        .collect();
+                    items.push(Ok(vec![GenerationChunk {
+                        text: String::new(),
+                        done: true,
+                        timings,
+                    }]));
+                    Ok(Box::pin(tokio_stream::iter(items)) as ChunkStream)
+                }
+                Script::Fail(message) => Err(anyhow::anyhow!(message)),
+                Script::Interrupt(chunks, message) => {
+                    let mut items: Vec<Result<Vec<GenerationChunk>>> = chunks
+                        .into_iter()
+                        .map(|text| Ok(vec![GenerationChunk::text(text)]))
+                        .collect();
+                    items.push(Err(anyhow::anyhow!(message)));
+                    Ok(Box::pin(tokio_stream::iter(items)) as ChunkStream)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,144 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+//! Clipboard integration and copy helpers.
+//!
+//! Copy actions always work on the raw conversation source (`App::history`
+//! or blocks extracted from it), never on the rendered [`ratatui::text::Text`].
+//! The helpers in this module additionally strip any rendering decorations
+//! (code frames, gutters, header bullets) that may have ended up in the text,
+//! so what lands on the clipboard is clean, pasteable source.
+//!
+//! The clipboard itself is written through the OSC 52 terminal escape
+//! sequence, which works in most modern terminals and over SSH without any
+//! platform-specific clipboard libraries.
+
+use anyhow::Result;
+use std::io::{self, Write};
+
+/// Prefix of the rendered code block header (`┌── lang ──`).
+const FRAME_TOP: &str = "┌──";
+/// Prefix of the rendered code block footer (`└──────────`).
+const FRAME_BOTTOM: &str = "└──";
+/// Gutter prepended to every rendered code line.
+const GUTTER: &str = "│ ";
+/// Bullet used when rendering `###` headers.
+const HEADER_BULLET: &str = "● ";
+
+/// Returns the clean source of a code block, ready for the clipboard.
+///
+/// Accepts either the bare code of a block (as returned by
+/// [`crate::ui::extract_code_blocks`]) or a complete fenced block. Fence
+/// lines, rendered frame lines and gutter prefixes are removed and trailing
+/// newlines are trimmed; indentation inside the code is preserved.
+pub fn clean_code(block: &str) -> String {
+    let lines: Vec<&str> = block
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .filter(|line| !is_frame_line(line))
+        .collect();
+    let gutter_rendered = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.trim_start().starts_with(GUTTER));
+    let lines: Vec<&str> = if gutter_rendered {
+        lines.into_iter().map(strip_gutter).collect()
+    } else {
+        lines
+    };
+    lines.join("\n").trim_end_matches('\n').to_string()
+}
+
+/// Returns the conversation history as clean Markdown for the clipboard.
+///
+/// Rendered frame lines are dropped, code gutters are removed and header
+/// bullets are turned back into `###` headers. Trailing whitespace on each
+/// line and blank lines around the whole transcript are trimmed.
+pub fn clean_markdown(history: &str) -> String {
+    let mut in_frame = false;
+    let mut lines = Vec::new();
+    for line in history.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(FRAME_TOP) {
+            in_frame = true;
+            continue;
+        }
+        if trimmed.starts_with(FRAME_BOTTOM) {
+            in_frame = false;
+            continue;
+        }
+        let line = if in_frame { strip_gutter(line) } else { line };
+        let line = match line.trim_start().strip_prefix(HEADER_BULLET) {
+            Some(header) => format!("### {}", header),
+            None => line.to_string(),
+        };
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+/// Builds the OSC 52 escape sequence that sets the system clipboard to `text`.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+/// Copies `text` to the system clipboard via the terminal (OSC 52).
+///
+/// # Errors
+///
+/// Returns an error if writing to or flushing stdout fails.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn is_frame_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with(FRAME_TOP) || trimmed.starts_with(FRAME_BOTTOM)
+}
+
+fn strip_gutter(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    trimmed
+        .strip_prefix(GUTTER)
+        .or_else(|| trimmed.strip_prefix(GUTTER.trim_end()))
+        .unwrap_or(line)
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
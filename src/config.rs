@@ -0,0 +1,520 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+
+//! User configuration.
+//!
+//! LazyLlama reads an optional TOML file from the platform configuration
+//! directory (`~/.config/lazyllama/config.toml` on Linux). Every setting has
+//! a default, so a missing file or missing keys are never an error.
+//!
+//! # Example
+//!
+//! ```toml
+//! models = ["llama3:8b", "qwen2.5-coder:7b"]
+//! prompt_template = "Answer concisely. {prompt}"
+//! regenerate_diff = true
+//! send_key = "ctrl-enter"
+//!
+//! [[snippets]]
+//! name = "Summarize"
+//! text = "Summarize this: "
+//!
+//! [[snippets]]
+//! name = "Test"
+//! text = "Write a unit test for: "
+//!
+//! [[hosts]]
+//! name = "laptop"
+//! url = "http://localhost:11434"
+//!
+//! [[hosts]]
+//! name = "gpu-box"
+//! url = "http://192.168.1.20:11434"
+//!
+//! [labels]
+//! user_icon = "🧑"
+//! ai_icon = "🦙"
+//!
+//! [model_templates]
+//! "llama3:8b" = "{prompt}\n\nRespond in Markdown."
+//!
+//! [context_limits]
+//! "llama3:8b" = 8192
+//!
+//! [num_predicts]
+//! "llama3:8b" = 512
+//! ```
+
+use crate::app::CLEAR_COMMAND;
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{value, Array, DocumentMut};
+
+/// Placeholder that prompt templates replace with the user's prompt.
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+
+/// Number of snippets reachable through the `Alt+1` … `Alt+9` shortcuts.
+pub const MAX_SNIPPETS: usize = 9;
+
+/// Default for [`Config::thinking_hint_after`], in seconds.
+pub const DEFAULT_THINKING_HINT_AFTER: u64 = 10;
+
+/// Default for [`Config::thinking_hint`].
+pub const DEFAULT_THINKING_HINT: &str = "still working — large models can take a while";
+
+/// Default for [`Config::confirm_quit_after`], in characters.
+pub const DEFAULT_CONFIRM_QUIT_AFTER: usize = 2000;
+
+/// Default for [`Config::timestamp_format`], e.g. `2026-03-14 09:26`.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// A saved piece of prompt text that can be inserted into the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Snippet {
+    /// Short label shown in the help overlay.
+    pub name: String,
+    /// Text inserted at the cursor.
+    pub text: String,
+}
+
+/// A named Ollama server that can be switched to at runtime.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Host {
+    /// Name shown in the host picker and the status bar.
+    pub name: String,
+    /// Base URL of the server, e.g. `http://localhost:11434`.
+    pub url: String,
+}
+
+/// Optional icons shown in front of the `YOU:` and `AI:` labels.
+///
+/// Icons are display-only: the history keeps the plain labels, so saved
+/// conversations parse the same with or without icons.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Labels {
+    /// Icon before user turns, e.g. `"🧑"`. Empty shows no icon.
+    pub user_icon: String,
+    /// Icon before AI turns, e.g. `"🦙"`. Empty shows no icon.
+    pub ai_icon: String,
+}
+
+/// Key combination that sends the input; see [`SendKey::sends`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SendKey {
+    /// `Enter` sends, `Ctrl+Enter` inserts a line break.
+    #[default]
+    Enter,
+    /// `Ctrl+Enter` sends, `Enter` inserts a line break.
+    CtrlEnter,
+}
+
+impl SendKey {
+    /// Whether an `Enter` press with the given modifiers sends the input
+    /// rather than inserting a line break.
+    ///
+    /// `Shift+Enter` always inserts a line break, whatever the setting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::config::SendKey;
+    ///
+    /// assert!(SendKey::Enter.sends(false, false));
+    /// assert!(!SendKey::Enter.sends(true, false));
+    /// assert!(SendKey::CtrlEnter.sends(true, false));
+    /// assert!(!SendKey::CtrlEnter.sends(false, true));
+    /// ```
+    pub fn sends(self, ctrl: bool, shift: bool) -> bool {
+        !shift && ctrl == (self == SendKey::CtrlEnter)
+    }
+}
+
+/// How fenced code blocks are drawn in the transcript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodeBlockStyle {
+    /// A `┌── lang ──` header, a `│` gutter and a `└──` footer.
+    #[default]
+    Bordered,
+    /// Only a `▎` gutter and a dim language label: no bottom border, and
+    /// one column less for the gutter.
+    Minimal,
+}
+
+/// Where autoscroll keeps the conversation view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoscrollMode {
+    /// Stick to the bottom of the transcript.
+    #[default]
+    Bottom,
+    /// Follow the bottom only until the last answer's first line reaches
+    /// the top of the view, so long answers are read from the start.
+    AnswerStart,
+}
+
+/// Format of the logs saved on exit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// The transcript as plain text.
+    #[default]
+    Txt,
+    /// The transcript as Markdown, as copied with `Ctrl+Y`.
+    Md,
+    /// The exchanges as JSON, for scripts.
+    Json,
+}
+
+impl LogFormat {
+    /// File extension of logs in this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            LogFormat::Txt => "txt",
+            LogFormat::Md => "md",
+            LogFormat::Json => "json",
+        }
+    }
+}
+
+/// Direction the conversation is laid out in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextDirection {
+    /// Right-align turns written mostly in a right-to-left script
+    /// (Hebrew, Arabic, …) and left-align the rest.
+    #[default]
+    Auto,
+    /// Left-align every turn.
+    Ltr,
+    /// Right-align every turn.
+    Rtl,
+}
+
+/// Settings loaded from `config.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Prompt snippets, bound to `Alt+1` … `Alt+9` in file order.
+    pub snippets: Vec<Snippet>,
+    /// Template wrapped around every prompt sent to a model. Must contain
+    /// [`PROMPT_PLACEHOLDER`].
+    pub prompt_template: Option<String>,
+    /// Per-model templates, taking precedence over `prompt_template`.
+    pub model_templates: HashMap<String, String>,
+    /// Primer put before the first prompt of every empty conversation,
+    /// visible in the chat.
+    pub greeting: Option<String>,
+    /// Show a word diff against the replaced answer after regenerating.
+    pub regenerate_diff: bool,
+    /// Models to show in the model list, in this order. Empty shows all.
+    pub models: Vec<String>,
+    /// Custom model order set with `Shift+Up`/`Shift+Down`; listed models
+    /// are shown first. Written by [`save_model_order`].
+    pub model_order: Vec<String>,
+    /// Whether `Enter` or `Ctrl+Enter` sends the input.
+    pub send_key: SendKey,
+    /// Strip trailing blank lines and whitespace from finished answers.
+    pub trim_response: bool,
+    /// Icons shown in front of the turn labels.
+    pub labels: Labels,
+    /// Ollama servers to choose from; the first one is used on startup.
+    /// Empty uses the default local server.
+    pub hosts: Vec<Host>,
+    /// Seconds without a first token after which `thinking_hint` is shown;
+    /// `0` never shows it.
+    pub thinking_hint_after: u64,
+    /// Reassurance shown while a slow model has not answered yet.
+    pub thinking_hint: String,
+    /// Context window (`num_ctx`) in tokens assumed for every model, used
+    /// to warn about prompts that may not fit. `None` never warns.
+    pub context_limit: Option<usize>,
+    /// Per-model context windows, taking precedence over `context_limit`.
+    pub context_limits: HashMap<String, usize>,
+    /// Most tokens a model may generate per answer (`num_predict`). `None`
+    /// lets the model decide.
+    pub num_predict: Option<i32>,
+    /// Per-model answer caps, taking precedence over `num_predict`.
+    pub num_predicts: HashMap<String, i32>,
+    /// Keep one draft input for all models instead of one per model.
+    pub shared_input: bool,
+    /// Reload each model's last saved conversation on startup.
+    pub restore_histories: bool,
+    /// Start with the compact transcript (toggled with `F5`).
+    pub compact: bool,
+    /// How code blocks are drawn.
+    pub code_block_style: CodeBlockStyle,
+    /// `strftime` format of the timestamp inserted with `Ctrl+D`.
+    pub timestamp_format: String,
+    /// Language of the first code block template inserted with `Alt+K`;
+    /// later ones use the language of the last code block sent.
+    pub code_language: String,
+    /// Ask before `Ctrl+Q` quits a session with longer conversations.
+    pub confirm_quit: bool,
+    /// Characters of conversation above which `confirm_quit` asks.
+    pub confirm_quit_after: usize,
+    /// Input commands, such as `/clear`, that only run when sent twice in
+    /// a row.
+    pub confirm_commands: Vec<String>,
+    /// Text put before the last answer when `Ctrl+P` copies it into the
+    /// input.
+    pub yank_lead_in: String,
+    /// Save unsent inputs on exit and restore them on startup.
+    pub restore_drafts: bool,
+    /// Most combined `chat_*` logs to keep; older ones are deleted on
+    /// exit. `None` keeps all of them.
+    pub max_chat_logs: Option<usize>,
+    /// Format of the logs saved on exit.
+    pub log_format: LogFormat,
+    /// Show a clock in the status bar, redrawn every second even when idle.
+    pub status_clock: bool,
+    /// Show how long the session has been running in the status bar,
+    /// redrawn every second like the clock.
+    pub status_session: bool,
+    /// Plain rendering for screen readers: no borders, box drawing,
+    /// colors or fake cursor (also set with `LAZYLLAMA_PLAIN=1`).
+    pub plain: bool,
+    /// Directory `Ctrl+X` exports code blocks to; relative paths start at
+    /// the working directory.
+    pub export_dir: String,
+    /// Re-issue a request whose answer stream broke off, asking the model
+    /// to continue the partial answer. Off by default, as the prompt and
+    /// partial answer are sent again.
+    pub resume_interrupted: bool,
+    /// Models that answer instead, in this order, when a request fails
+    /// before any text arrived, e.g. because the model is not installed.
+    pub fallback_models: Vec<String>,
+    /// Number the exchanges in the transcript (`#1`, `#2`, …).
+    pub number_turns: bool,
+    /// Where autoscroll keeps the view.
+    pub autoscroll_mode: AutoscrollMode,
+    /// Whether turns are right-aligned for right-to-left languages.
+    pub text_direction: TextDirection,
+    /// Match the model filter (`Ctrl+F`) fuzzily, e.g. `cl13` finds
+    /// `codellama:13b`; `false` matches substrings only.
+    pub fuzzy_filter: bool,
+    /// Keep conversations and drafts of models that are no longer
+    /// installed, e.g. while a remote server is missing some of them.
+    pub keep_unavailable_models: bool,
+    /// Turn off all animations (spinner, cursor blink), so the UI only
+    /// redraws when something happens.
+    pub quiet: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            snippets: Vec::new(),
+            prompt_template: None,
+            model_templates: HashMap::new(),
+            greeting: None,
+            regenerate_diff: false,
+            models: Vec::new(),
+            model_order: Vec::new(),
+            send_key: SendKey::default(),
+            trim_response: true,
+            labels: Labels::default(),
+            hosts: Vec::new(),
+            thinking_hint_after: DEFAULT_THINKING_HINT_AFTER,
+            thinking_hint: DEFAULT_THINKING_HINT.to_string(),
+            context_limit: None,
+            context_limits: HashMap::new(),
+            num_predict: None,
+            num_predicts: HashMap::new(),
+            shared_input: false,
+            restore_histories: false,
+            compact: false,
+            code_block_style: CodeBlockStyle::default(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            code_language: String::new(),
+            confirm_quit: false,
+            confirm_quit_after: DEFAULT_CONFIRM_QUIT_AFTER,
+            confirm_commands: vec![CLEAR_COMMAND.to_string()],
+            yank_lead_in: String::new(),
+            restore_drafts: true,
+            max_chat_logs: None,
+            log_format: LogFormat::default(),
+            status_clock: false,
+            status_session: false,
+            plain: false,
+            export_dir: ".".to_string(),
+            resume_interrupted: false,
+            fallback_models: Vec::new(),
+            number_turns: false,
+            autoscroll_mode: AutoscrollMode::default(),
+            text_direction: TextDirection::default(),
+            fuzzy_filter: true,
+            keep_unavailable_models: false,
+            quiet: false,
+        }
+    }
+}
+
+impl Config {
+    /// Parses a configuration from TOML source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` is not valid TOML or a value has the
+    /// wrong type.
+    pub fn parse(source: &str) -> Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Checks that every prompt template contains [`PROMPT_PLACEHOLDER`]
+    /// and that `timestamp_format` is a valid `strftime` format.
+    ///
+    /// Invalid templates are not fatal: [`apply_template`] leaves prompts
+    /// unchanged for them, and an invalid timestamp format only makes
+    /// `Ctrl+D` report the problem. This only reports it up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        if !is_valid_timestamp_format(&self.timestamp_format) {
+            anyhow::bail!("timestamp_format {:?} is not a valid format", self.timestamp_format);
+        }
+        if let Some(template) = &self.prompt_template {
+            if !template.contains(PROMPT_PLACEHOLDER) {
+                anyhow::bail!("prompt_template lacks {}", PROMPT_PLACEHOLDER);
+            }
+        }
+        let mut models: Vec<&String> = self.model_templates.keys().collect();
+        models.sort();
+        for model in models {
+            if !self.model_templates[model].contains(PROMPT_PLACEHOLDER) {
+                anyhow::bail!("template for {} lacks {}", model, PROMPT_PLACEHOLDER);
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the configuration from `path`, falling back to the defaults if
+    /// the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let source = fs::read_to_string(path)?;
+        Self::parse(&source).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Loads the configuration from the default [`config_path`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Config::load_from`].
+    pub fn load() -> Result<Self> {
+        match config_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Config::default()),
+        }
+    }
+}
+
+/// Whether `format` is a `strftime` format that chrono can render.
+///
+/// Formatting a timestamp with an invalid format panics, so formats from
+/// the config file are checked with this first.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::config::is_valid_timestamp_format;
+///
+/// assert!(is_valid_timestamp_format("%Y-%m-%d %H:%M"));
+/// assert!(!is_valid_timestamp_format("%Q"));
+/// ```
+pub fn is_valid_timestamp_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+/// Returns the location of `config.toml`, if the platform has a config directory.
+pub fn config_path() -> Option<PathBuf> {
+    let mut path = dirs::config_dir()?;
+    path.push("lazyllama");
+    path.push("config.toml");
+    Some(path)
+}
+
+/// Stores `order` as `model_order` in the config file at `path`.
+///
+/// The rest of the file, including comments, is kept as it is. The file and
+/// its directory are created if they do not exist.
+///
+/// # Errors
+///
+/// Returns an error if the existing file is not valid TOML or the file
+/// cannot be read or written.
+pub fn save_model_order(path: &Path, order: &[String]) -> Result<()> {
+    let source = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let mut document: DocumentMut = source
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    document["model_order"] = value(order.iter().map(String::as_str).collect::<Array>());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, document.to_string())?;
+    Ok(())
+}
+
+/// Wraps `prompt` in `template` by replacing [`PROMPT_PLACEHOLDER`].
+///
+/// Returns the prompt unchanged if there is no template or the template
+/// does not contain the placeholder.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::config::apply_template;
+///
+/// assert_eq!(apply_template(Some("Be brief. {prompt}"), "Hi"), "Be brief. Hi");
+/// assert_eq!(apply_template(Some("no placeholder"), "Hi"), "Hi");
+/// assert_eq!(apply_template(None, "Hi"), "Hi");
+/// ```
+pub fn apply_template(template: Option<&str>, prompt: &str) -> String {
+    match template {
+        Some(template) if template.contains(PROMPT_PLACEHOLDER) => {
+            template.replace(PROMPT_PLACEHOLDER, prompt)
+        }
+        _ => prompt.to_string(),
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+
+//! Word-level diff between two answers.
+//!
+//! Used to show what changed when an answer is regenerated. The diff is a
+//! plain longest-common-subsequence over words (including their trailing
+//! whitespace), which is more than fast enough for a single answer. Inputs
+//! larger than [`MAX_DIFF_CELLS`] fall back to a line-level diff to keep the
+//! quadratic table bounded.
+
+/// Upper bound for the LCS table size (tokens of old × tokens of new).
+pub const MAX_DIFF_CELLS: usize = 250_000;
+
+/// One piece of a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOp {
+    /// Text present in both versions.
+    Same(String),
+    /// Text only present in the new version.
+    Added(String),
+    /// Text only present in the old version.
+    Removed(String),
+}
+
+/// Computes a word diff turning `old` into `new`.
+///
+/// Adjacent pieces of the same kind are merged, so the result alternates
+/// between kinds.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::diff::{diff_words, DiffOp};
+///
+/// let ops = diff_words("the red car", "the blue car");
+/// assert_eq!(ops, vec![
+///     DiffOp::Same("the ".to_string()),
+///     DiffOp::Removed("red ".to_string()),
+///     DiffOp::Added("blue ".to_string()),
+///     DiffOp::Same("car".to_string()),
+/// ]);
+/// ```
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffOp> {
+    let old_words = tokenize(old);
+    let new_words = tokenize(new);
+    if old_words.len().saturating_mul(new_words.len()) <= MAX_DIFF_CELLS {
+        return diff_tokens(&old_words, &new_words);
+    }
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    if old_lines.len().saturating_mul(new_lines.len()) <= MAX_DIFF_CELLS {
+        return diff_tokens(&old_lines, &new_lines);
+    }
+    vec![DiffOp::Removed(old.to_string()), DiffOp::Added(new.to_string())]
+}
+
+/// Splits `text` into words, each carrying its trailing whitespace.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_space = true;
+        } else if in_space {
+            tokens.push(&text[start..i]);
+            start = i;
+            in_space = false;
+        }
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn diff_tokens(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(&mut ops, DiffOp::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] > lcs[i + 1][j]) {
+            push(&mut ops, DiffOp::Added(new[j].to_string()));
+            j += 1;
+        } else {
+            push(&mut ops, DiffOp::Removed(old[i].to_string()));
+            i += 1;
+        }
+    }
+    ops
+}
+
+/// Appends `op`, merging it into the previous piece if both are the same kind.
+fn push(ops: &mut Vec<DiffOp>, op: DiffOp) {
+    match (ops.last_mut(), op) {
+        (Some(DiffOp::Same(last)), DiffOp::Same(text))
+        | (Some(DiffOp::Added(last)), DiffOp::Added(text))
+        | (Some(DiffOp::Removed(last)), DiffOp::Removed(text)) => last.push_str(&text),
+        (_, op) => ops.push(op),
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+//! Errors returned by the library API.
+//!
+//! [`App`](crate::app::App) and [`utils`](crate::utils) report failures as
+//! [`LazyLlamaError`], so embedders can tell a server that is down from a
+//! missing model or a full disk. The binary converts them to `anyhow`.
+
+use std::error::Error as StdError;
+use thiserror::Error;
+
+/// Boxed underlying error of a [`LazyLlamaError`].
+pub type Source = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Result type of the library API.
+pub type Result<T> = std::result::Result<T, LazyLlamaError>;
+
+/// A failure in LazyLlama's library API.
+#[derive(Debug, Error)]
+pub enum LazyLlamaError {
+    /// The model server could not be reached or rejected the request.
+    #[error("{0}")]
+    Connection(#[source] Source),
+    /// The server does not have the requested model.
+    #[error("model {model} not found: {source}")]
+    ModelNotFound {
+        /// Name of the requested model.
+        model: String,
+        /// Error reported by the server.
+        #[source]
+        source: Source,
+    },
+    /// The response stream broke off after the request was accepted.
+    #[error("response stream interrupted: {0}")]
+    Stream(#[source] Source),
+    /// Drawing to the terminal failed.
+    #[error("terminal error: {0}")]
+    Terminal(#[source] Source),
+    /// The server is too old for a feature.
+    #[error("{feature} needs Ollama {required} or newer, but the server runs {found}")]
+    UnsupportedServer {
+        /// The feature that was requested.
+        feature: String,
+        /// Minimum server version of the feature.
+        required: String,
+        /// Version the server reported.
+        found: String,
+    },
+    /// The platform has no local data directory.
+    #[error("data directory not found")]
+    NoDataDir,
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl LazyLlamaError {
+    /// Classifies an error returned by a model client when requesting
+    /// `model`.
+    ///
+    /// Servers report unknown models only in the message text (Ollama says
+    /// `model "x" not found, try pulling it first`), so that is what is
+    /// matched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lazyllama::error::LazyLlamaError;
+    ///
+    /// let err = LazyLlamaError::from_client("llama3", anyhow::anyhow!("connection refused"));
+    /// assert!(matches!(err, LazyLlamaError::Connection(_)));
+    /// let err = LazyLlamaError::from_client("llama3", anyhow::anyhow!("model \"llama3\" not found"));
+    /// assert!(matches!(err, LazyLlamaError::ModelNotFound { .. }));
+    /// ```
+    pub fn from_client(model: &str, error: anyhow::Error) -> Self {
+        if error.to_string().contains("not found") {
+            LazyLlamaError::ModelNotFound {
+                model: model.to_string(),
+                source: error.into(),
+            }
+        } else {
+            LazyLlamaError::Connection(error.into())
+        }
+    }
+}
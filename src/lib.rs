@@ -1,15 +1,15 @@
 //! # LazyLlama Library
 //! 
-//! A comprehensive library interface for LazyLlama's core modules, primarily designed
-//! to enable testing and potential future code reuse. This library crate exposes the
-//! same modules that the main binary uses, but organized as a library structure.
+//! A comprehensive library interface for LazyLlama's core modules, designed to
+//! enable testing and code reuse. The main binary is built on top of this crate.
 //! 
 //! ## Important Architecture Note
 //! 
-//! **The main binary (`src/main.rs`) does NOT use this library crate directly.**
-//! Instead, it declares modules inline using `mod app;`, `mod ui;`, and `mod utils;`.
-//! This is a common Rust pattern for binary applications that want to keep the
-//! main executable lightweight while still providing library access for testing.
+//! **The main binary (`src/main.rs`) uses this library crate directly.**
+//! It only contains the terminal setup and the event loop and imports
+//! everything else, e.g. `use lazyllama::app::App;`. Each module is therefore
+//! compiled once, and items that only tests and embedders use are part of the
+//! public API rather than dead code in the binary.
 //! 
 //! ## Purpose and Usage
 //! 
@@ -25,9 +25,9 @@
 //! Without this library structure, testing internal functions and structs would 
 //! be much more difficult since binary crates don't expose their internal modules.
 //! 
-//! ### 2. **Future Code Reuse**
+//! ### 2. **Code Reuse**
 //! 
-//! While not currently used by main.rs, this library enables:
+//! Besides the main binary, this library enables:
 //! - Other applications to use LazyLlama components as dependencies
 //! - Creating additional binaries that share the same core functionality
 //! - Building plugins or extensions that interact with LazyLlama's modules
@@ -40,7 +40,7 @@
 //! 
 //! ## Module Structure
 //! 
-//! The library exposes the same three modules that main.rs uses directly:
+//! The library exposes the modules that main.rs builds on:
 //! 
 //! ### [`app`] - Application State and Logic
 //! 
@@ -60,6 +60,37 @@
 //! - Conversation history formatting and display
 //! - Model selection interface rendering
 //! 
+//! ### [`client`] - Model Backend Abstraction
+//! 
+//! Decouples the application from the Ollama HTTP API:
+//! - `ModelClient` trait implemented for the `ollama-rs` client
+//! - `ScriptedClient` replaying canned responses for headless tests
+//! 
+//! ### [`clipboard`] - Clipboard Integration
+//! 
+//! Copies conversation text to the system clipboard:
+//! - Clean Markdown/code extraction without rendering decorations
+//! - OSC 52 terminal clipboard support (works over SSH)
+//! 
+//! ### [`config`] - User Configuration
+//! 
+//! Loads optional settings from `config.toml`:
+//! - Prompt snippets bound to `Alt+1` … `Alt+9`
+//! 
+//! ### [`diff`] - Answer Diffs
+//! 
+//! Word-level diff used to compare a regenerated answer with the previous one.
+//! 
+//! ### [`error`] - Library Errors
+//! 
+//! `LazyLlamaError`, returned by the public `App` and `utils` functions so
+//! embedders can match on connection, model, stream and I/O failures.
+//! 
+//! ### [`testing`] - Test Support
+//! 
+//! `render_snapshot` renders the UI headlessly into a string, to assert on
+//! the exact screen layout in regression tests. Only the library has it.
+//! 
 //! ### [`utils`] - Utility Functions and File Operations
 //! 
 //! Provides essential utility functions for:
@@ -68,18 +99,22 @@
 //! - Data directory management
 //! - Error handling and file naming utilities
 //! 
-//! ## Current Architecture: Binary with Library Interface
+//! ## Current Architecture: Binary on top of the Library
 //! 
 //! ```text
-//! src/main.rs (binary)
-//! ├── mod app;          ← Direct module inclusion
-//! ├── mod ui;           ← Direct module inclusion
-//! └── mod utils;        ← Direct module inclusion
+//! src/lib.rs (library)
+//! ├── pub mod app;      ← Application state and logic
+//! ├── pub mod client;   ← Model backend abstraction
+//! ├── pub mod clipboard;← Clipboard integration
+//! ├── pub mod config;   ← User configuration
+//! ├── pub mod diff;     ← Answer diffs
+//! ├── pub mod error;    ← Library errors
+//! ├── pub mod testing;  ← Headless render snapshots for tests
+//! ├── pub mod ui;       ← Terminal user interface rendering
+//! └── pub mod utils;    ← Utility functions and file operations
 //! 
-//! src/lib.rs (library for testing)
-//! ├── pub mod app;      ← Same modules, but public for external access
-//! ├── pub mod ui;       ← Same modules, but public for external access
-//! └── pub mod utils;    ← Same modules, but public for external access
+//! src/main.rs (binary)
+//! └── use lazyllama::{app, ui, utils, …}; ← Terminal setup and event loop
 //! 
 //! tests/ (integration tests)
 //! └── use lazyllama::*; ← Uses the library interface
@@ -100,10 +135,15 @@
 //! }
 //! ```
 //! 
-//! This dual structure (binary with inline modules + library interface) is a
-//! common Rust pattern that provides the best of both worlds: a lean binary 
-//! executable and comprehensive testing capabilities.
+//! Keeping the binary a thin layer over the library is a common Rust pattern:
+//! the code the binary runs is the same code the tests and embedders use.
 
 pub mod app;
+pub mod client;
+pub mod clipboard;
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod testing;
 pub mod ui;
 pub mod utils;
\ No newline at end of file
@@ -39,28 +39,83 @@
 //! ## Usage
 //!
 //! Run the application and use the following controls:
-//! - `Ctrl+Q`: Quit the application
+//! - `Ctrl+Q`: Quit the application (asks first with `confirm_quit`)
 //! - `Ctrl+C`: Clear current model's chat history
+//! - `Ctrl+L`: Clear the input, keeping the chat history
+//! - `Ctrl+N`: Start a new conversation, saving the current one first
 //! - `Ctrl+S`: Toggle autoscroll mode
+//! - `Ctrl+Y`: Copy the conversation to the clipboard
+//! - `Ctrl+K`: Copy the last code block to the clipboard
+//! - `Ctrl+X`: Export the code blocks of the last answer to files
+//! - `Ctrl+Shift+Y` / `Alt+Y`: Copy the last answer to the clipboard
+//! - `Alt+C`: Copy the last request as a `curl` command
+//! - `Ctrl+P`: Append the last answer to the input, to send it on
+//! - `Ctrl+Plus` / `Ctrl+Minus` (or `Alt`): Raise/lower the model's temperature
+//! - `Alt+]` / `Alt+[`: Raise/lower the model's answer cap (`num_predict`)
 //! - `Arrow Keys`: Switch between AI models
+//! - `Shift+↑` / `Shift+↓`: Move the selected model up/down in the list
+//! - `Ctrl+A`: Toggle between the configured model list and all models
+//! - `Ctrl+Tab` / `Ctrl+O`: Switch between recently used models
+//! - `Ctrl+F`: Filter the models by name, fuzzily
+//! - `Alt+1` … `Alt+9`: Insert a saved prompt snippet
+//! - `Ctrl+D`: Insert the current date and time
+//! - `Alt+K`: Insert an empty code block
+//! - `Alt+↑` / `Alt+↓`: Highlight an earlier/later exchange
+//! - `Ctrl+T`: Collapse or expand the highlighted exchange
+//! - `Ctrl+E`: Ask the model to explain the highlighted exchange's answer
+//! - `Ctrl+B` / `Ctrl+G`: Bookmark the reading position / jump back to it
+//! - `Ctrl+R`: Regenerate the last answer
+//! - `r`: Retry a failed request (while the input is empty)
+//! - `F2`: Switch between the Ollama hosts from the config file
+//! - `F3`: Manage saved logs (count and size per model, delete)
+//! - `F4`: Open the data directory with the saved logs in the file manager
+//! - `F5`: Toggle the compact transcript (fewer blank lines)
+//! - `F6`: In debug mode, toggle showing each request before it is sent
+//! - `F7`: Cycle the text emphasis (normal, all bold, dim)
+//! - `F8`: Reload the config file
+//! - `F9`: Show details of the selected model
+//! - `F10`: Reload the model list from the server
+//! - `F11`: Toggle focus mode (hides the model list and status bar)
+//! - `F1`: Show the help overlay
 //! - `Page Up/Down`: Manual scrolling
-//! - `Enter`: Send message to AI
+//! - `Enter`: Send message to AI (`Ctrl+Enter` with `send_key = "ctrl-enter"`)
+//! - `Shift+Enter`: Insert a line break
 //!
 //! Each AI model maintains separate input buffers, chat histories, and scroll positions.
+//!
+//! Set `LAZYLLAMA_POLL_MS` to a number of milliseconds to change how often the
+//! event loop wakes up (default 100), or to `adaptive` to poll quickly while a
+//! response is streaming and sparingly while idle.
+//!
+//! `lazyllama --no-save` (or `LAZYLLAMA_EPHEMERAL=1`) starts an ephemeral
+//! session that never writes history, conversation or config files.
+//!
+//! `lazyllama --list-models` prints the installed model names, one per line,
+//! and exits without starting the TUI. It asks the first host from the config
+//! file, like the interactive session, and exits with status 1 if Ollama is
+//! unreachable.
+//!
+//! Prompt snippets are read from `config.toml` in the platform config
+//! directory (see [`lazyllama::config`]).
 
-mod app;
-mod ui;
-mod utils;
-
-use crate::app::App;
 use anyhow::Result;
+use lazyllama::app::App;
+use lazyllama::client::{ollama_client, startup_client, write_model_list};
+use lazyllama::config::Config;
+use lazyllama::{ui, utils};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{io, time::Duration};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Main entry point for the LazyLlama application.
 ///
@@ -77,20 +132,86 @@ use std::{io, time::Duration};
 /// # Event Handling
 ///
 /// The main loop processes the following key combinations:
-/// - `Ctrl+Q`: Graceful application exit
-/// - `Ctrl+C`: Clear current model's buffer
+/// - `Ctrl+Q`: Graceful application exit; with `confirm_quit` set and long
+///   conversations it asks first, and `y` or a second `Ctrl+Q` quits
+/// - `Ctrl+C`: Clear current model's buffer (a SIGINT sent to the process
+///   quits like `Ctrl+Q` instead)
+/// - `Ctrl+L`: Discard the input draft
+/// - `Ctrl+N`: Archive the current model's conversation, then clear it
 /// - `Ctrl+S`: Toggle autoscroll behavior
+/// - `Ctrl+Y` / `Ctrl+K`: Copy the conversation / last code block
+/// - `Ctrl+X`: Export the code blocks of the last or focused answer
+/// - `Ctrl+Shift+Y` / `Alt+Y`: Copy the last answer (many terminals send
+///   `Ctrl+Shift+Y` as plain `Ctrl+Y`, hence the `Alt` alternative)
+/// - `Alt+C`: Copy a `curl` command repeating the last request
+/// - `Ctrl+P`: Append the last answer, after `yank_lead_in`, to the input
+/// - `Ctrl+Plus/Minus` / `Alt+Plus/Minus`: Nudge the selected model's
+///   temperature by 0.1 (terminals often swallow the `Ctrl` variants)
+/// - `Alt+]` / `Alt+[`: Step the selected model's answer cap through
+///   `NUM_PREDICT_STEPS`, up to unlimited
 /// - `Up/Down Arrow`: Switch between AI models with buffer persistence
+/// - `Shift+Up/Down`: Reorder the model list (saved to the config file)
+/// - `Ctrl+A`: Show all models / only the configured ones
+/// - `Ctrl+Tab` / `Ctrl+O`: Open/cycle the recent-models switcher
+/// - `Ctrl+F`: Open the model filter (type to filter, `Up/Down`, `Enter`
+///   switches, `Esc` cancels)
+/// - `Alt+1` … `Alt+9`: Insert the corresponding snippet at the cursor
+/// - `Ctrl+D`: Insert the current time at the cursor, in `timestamp_format`
+/// - `Alt+K`: Insert an empty code block in the last used language and put
+///   the cursor inside it
+/// - `Alt+Up/Down`: Highlight an exchange, `Esc` clears the highlight
+/// - `Ctrl+T`: Collapse/expand the highlighted exchange
+/// - `Ctrl+E`: Insert "Explain this:" with the highlighted exchange's answer
+///   quoted (long answers are truncated)
+/// - `Ctrl+B`: Bookmark the scroll position (again on the same line removes it)
+/// - `Ctrl+G`: Jump to the bookmark, disabling autoscroll
+/// - `Ctrl+R`: Regenerate the last answer
+/// - `r`: Retry the failed request, if the last one failed and the input
+///   is empty
+/// - `F2`: Open the host picker (`Up/Down`, `Enter` switches, `Esc` cancels)
+/// - `F3`: Open the log manager (`d` deletes a model's logs after `y`)
+/// - `F4`: Open the data directory in the file manager
+/// - `F5`: Toggle the compact transcript
+/// - `F6`: Toggle the `[request debug]` echo (debug mode only)
+/// - `F7`: Cycle the text emphasis
+/// - `F8`: Re-read the config file; a broken file keeps the old settings
+/// - `F9`: Open the model info popup (`↑`/`↓` scroll, `Esc` closes)
+/// - `F10`: Reload the model list, e.g. after `ollama pull`
+/// - `F11`: Toggle focus mode
+/// - `F1`: Show the help overlay (any key closes it)
 /// - `Page Up/Down`: Manual scrolling with autoscroll disable
-/// - `Enter`: Send query to selected AI model
+/// - `Enter`: Send query to selected AI model, or insert a line break if
+///   `send_key` is `ctrl-enter`; `Ctrl+Enter` does the opposite
+/// - `Shift+Enter`: Insert a line break
 /// - `Backspace`: Delete characters from input
 /// - `Character keys`: Add text to input buffer
 ///
 /// # Error Handling
 ///
 /// Properly handles terminal setup/teardown and ensures cleanup even on errors.
+/// A SIGINT takes the same shutdown path as `Ctrl+Q`: the loop ends at the
+/// next poll, the terminal is restored and histories are saved.
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut no_save = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--no-save" => no_save = true,
+            command => run_command(command).await,
+        }
+    }
+
+    // In raw mode Ctrl+C arrives as a key press and clears the conversation.
+    // A real SIGINT can only come from outside (e.g. `kill -INT`); it quits
+    // like Ctrl+Q, so the terminal is restored and histories are saved.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            flag.store(true, Ordering::SeqCst);
+        }
+    });
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -98,14 +219,39 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new().await;
+    app.ephemeral |= no_save;
+    app.key_source = Some(Box::new(waiting_key_presses));
     let mut should_quit = false;
 
     // Initial draw
     terminal.draw(|f| ui::ui(f, &mut app))?;
+    // Saved conversations are read after the first frame, so a large data
+    // directory never delays startup.
+    if app.restore_histories {
+        app.start_history_reload();
+    }
 
-    while !should_quit {
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+    while !should_quit && !interrupted.load(Ordering::SeqCst) {
+        if app.poll_history_reload() {
+            terminal.draw(|f| ui::ui(f, &mut app))?;
+        }
+        // Keys typed while an answer streamed that the app left to the
+        // event loop come first.
+        let deferred = app.deferred_keys.pop_front();
+        if deferred.is_some() || event::poll(app.poll_interval())? {
+            let event = match deferred {
+                Some(key) => Event::Key(key),
+                None => event::read()?,
+            };
+            if let Event::Mouse(mouse) = event {
+                if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                    && app.click(mouse.column, mouse.row)
+                {
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                }
+                continue;
+            }
+            if let Event::Key(key) = event {
                 // Windows-specific fix: Only process KeyPress events to prevent double input
                 if key.kind != KeyEventKind::Press {
                     continue;
@@ -114,29 +260,199 @@ async fn main() -> Result<()> {
                 if app.debug_keys {
                     app.debug_last_key = Some(format!("{:?}", key));
                 }
-                
+                app.status_message = None;
+
                 let is_ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-                match (key.code, is_ctrl) {
-                    (KeyCode::Char('q'), true) => should_quit = true,
-                    (KeyCode::Char('c'), true) => {
-                        // Lösche nur den aktuellen Modell-Buffer
-                        app.history.clear();
-                        app.scroll = 0;
-                        app.autoscroll = true;
-                        app.save_current_model_buffers();
+
+                // Any key closes the help overlay.
+                if app.show_help {
+                    app.show_help = false;
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // The quit confirmation takes the next key: `y` or another
+                // Ctrl+Q quits, anything else cancels.
+                if app.quit_confirmation.is_some() {
+                    let confirmed = key.code == KeyCode::Char('y')
+                        || (is_ctrl && key.code == KeyCode::Char('q'));
+                    should_quit = app.answer_quit_confirmation(confirmed);
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // The recent-models switcher is modal: while it is open it
+                // consumes all keys.
+                if app.mru_selection.is_some() {
+                    match (key.code, is_ctrl) {
+                        (KeyCode::Tab, true) | (KeyCode::Char('o'), true) | (KeyCode::Down, _) => {
+                            app.cycle_mru_switcher()
+                        }
+                        (KeyCode::Up, _) => app.cycle_mru_switcher_back(),
+                        (KeyCode::Enter, _) => app.confirm_mru_switcher(),
+                        (KeyCode::Esc, _) => app.cancel_mru_switcher(),
+                        _ => {}
+                    }
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // The host picker is modal as well.
+                if app.host_selection.is_some() {
+                    match key.code {
+                        KeyCode::Down => app.host_picker_next(),
+                        KeyCode::Up => app.host_picker_previous(),
+                        KeyCode::Enter => app.confirm_host_picker().await,
+                        KeyCode::Esc => app.cancel_host_picker(),
+                        _ => {}
+                    }
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // The model filter takes typed characters as its query.
+                if app.model_filter.is_some() {
+                    match (key.code, is_ctrl) {
+                        (KeyCode::Down, _) => app.model_filter_next(),
+                        (KeyCode::Up, _) => app.model_filter_previous(),
+                        (KeyCode::Enter, _) => app.confirm_model_filter(),
+                        (KeyCode::Esc, _) | (KeyCode::Char('f'), true) => app.cancel_model_filter(),
+                        (KeyCode::Backspace, _) => app.model_filter_backspace(),
+                        (KeyCode::Char(c), false) => app.model_filter_insert(c),
+                        _ => {}
+                    }
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // The model info popup only scrolls and closes.
+                if app.model_info_popup.is_some() {
+                    match key.code {
+                        KeyCode::Down => app.scroll_model_info(1),
+                        KeyCode::Up => app.scroll_model_info(-1),
+                        KeyCode::Esc | KeyCode::F(9) => app.close_model_info(),
+                        _ => {}
+                    }
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                // So is the log manager; deleting asks for `y` first.
+                if let Some(manager) = &app.log_manager {
+                    if manager.confirm_delete {
+                        app.confirm_log_deletion(key.code == KeyCode::Char('y'));
+                    } else {
+                        match key.code {
+                            KeyCode::Down => app.log_manager_next(),
+                            KeyCode::Up => app.log_manager_previous(),
+                            KeyCode::Char('d') | KeyCode::Delete => app.request_log_deletion(),
+                            KeyCode::Esc | KeyCode::F(3) => app.close_log_manager(),
+                            _ => {}
+                        }
+                    }
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                if key.modifiers.contains(KeyModifiers::ALT) {
+                    let handled = match key.code {
+                        KeyCode::Char(c @ '1'..='9') => {
+                            app.insert_snippet(c as usize - '1' as usize);
+                            true
+                        }
+                        KeyCode::Up => {
+                            app.focus_previous_exchange();
+                            true
+                        }
+                        KeyCode::Down => {
+                            app.focus_next_exchange();
+                            true
+                        }
+                        KeyCode::Char('y') => {
+                            app.copy_last_answer();
+                            true
+                        }
+                        KeyCode::Char('c') => {
+                            app.copy_request_as_curl();
+                            true
+                        }
+                        KeyCode::Char('k') => {
+                            app.insert_code_block();
+                            true
+                        }
+                        KeyCode::Char('+') | KeyCode::Char('=') => {
+                            app.raise_temperature();
+                            true
+                        }
+                        KeyCode::Char('-') => {
+                            app.lower_temperature();
+                            true
+                        }
+                        KeyCode::Char(']') => {
+                            app.raise_num_predict();
+                            true
+                        }
+                        KeyCode::Char('[') => {
+                            app.lower_num_predict();
+                            true
+                        }
+                        _ => false,
+                    };
+                    if handled {
+                        terminal.draw(|f| ui::ui(f, &mut app))?;
+                        continue;
                     }
+                }
+
+                // Cursor motion and deletion in the input.
+                if app.handle_editing_key(key) {
+                    terminal.draw(|f| ui::ui(f, &mut app))?;
+                    continue;
+                }
+
+                match (key.code, is_ctrl) {
+                    (KeyCode::Char('q'), true) => should_quit = app.request_quit(),
+                    (KeyCode::F(1), _) => app.show_help = true,
+                    (KeyCode::F(2), _) => app.open_host_picker(),
+                    (KeyCode::F(3), _) => app.open_log_manager(),
+                    (KeyCode::F(4), _) => app.open_data_dir(),
+                    (KeyCode::F(5), _) => app.compact = !app.compact,
+                    (KeyCode::F(6), _) => app.toggle_echo_requests(),
+                    (KeyCode::F(7), _) => app.cycle_emphasis(),
+                    (KeyCode::F(8), _) => app.reload_config(),
+                    (KeyCode::F(9), _) => app.open_model_info().await,
+                    (KeyCode::F(10), _) => app.reload_models().await,
+                    (KeyCode::F(11), _) => app.focus_mode = !app.focus_mode,
+                    (KeyCode::Char('c'), true) => app.clear_conversation(),
+                    (KeyCode::Char('l'), true) => app.clear_input(),
+                    (KeyCode::Char('n'), true) => app.new_conversation(),
+                    (KeyCode::Char('a'), true) => app.toggle_show_all_models(),
                     (KeyCode::Char('s'), true) => app.autoscroll = !app.autoscroll,
-                    (KeyCode::Left, true) => {
-                        app.move_cursor_word_left();
+                    (KeyCode::Char('t'), true) => app.toggle_focused_exchange(),
+                    (KeyCode::Char('e'), true) => app.explain_focused_exchange(),
+                    (KeyCode::Char('b'), true) => app.toggle_bookmark(),
+                    (KeyCode::Char('g'), true) => app.jump_to_bookmark(),
+                    (KeyCode::Esc, _) => app.clear_exchange_focus(),
+                    (KeyCode::Char('Y'), true) => app.copy_last_answer(),
+                    (KeyCode::Char('y'), true) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                        app.copy_last_answer()
                     }
-                    (KeyCode::Right, true) => {
-                        app.move_cursor_word_right();
+                    (KeyCode::Char('y'), true) => app.copy_conversation(),
+                    (KeyCode::Char('k'), true) => app.copy_last_code_block(),
+                    (KeyCode::Char('x'), true) => app.export_code_blocks(),
+                    (KeyCode::Char('p'), true) => app.yank_last_answer(),
+                    (KeyCode::Char('d'), true) => app.insert_timestamp(),
+                    (KeyCode::Char('+'), true) | (KeyCode::Char('='), true) => {
+                        app.raise_temperature()
                     }
-                    (KeyCode::Home, _) => {
-                        app.move_cursor_home();
+                    (KeyCode::Char('-'), true) => app.lower_temperature(),
+                    (KeyCode::Tab, true) | (KeyCode::Char('o'), true) => app.cycle_mru_switcher(),
+                    (KeyCode::Char('f'), true) => app.open_model_filter(),
+                    (KeyCode::Up, _) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                        app.move_model_up();
                     }
-                    (KeyCode::End, _) => {
-                        app.move_cursor_end();
+                    (KeyCode::Down, _) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                        app.move_model_down();
                     }
                     (KeyCode::Up, _) => {
                         app.select_previous_model();
@@ -152,46 +468,34 @@ async fn main() -> Result<()> {
                         app.autoscroll = false;
                         app.scroll = app.scroll.saturating_add(5);
                     }
-                    (KeyCode::Left, _) => {
-                        app.move_cursor_left();
-                    }
-                    (KeyCode::Right, _) => {
-                        app.move_cursor_right();
-                    }
-                    (KeyCode::Backspace, true) => {
-                        app.delete_word_left();
-                    }
-                    (KeyCode::Char('h'), true) => {
-                        // Kitty maps Ctrl+Backspace to Ctrl+H, so handle it as word deletion.
-                        app.delete_word_left();
-                    }
-                    (KeyCode::Delete, true) => {
-                        app.delete_word_right();
-                    }
-                    (KeyCode::Delete, _) => {
-                        app.delete_forward();
-                    }
-                    (KeyCode::Enter, _) => {
-                        if !app.input.is_empty() && !app.is_loading {
+                    // Many terminals report Ctrl+Enter as Ctrl+J.
+                    (KeyCode::Enter, _) | (KeyCode::Char('j'), true) => {
+                        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                        if !app.send_key.sends(is_ctrl, shift) {
+                            app.insert_char('\n');
+                        } else if !app.input.is_empty() {
                             app.send_query(&mut terminal).await?;
                         }
                     }
+                    (KeyCode::Char('r'), true) if !app.is_loading => {
+                        app.regenerate(&mut terminal).await?;
+                    }
+                    (KeyCode::Char('r'), false) if app.can_retry() => {
+                        app.retry(&mut terminal).await?;
+                    }
                     (KeyCode::Char(c), false) => {
                         app.insert_char(c);
                     }
-                    (KeyCode::Backspace, _) => {
-                        app.backspace();
-                    }
                     _ => {}
                 }
                 
                 // Only redraw after an actual event occurred
                 terminal.draw(|f| ui::ui(f, &mut app))?;
             }
-        } else if app.is_loading {
+        } else if app.is_loading && !app.quiet {
             // Redraw during loading for spinner animation
             terminal.draw(|f| ui::ui(f, &mut app))?;
-        } else if app.update_cursor_blink() {
+        } else if app.update_cursor_blink() | app.update_status_clock() {
             terminal.draw(|f| ui::ui(f, &mut app))?;
         }
     }
@@ -207,9 +511,65 @@ async fn main() -> Result<()> {
     app.save_current_model_buffers();
     
     // Speichere sowohl die allgemeine History als auch die modellspezifischen Histories
-    utils::save_history_to_file(&app.history)?;
-    utils::save_model_histories(&app.model_histories)?;
+    if !app.ephemeral {
+        utils::save_history_to_file_as(&app.history, app.log_format)?;
+        if let Some(keep) = app.max_chat_logs {
+            utils::prune_old_logs(keep)?;
+        }
+        utils::save_model_histories_as(&app.all_model_histories(), app.log_format)?;
+        utils::save_mru_order(&app.persistent_mru_order())?;
+        utils::save_bookmarks(&app.model_bookmarks)?;
+        if app.restore_drafts {
+            utils::save_drafts(&app.drafts())?;
+        }
+    }
     Ok(())
 }
 
+/// Reads the key presses waiting in the terminal without blocking.
+///
+/// Used as the app's key source while an answer streams; other events,
+/// such as mouse clicks, are dropped.
+fn waiting_key_presses() -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => keys.push(key),
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    keys
+}
+
+/// Runs a non-interactive command given on the command line and exits.
+///
+/// Supported commands:
+/// - `--list-models`: Print the installed model names, one per line
+///
+/// `--no-save` is an option of the interactive session and handled by
+/// `main` itself.
+///
+/// Never touches the terminal state. Exits with status 1 if the command
+/// fails and 2 for an unknown argument.
+async fn run_command(arg: &str) -> ! {
+    match arg {
+        "--list-models" => {
+            // Ask the server the interactive session would connect to.
+            let hosts = Config::load().map(|config| config.hosts).unwrap_or_default();
+            let client = startup_client(&hosts, &ollama_client);
+            let mut stdout = io::stdout();
+            if let Err(e) = write_model_list(client.as_ref(), &mut stdout).await {
+                eprintln!("lazyllama: could not list models: {}", e);
+                std::process::exit(1);
+            }
+            std::process::exit(0);
+        }
+        _ => {
+            eprintln!("lazyllama: unknown argument '{}'", arg);
+            eprintln!("Usage: lazyllama [--no-save] [--list-models]");
+            std::process::exit(2);
+        }
+    }
+}
 
@@ -0,0 +1,71 @@
+/*
+ *  _                      _      _
+ * | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _
+ * | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |
+ * | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |
+ * |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
+ *
+ * Copyright (C) 2026 Raimo Geisel
+ *
+ * This program is free software; you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation; either version 2 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301 USA.
+ */
+
+//! Headless rendering for regression tests.
+//!
+//! [`render_snapshot`] draws [`ui`](crate::ui::ui) into a ratatui
+//! [`TestBackend`] and returns the screen as text, so tests can assert on
+//! the exact layout for a given [`App`] state. Like
+//! [`ScriptedClient`](crate::client::ScriptedClient) it is part of the
+//! public API, so integration tests and embedders can use it without
+//! enabling anything.
+
+use crate::app::App;
+use crate::ui;
+use ratatui::{backend::TestBackend, Terminal};
+
+/// Renders one frame of `app` on a `width` × `height` screen and returns
+/// its rows joined with line breaks.
+///
+/// Only the cell symbols are kept, not their styles, and trailing spaces
+/// are trimmed from every row.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::app::App;
+/// use lazyllama::testing::render_snapshot;
+///
+/// let mut app = App::builder().models(vec!["mock".to_string()]).build();
+/// let screen = render_snapshot(&mut app, 80, 24);
+/// assert_eq!(screen.lines().count(), 24);
+/// assert!(screen.contains("Conversation History"));
+/// ```
+pub fn render_snapshot(app: &mut App, width: u16, height: u16) -> String {
+    let mut terminal =
+        Terminal::new(TestBackend::new(width, height)).expect("TestBackend never fails");
+    terminal
+        .draw(|f| ui::ui(f, app))
+        .expect("TestBackend never fails");
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content
+        .chunks(usize::from(width.max(1)))
+        .map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
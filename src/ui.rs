@@ -37,15 +37,119 @@
 //! - Model status indicators and selection highlighting
 //! - Animated loading indicators
 
-use crate::app::App;
+use crate::app::{model_match, App, LogManager, ModelFilter, ModelInfoPopup};
+use crate::client::GenerationTimings;
+use crate::config::{AutoscrollMode, CodeBlockStyle, Labels, Snippet, TextDirection, MAX_SNIPPETS};
+use crate::diff::{diff_words, DiffOp};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
+use chrono::Local;
 use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// Fenced code block with an optional language tag, which may be followed
+/// by more info such as a file name (```` ```rust // src/main.rs ````).
+///
+/// Compiled once: the history is parsed on every frame while streaming.
+static CODE_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)```(?P<lang>\w+)?(?P<info>[^\n`]*)\n(?P<code>.*?)```").unwrap()
+});
+
+/// Opening fence without a closing one, up to the end of the text.
+static OPEN_FENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)```(?P<lang>\w+)?[^\n`]*\n(?P<code>.*)\z").unwrap());
+
+/// Inline image `![alt](url)`, reference link `[text][label]`,
+/// `~~strikethrough~~` or `==highlight==`. The text between `~~` or `==`
+/// must not start or end with a space, so `a == b` stays as it is, and
+/// `==` must not touch a letter or digit on the outside, so neither does
+/// `a==b and c==d`.
+static INLINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"!\[([^\]\n]*)\]\(([^()\s]+)\)|\[([^\[\]\n]+)\]\[([^\[\]\n]*)\]",
+        r"|~~(\S(?:[^\n]*?\S)?)~~|\B==(\S(?:[^\n]*?\S)?)==\B",
+    ))
+    .unwrap()
+});
+
+/// Typical lines of the languages [`detect_language`] recognizes. Each
+/// pattern counts once per code block.
+static LANGUAGE_MARKERS: LazyLock<Vec<(&'static str, Vec<Regex>)>> = LazyLock::new(|| {
+    let markers: [(&str, &[&str]); 4] = [
+        (
+            "rust",
+            &[
+                r"\bfn \w+\s*[<(]",
+                r"\blet (mut )?\w+",
+                r"^\s*(pub )?(struct|enum|impl|mod|trait)\b",
+                r"\w+::\w+",
+                r"\b(println|format|vec|assert_eq)!",
+                r"\)\s*->\s*\S",
+                r"&(mut |self\b|str\b)",
+            ],
+        ),
+        (
+            "python",
+            &[
+                r"^\s*def \w+\(.*\):\s*$",
+                r"^\s*(from \S+ )?import \w+[\w., ]*$",
+                r"^\s*(if|elif|else|for|while|class|with|try|except)\b.*:\s*$",
+                r"\bself\.",
+                r"\bprint\(",
+                r"\b(None|True|False)\b",
+                r"__\w+__",
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                r"^\s*(const|let|var) \w+\s*=",
+                r"=>",
+                r"\bfunction\b",
+                r"\bconsole\.\w+\(",
+                r"===|!==",
+                r#"\brequire\(|^\s*import .* from ['"]"#,
+                r"\b(document|window)\.",
+            ],
+        ),
+        (
+            "bash",
+            &[
+                r"^\s*(sudo|apt|apt-get|echo|cd|export|mkdir|cargo|npm|pip|git|curl|ls|rm|chmod)\b",
+                r"\$\{?[A-Za-z_]\w*\}?",
+                r"^\s*(fi|done|then|esac)\b",
+                r"\s(&&|\|\|)\s",
+                r"^\s*\$ ",
+            ],
+        ),
+    ];
+    markers
+        .iter()
+        .map(|(lang, patterns)| {
+            let patterns = patterns
+                .iter()
+                .map(|p| Regex::new(&format!("(?m){}", p)).unwrap())
+                .collect();
+            (*lang, patterns)
+        })
+        .collect()
+});
+
+/// Quoted JSON object key, possibly after the opening brace.
+static JSON_KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*[\[{]?\s*"[^"\n]*"\s*:"#).unwrap());
+
+/// Reference link definition line `[label]: url`.
+static LINK_DEFINITION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[([^\[\]]+)\]:\s*(\S+)\s*$").unwrap());
 
 /// ASCII art banner displayed at the top of the application.
 /// 
@@ -58,6 +162,81 @@ pub const BANNER: &str = r#"
 |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|
 "#;
 
+/// Key bindings listed in the help overlay.
+pub const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("Enter", "Send message (see send_key)"),
+    ("Shift+Enter", "Insert line break"),
+    ("Ctrl+Q", "Quit"),
+    ("Ctrl+C", "Clear conversation"),
+    ("Ctrl+L", "Clear input"),
+    ("Ctrl+N", "New conversation (saves the old one)"),
+    ("Ctrl+S", "Toggle autoscroll"),
+    ("Ctrl+Y", "Copy conversation"),
+    ("Ctrl+K", "Copy last code block"),
+    ("Ctrl+X", "Export code blocks to files"),
+    ("Ctrl+Shift+Y/Alt+Y", "Copy last answer"),
+    ("Alt+C", "Copy last request as curl"),
+    ("Ctrl+P", "Last answer to input"),
+    ("Ctrl/Alt+Plus/Minus", "Raise/lower temperature"),
+    ("Alt+]/Alt+[", "Raise/lower answer cap"),
+    ("Up/Down", "Switch model"),
+    ("Shift+Up/Down", "Move model in the list"),
+    ("Ctrl+A", "Show all / configured models"),
+    ("Ctrl+Tab/Ctrl+O", "Recent models"),
+    ("Ctrl+F", "Filter models"),
+    ("PgUp/PgDn", "Scroll"),
+    ("Alt+1..9", "Insert snippet"),
+    ("Ctrl+D", "Insert date/time"),
+    ("Alt+K", "Insert code block"),
+    ("Alt+Up/Down", "Highlight exchange"),
+    ("Ctrl+T", "Collapse/expand exchange"),
+    ("Ctrl+E", "Ask to explain exchange"),
+    ("Ctrl+B", "Set/remove bookmark"),
+    ("Ctrl+G", "Jump to bookmark"),
+    ("Ctrl+R", "Regenerate last answer"),
+    ("F2", "Switch host"),
+    ("F3", "Manage saved logs"),
+    ("F4", "Open data directory"),
+    ("F5", "Toggle compact transcript"),
+    ("F7", "Cycle text emphasis"),
+    ("F6", "Toggle request debug echo"),
+    ("F8", "Reload config file"),
+    ("F9", "Model details"),
+    ("F10", "Refresh models"),
+    ("F11", "Toggle focus mode"),
+    ("F1", "Toggle this help"),
+];
+
+/// Maximum number of input lines shown before the input box scrolls.
+pub const MAX_INPUT_LINES: usize = 6;
+
+/// Marker in front of the selected model.
+const HIGHLIGHT_SYMBOL: &str = ">> ";
+
+/// Clickable label at the end of every answer that copies it.
+pub const COPY_BUTTON: &str = "[copy]";
+
+/// Narrowest terminal the layout works in.
+pub const MIN_WIDTH: u16 = 40;
+
+/// Returns the smallest terminal, as width and height, that `app`'s layout
+/// fits in: every pane needs room for its borders and a line of text.
+///
+/// Focus mode and plain mode drop the banner, so they fit smaller
+/// terminals. [`ui`] shows only a notice below this size.
+pub fn min_terminal_size(app: &App) -> (u16, u16) {
+    // Conversation and input each need three lines with their borders.
+    let panes = 3 + 3;
+    let height = if app.focus_mode {
+        1 + panes
+    } else if app.plain {
+        1 + panes + 1
+    } else {
+        7 + panes + 1
+    };
+    (MIN_WIDTH, height)
+}
+
 /// Main rendering function for the Ratatui terminal interface.
 ///
 /// This function orchestrates the complete UI layout and rendering process,
@@ -85,6 +264,13 @@ pub const BANNER: &str = r#"
 /// └─────────────────────────────────────────┘
 /// ```
 ///
+/// In focus mode ([`App::focus_mode`]) the banner, model list and status bar
+/// are replaced by a one-line title and the conversation and input field
+/// span the whole terminal.
+///
+/// A terminal smaller than [`min_terminal_size`] only gets a centered
+/// notice naming the size needed.
+///
 /// # Features
 ///
 /// - **Model List**: Shows available AI models with status indicators
@@ -108,79 +294,127 @@ pub const BANNER: &str = r#"
 /// and is optimized for minimal computational overhead while providing
 /// smooth visual feedback.
 pub fn ui(f: &mut Frame, app: &mut App) {
+    let render_started = app.debug_keys.then(Instant::now);
     if app.debug_keys {
         app.render_count = app.render_count.wrapping_add(1);
     }
-    let root_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(7),
-            Constraint::Min(0),
-            Constraint::Length(1),
-        ])
-        .split(f.area());
-
-    f.render_widget(
-        Paragraph::new(BANNER)
-            .style(Style::default().fg(Color::Cyan))
-            .alignment(Alignment::Center),
-        root_layout[0],
-    );
-
-    let main_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
-        .split(root_layout[1]);
-
-    // Modellliste rendern mit erweiterten Informationen
     let selected_model = app.list_state.selected()
         .and_then(|i| app.models.get(i))
         .cloned()
         .unwrap_or_else(|| "None".to_string());
-    
-    let items: Vec<ListItem> = app
-        .models
-        .iter()
-        .enumerate()
-        .map(|(i, m)| {
-            let is_selected = app.list_state.selected() == Some(i);
-            let history_len = app.model_histories.get(m).map(|h| h.len()).unwrap_or(0);
-            let display = if history_len > 0 {
-                format!("{} [{}]", m, if history_len > 1000 { "📝" } else { "📄" })
-            } else {
-                m.clone()
-            };
-            ListItem::new(display)
-                .style(if is_selected {
-                    Style::default().fg(Color::Yellow)
-                } else {
-                    Style::default()
-                })
-        })
-        .collect();
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL)
-            .title(format!(" Models ({}) ", app.models.len())))
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
-    f.render_stateful_widget(list, main_chunks[0], &mut app.list_state);
+
+    let (min_width, min_height) = min_terminal_size(app);
+    if f.area().width < min_width || f.area().height < min_height {
+        app.copy_regions.clear();
+        let notice = format!("Terminal too small (need ≥ {}×{})", min_width, min_height);
+        let area = f.area();
+        // Narrow terminals wrap the notice over a few lines.
+        let lines = (notice.chars().count() as u16).div_ceil(area.width.max(1));
+        let row = Rect {
+            y: area.y + area.height.saturating_sub(lines) / 2,
+            height: lines.min(area.height),
+            ..area
+        };
+        f.render_widget(
+            Paragraph::new(notice).alignment(Alignment::Center).wrap(Wrap { trim: true }),
+            row,
+        );
+        return;
+    }
+
+    let (chat_area, status_area) = if app.focus_mode {
+        let root_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(f.area());
+        // The status bar is hidden, so status messages move into the title.
+        let title = match &app.status_text() {
+            Some(message) => format!(" {} [{}] ", message, selected_model),
+            None => format!(" LazyLlama [{}] | F11: Exit focus mode ", selected_model),
+        };
+        let title = if app.ephemeral {
+            format!(" ⚠ NOT SAVED |{}", title)
+        } else {
+            title
+        };
+        f.render_widget(
+            Paragraph::new(title).style(Style::default().fg(Color::Cyan)),
+            root_layout[0],
+        );
+        (root_layout[1], None)
+    } else {
+        let root_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                // The ASCII art banner means nothing to a screen reader.
+                Constraint::Length(if app.plain { 1 } else { 7 }),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(f.area());
+
+        f.render_widget(
+            Paragraph::new(if app.plain { "LazyLlama" } else { BANNER })
+                .style(Style::default().fg(Color::Cyan))
+                .alignment(Alignment::Center),
+            root_layout[0],
+        );
+
+        let main_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+            .split(root_layout[1]);
+        render_model_list(f, app, main_chunks[0]);
+        (main_chunks[1], Some(root_layout[2]))
+    };
 
     let chat_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(3), Constraint::Length(3)])
-        .split(main_chunks[1]);
+        .constraints([
+            Constraint::Min(3),
+            Constraint::Length(input_height(&app.input)),
+        ])
+        .split(chat_area);
 
     // Verlauf parsen und Scrollen berechnen
-    let history_text = parse_history(&app.history);
+    let render_options = RenderOptions {
+        current_model: app.list_state.selected().and_then(|i| app.models.get(i)).cloned(),
+        // A rule exactly as wide as the inner area never wraps, so it
+        // counts as a single line in the scroll computation below.
+        width: Some(chat_chunks[0].width.saturating_sub(2)),
+        collapsed: app.collapsed.clone(),
+        focused_exchange: app.focused_exchange,
+        // Diffing a partial answer on every streamed chunk is wasted work.
+        previous_answer: app.previous_answer.clone().filter(|_| !app.is_loading),
+        link_definitions: HashMap::new(),
+        labels: app.labels.clone(),
+        compact: app.compact,
+        code_block_style: app.code_block_style,
+        emphasis: app.emphasis,
+        plain: app.plain,
+        number_turns: app.number_turns,
+        text_direction: app.text_direction,
+    };
+    let (history_text, exchange_offsets) = parse_history_layout(&app.history, &render_options);
     let visible_height = chat_chunks[0].height.saturating_sub(2);
     let total_lines = history_text.height() as u16;
+    let copy_lines = copy_button_lines(&app.history, &history_text, &exchange_offsets, app.is_loading);
+
+    if app.scroll_to_focus {
+        app.scroll_to_focus = false;
+        if let Some(&offset) = app.focused_exchange.and_then(|i| exchange_offsets.get(i)) {
+            app.autoscroll = false;
+            app.scroll = offset as u16;
+        }
+    }
 
     if app.autoscroll {
-        app.scroll = total_lines.saturating_sub(visible_height);
+        let bottom = total_lines.saturating_sub(visible_height);
+        app.scroll = match app.autoscroll_mode {
+            AutoscrollMode::Bottom => bottom,
+            AutoscrollMode::AnswerStart => answer_start_line(&history_text, &exchange_offsets, &app.labels)
+                .map_or(bottom, |line| bottom.min(line as u16)),
+        };
     } else {
         let max_scroll = total_lines.saturating_sub(visible_height);
         if app.scroll > max_scroll {
@@ -210,222 +444,1947 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             .scroll((app.scroll, 0)),
         chat_chunks[0],
     );
+    if let Some(bookmark) = app.bookmark.filter(|_| !app.plain) {
+        // Drawn on the right border like a scrollbar thumb.
+        let area = chat_chunks[0];
+        if area.width > 0 && visible_height > 0 {
+            let row = bookmark_marker_row(bookmark, total_lines, visible_height);
+            f.buffer_mut()[(area.right() - 1, area.y + 1 + row)]
+                .set_symbol("◆")
+                .set_fg(Color::Cyan);
+        }
+    }
+
+    // Copy buttons sit on the last line of each answer, usually its rule,
+    // and are hit-tested against mouse clicks by `App::click`.
+    app.copy_regions.clear();
+    let area = chat_chunks[0];
+    let button_width = COPY_BUTTON.len() as u16;
+    if area.width.saturating_sub(2) > button_width {
+        for (index, line) in copy_lines {
+            let Some(row) = (line as u16).checked_sub(app.scroll).filter(|&r| r < visible_height) else {
+                continue;
+            };
+            let rect = Rect::new(area.right() - 1 - button_width, area.y + 1 + row, button_width, 1);
+            f.buffer_mut()
+                .set_string(rect.x, rect.y, COPY_BUTTON, Style::default().fg(Color::DarkGray));
+            app.copy_regions.push((rect, index));
+        }
+    }
 
     // Spinner-Animation berechnen
     let spinner_frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
     let frame_idx = (app.start_time.elapsed().as_millis() / 100) as usize % spinner_frames.len();
+    let overflow = app.context_overflow();
     let input_title = if app.is_loading {
-        format!(" {} AI is thinking... ", spinner_frames[frame_idx])
+        let progress = app
+            .stream_start
+            .and_then(|start| app.history.get(start..))
+            .and_then(stream_progress)
+            .map(|p| format!("{} ", p))
+            .unwrap_or_default();
+        let hint = app
+            .active_thinking_hint()
+            .map(|hint| format!("({}) ", hint))
+            .unwrap_or_default();
+        // A spinning frame would be announced over and over.
+        let spinner = if app.plain || app.quiet { "" } else { spinner_frames[frame_idx] };
+        format!(" {} AI is thinking... {}{}", spinner, progress, hint)
     } else {
-        " > Input ".into()
+        let mut title = String::from(" > Input ");
+        let files = attached_files(&app.input);
+        if !files.is_empty() {
+            title.push_str(&format!("📎 {} ", files.join(", ")));
+        }
+        if let Some((estimate, limit)) = overflow {
+            title.push_str(&format!("⚠ ~{} of {} tokens, may not fit the context ", estimate, limit));
+        }
+        title
     };
 
-    let input_chars: Vec<char> = app.input.chars().collect();
-    let cursor_pos = app.cursor_pos.min(input_chars.len());
-    let mut input_spans = Vec::new();
+    // Plain mode shows the terminal's own cursor, which screen readers
+    // follow, instead of a reversed cell.
+    let input_text = input_text(&app.input, app.cursor_pos, app.cursor_visible && !app.plain);
+    // Keep the cursor line visible when the input is taller than its box.
+    let input_visible = chat_chunks[1].height.saturating_sub(2).max(1);
+    let cursor_line = input_cursor_line(&app.input, app.cursor_pos) as u16;
+    let input_scroll = cursor_line.saturating_sub(input_visible - 1);
+    // Long lines are not wrapped but scrolled to keep the cursor in view.
+    let input_width = chat_chunks[1].width.saturating_sub(2);
+    let input_scroll_x = input_scroll_x(&app.input, app.cursor_pos, input_width);
+    let (hidden_left, hidden_right) = input_overflow(&app.input, input_scroll_x, input_width);
 
-    if cursor_pos > 0 {
-        let before: String = input_chars[..cursor_pos].iter().collect();
-        input_spans.push(Span::raw(before));
+    let mut input_block = Block::default()
+        .borders(Borders::ALL)
+        .title(input_title)
+        .border_style(if app.is_loading {
+            Style::default().fg(Color::Yellow)
+        } else if overflow.is_some() {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        });
+    let indicator_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    if hidden_left {
+        input_block = input_block.title_bottom(Line::styled("‹", indicator_style).left_aligned());
+    }
+    if hidden_right {
+        input_block = input_block.title_bottom(Line::styled("›", indicator_style).right_aligned());
+    }
+    f.render_widget(
+        Paragraph::new(input_text)
+            .scroll((input_scroll, input_scroll_x))
+            .block(input_block),
+        chat_chunks[1],
+    );
+    if app.plain {
+        let column = app.input.chars().take(app.cursor_pos).collect::<String>();
+        let column = column.rsplit('\n').next().unwrap_or("").chars().count() as u16;
+        let area = chat_chunks[1];
+        f.set_cursor_position((
+            area.x + 1 + column.saturating_sub(input_scroll_x),
+            area.y + 1 + cursor_line.saturating_sub(input_scroll),
+        ));
+    }
+    if let Some(status_area) = status_area {
+        render_status_bar(f, app, &selected_model, total_lines, visible_height, status_area);
     }
 
-    let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
-    if cursor_pos < input_chars.len() {
-        let ch = input_chars[cursor_pos].to_string();
-        if app.cursor_visible {
-            input_spans.push(Span::styled(ch, cursor_style));
-        } else {
-            input_spans.push(Span::raw(ch));
-        }
+    if let Some(selected) = app.mru_selection {
+        render_mru_switcher(f, app, selected);
+    }
+    if let Some(selected) = app.host_selection {
+        render_host_picker(f, app, selected);
+    }
+    if let Some(filter) = &app.model_filter {
+        render_model_filter(f, app, filter);
+    }
+    if let Some(manager) = &app.log_manager {
+        render_log_manager(f, manager);
+    }
+    if let Some(popup) = &app.model_info_popup {
+        render_model_info(f, popup);
+    }
+    if let Some(message) = &app.quit_confirmation {
+        render_quit_confirmation(f, message);
+    }
+    if app.show_help {
+        render_help(f, app);
+    }
+    if app.plain {
+        plain_buffer(f.buffer_mut());
+    }
+    if let Some(started) = render_started {
+        app.last_render_us = started.elapsed().as_micros() as u64;
+    }
+}
 
-        if cursor_pos + 1 < input_chars.len() {
-            let after: String = input_chars[cursor_pos + 1..].iter().collect();
-            input_spans.push(Span::raw(after));
+/// Turns a rendered frame into plain text for screen readers: box-drawing
+/// and block characters (borders, dividers, code frames) become spaces and
+/// all colors and modifiers are removed.
+pub fn plain_buffer(buffer: &mut Buffer) {
+    for cell in buffer.content.iter_mut() {
+        let decorative = cell
+            .symbol()
+            .chars()
+            .all(|c| ('\u{2500}'..='\u{259F}').contains(&c) || c == '◆');
+        if decorative && !cell.symbol().is_empty() {
+            cell.set_symbol(" ");
         }
-    } else if app.cursor_visible {
-        input_spans.push(Span::styled(" ", cursor_style));
+        cell.set_style(Style::reset());
     }
+}
 
-    let input_text = Text::from(Line::from(input_spans));
+/// Height of the input box including borders: one row per input line,
+/// capped at [`MAX_INPUT_LINES`].
+pub fn input_height(input: &str) -> u16 {
+    let lines = input.split('\n').count().min(MAX_INPUT_LINES);
+    lines as u16 + 2
+}
 
-    f.render_widget(
-        Paragraph::new(input_text).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(input_title)
-                .border_style(if app.is_loading {
-                    Style::default().fg(Color::Yellow)
-                } else {
-                    Style::default()
-                }),
-        ),
-        chat_chunks[1],
-    );
-    let mut status = format!(
-        " C-q: Quit | C-c: Clear | C-s: AutoScroll | PgUp/Dn: Scroll | ↑↓: Switch Model [{}] ",
-        selected_model
-    );
-    if app.debug_keys {
-        let max_scroll = total_lines.saturating_sub(visible_height);
-        let last_key = app.debug_last_key.as_deref().unwrap_or("-");
-        status.push_str(&format!(
-            "| Scroll: {}/{} | Render: {} | Key: {} ",
-            app.scroll, max_scroll, app.render_count, last_key
-        ));
+/// Index of the input line the cursor (a char position) is on.
+pub fn input_cursor_line(input: &str, cursor_pos: usize) -> usize {
+    input.chars().take(cursor_pos).filter(|&c| c == '\n').count()
+}
+
+/// Horizontal scroll offset of the input field, in columns, that keeps the
+/// cursor (a char position) inside a box `width` columns wide.
+pub fn input_scroll_x(input: &str, cursor_pos: usize, width: u16) -> u16 {
+    let line_start = input
+        .chars()
+        .take(cursor_pos)
+        .enumerate()
+        .filter(|&(_, c)| c == '\n')
+        .last()
+        .map_or(0, |(i, _)| i + 1);
+    let column = cursor_pos.min(input.chars().count()) - line_start;
+    // The cursor cell itself must fit as well.
+    (column + 1).saturating_sub(width as usize) as u16
+}
+
+/// Whether input text is hidden left and right of the visible columns
+/// `offset..offset + width`, for the `‹`/`›` indicators.
+pub fn input_overflow(input: &str, offset: u16, width: u16) -> (bool, bool) {
+    let longest = input.split('\n').map(|l| l.chars().count()).max().unwrap_or(0);
+    let left = offset > 0 && longest > 0;
+    let right = longest > offset as usize + width as usize;
+    (left, right)
+}
+
+/// Builds the input field text with one line per `\n` and the cursor shown
+/// as a reversed cell at `cursor_pos` (a char position).
+///
+/// A cursor on a line break or at the end is drawn as a reversed space.
+pub fn input_text(input: &str, cursor_pos: usize, cursor_visible: bool) -> Text<'static> {
+    let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+    let cursor_pos = cursor_pos.min(input.chars().count());
+    let mut text = Text::default();
+    let mut line_start = 0;
+    for line in input.split('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        let line_end = line_start + chars.len();
+        let mut spans = Vec::new();
+        if cursor_visible && (line_start..=line_end).contains(&cursor_pos) {
+            let col = cursor_pos - line_start;
+            if col > 0 {
+                spans.push(Span::raw(chars[..col].iter().collect::<String>()));
+            }
+            match chars.get(col) {
+                Some(c) => {
+                    spans.push(Span::styled(c.to_string(), cursor_style));
+                    if col + 1 < chars.len() {
+                        spans.push(Span::raw(chars[col + 1..].iter().collect::<String>()));
+                    }
+                }
+                None => spans.push(Span::styled(" ", cursor_style)),
+            }
+        } else {
+            spans.push(Span::raw(line.to_string()));
+        }
+        text.lines.push(Line::from(spans));
+        line_start = line_end + 1;
     }
+    text
+}
+
+/// Renders the help overlay as a centered popup.
+fn render_help(f: &mut Frame, app: &App) {
+    let text = help_text(&app.snippets);
+    let height = (text.lines.len() as u16).saturating_add(2);
+    let area = centered_rect(60, height, f.area());
+    f.render_widget(Clear, area);
     f.render_widget(
-        Paragraph::new(status).style(Style::default().bg(Color::White).fg(Color::Black)),
-        root_layout[2],
+        Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title(" Help (any key: close) "))
+            .wrap(Wrap { trim: false }),
+        area,
     );
 }
 
-/// Parses conversation history and converts it into a formatted Ratatui Text object.
-///
-/// This function processes the raw conversation history string and applies syntax
-/// highlighting for markdown elements, particularly code blocks. It uses regex
-/// pattern matching to identify code blocks and delegates regular text processing
-/// to [`process_styled_text`].
-///
-/// # Arguments
-///
-/// * `history` - The raw conversation history string containing user and AI messages
-///
-/// # Returns
-///
-/// A formatted [`Text`] object ready for rendering with Ratatui, containing:
-/// - Syntax-highlighted code blocks with language-specific borders
-/// - Styled user/AI message labels with appropriate colors
-/// - Markdown formatting for headers and emphasis
-///
-/// # Code Block Processing
-///
-/// Code blocks are detected using the regex pattern:
-/// ```regex
-/// (?s)```(?P<lang>\w+)?\n(?P<code>.*?)```
-/// ```
-///
-/// Each code block is rendered with:
-/// - Language-specific header: `┌── rust ──`
-/// - Yellow-colored borders and prefixes
-/// - Preserved indentation and formatting
-/// - Consistent visual separation from regular text
-///
-/// # Performance
-///
-/// Uses single-pass regex processing with efficient string slicing to minimize
-/// allocations. The function handles large conversation histories gracefully
-/// without significant performance degradation.
-///
-/// # Example Input/Output
+/// Builds the contents of the help overlay.
 ///
-/// ```text
-/// Input: "YOU: Hello\n\nAI: Here's some code:\n\n```rust\nfn main() {}\n```"
-/// Output: Formatted Text with colored labels and bordered code block
-/// ```
-pub fn parse_history<'a>(history: &'a str) -> Text<'a> {
-    let code_block_re = Regex::new(r"(?s)```(?P<lang>\w+)?\n(?P<code>.*?)```").unwrap();
+/// Lists [`KEY_BINDINGS`] followed by the configured snippets with their
+/// `Alt+N` shortcut. Snippets beyond [`MAX_SNIPPETS`] are not reachable by
+/// a shortcut and are left out.
+pub fn help_text(snippets: &[Snippet]) -> Text<'static> {
+    let key_style = Style::default().fg(Color::Yellow);
+    let heading_style = Style::default().add_modifier(Modifier::BOLD);
     let mut text = Text::default();
-    let mut last_match_end = 0;
-
-    for caps in code_block_re.captures_iter(history) {
-        let full_match = caps.get(0).unwrap();
-        if full_match.start() > last_match_end {
-            process_styled_text(&history[last_match_end..full_match.start()], &mut text);
-        }
-        let lang = caps.name("lang").map_or("code", |m| m.as_str());
-        let code_content = caps.name("code").map_or("", |m| m.as_str());
-
-        text.push_line(Line::from(Span::styled(
-            format!(" ┌── {} ──", lang),
-            Style::default().fg(Color::Yellow),
-        )));
-        for line in code_content.lines() {
-            text.push_line(Line::from(vec![
-                Span::styled(" │ ", Style::default().fg(Color::Yellow)),
-                Span::raw(line),
-            ]));
-        }
-        text.push_line(Line::from(Span::styled(
-            " └──────────",
-            Style::default().fg(Color::Yellow),
+    for (key, action) in KEY_BINDINGS {
+        text.lines.push(Line::from(vec![
+            Span::styled(format!("{:<16}", key), key_style),
+            Span::raw(*action),
+        ]));
+    }
+    text.lines.push(Line::from(""));
+    text.lines.push(Line::from(Span::styled("Snippets", heading_style)));
+    if snippets.is_empty() {
+        text.lines.push(Line::from(Span::styled(
+            "No snippets configured (see config.toml)",
+            Style::default().fg(Color::DarkGray),
         )));
-        last_match_end = full_match.end();
     }
-    if last_match_end < history.len() {
-        process_styled_text(&history[last_match_end..], &mut text);
+    for (i, snippet) in snippets.iter().take(MAX_SNIPPETS).enumerate() {
+        text.lines.push(Line::from(vec![
+            Span::styled(format!("{:<16}", format!("Alt+{}", i + 1)), key_style),
+            Span::raw(snippet.name.clone()),
+        ]));
     }
     text
 }
 
-/// Processes regular text line-by-line and applies styling for labels and markdown headers.
-///
-/// This function handles non-code text formatting, applying appropriate colors and
-/// styles to different types of content including conversation labels, markdown
-/// headers, and regular text. It preserves the original text structure while
-/// adding visual styling.
-///
-/// # Arguments
+/// Shortens `name` to at most `max_width` characters by replacing its
+/// middle with `…`.
 ///
-/// * `text` - The raw text string to be processed and styled
-/// * `target` - Mutable reference to the Text object where styled content is appended
-///
-/// # Styling Rules
+/// The family prefix and the tag after the last `:` are kept as long as
+/// the tag leaves room for part of the family; otherwise both ends of the
+/// name are kept evenly.
 ///
-/// - **Headers**: Lines starting with `###` are converted to bullet points (`•`) in bold white
-/// - **User Messages**: "YOU:" prefix is styled in bold magenta, rest in default color
-/// - **AI Messages**: "AI:" prefix is styled in bold cyan, rest in default color
-/// - **Regular Text**: Rendered without special styling in default terminal colors
-///
-/// # Text Processing
+/// # Example
 ///
-/// The function processes each line individually and:
-/// 1. Trims whitespace to detect line types
-/// 2. Creates appropriate styled spans based on content
-/// 3. Preserves original text after removing formatting markers
-/// 4. Combines spans into cohesive line objects
-/// 
-/// # Color Scheme
+/// ```
+/// use lazyllama::ui::truncate_middle;
 ///
-/// - Headers: White with bold modifier
-/// - User labels: Magenta with bold modifier
-/// - AI labels: Cyan with bold modifier
-/// - Regular text: Default terminal colors
+/// assert_eq!(truncate_middle("llama3:8b", 20), "llama3:8b");
+/// assert_eq!(truncate_middle("deepseek-coder-v2-lite-instruct:16b", 16), "deepseek-co…:16b");
+/// assert_eq!(truncate_middle("qwen2.5:7b-instruct-q4_K_M", 11), "qwen2…4_K_M");
+/// ```
+pub fn truncate_middle(name: &str, max_width: usize) -> String {
+    let len = name.chars().count();
+    if len <= max_width {
+        return name.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let keep = max_width - 1;
+    let tail_len = match name.rfind(':') {
+        // Keep the tag and at least half the room for the family.
+        Some(colon) if name[colon..].chars().count() <= keep / 2 => name[colon..].chars().count(),
+        _ => keep / 2,
+    };
+    let head: String = name.chars().take(keep - tail_len).collect();
+    let tail: String = name.chars().skip(len - tail_len).collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Returns the exchange index and rendered line of the copy button of
+/// every answered exchange: the exchange's last non-blank line.
 ///
-/// # Side Effects
+/// The answer still streaming while `loading` gets no button.
+fn copy_button_lines(
+    history: &str,
+    text: &Text,
+    offsets: &[usize],
+    loading: bool,
+) -> Vec<(usize, usize)> {
+    let exchanges = split_exchanges(strip_trailing_separator(history));
+    let is_blank = |line: &Line| line.spans.iter().all(|s| s.content.trim().is_empty());
+    let mut buttons = Vec::new();
+    for (index, exchange) in exchanges.iter().enumerate() {
+        if exchange_parts(exchange).is_none() || (loading && index + 1 == exchanges.len()) {
+            continue;
+        }
+        let (Some(&start), end) = (offsets.get(index), offsets.get(index + 1)) else {
+            continue;
+        };
+        let end = end.copied().unwrap_or(text.lines.len());
+        if let Some(line) = (start..end).rev().find(|&l| !is_blank(&text.lines[l])) {
+            buttons.push((index, line));
+        }
+    }
+    buttons
+}
+
+/// Renders the model list with history indicators into `area`.
 ///
-/// Appends styled content directly to the provided `target` Text object,
-/// allowing for incremental building of complex formatted documents.
-pub fn process_styled_text<'a>(text: &'a str, target: &mut Text<'a>) {
-    for line in text.lines() {
-        let trimmed = line.trim();
-        let mut spans = Vec::new();
-        if trimmed.starts_with("###") {
-            spans.push(Span::styled(
-                format!("● {}", trimmed.trim_start_matches('#').trim()),
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            ));
-        } else if line.starts_with("YOU:") {
-            spans.push(Span::styled(
-                "YOU:",
-                Style::default()
-                    .fg(Color::Magenta)
-                    .add_modifier(Modifier::BOLD),
-            ));
-            spans.push(Span::raw(&line[4..]));
-        } else if line.starts_with("AI:") {
-            spans.push(Span::styled(
-                "AI: ",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            ));
-            spans.push(Span::raw(&line[3..]));
-        } else {
-            spans.push(Span::raw(line));
+/// Names too long for the pane are shortened with [`truncate_middle`]; the
+/// status bar shows the selected model's full name.
+fn render_model_list(f: &mut Frame, app: &mut App, area: Rect) {
+    // Borders and the highlight symbol, which the list reserves on every row.
+    let name_width = usize::from(area.width).saturating_sub(2 + HIGHLIGHT_SYMBOL.len());
+    let dirty = app.has_unsaved_buffer();
+    let items: Vec<ListItem> = app
+        .models
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let is_selected = app.list_state.selected() == Some(i);
+            let history_len = app.model_histories.get(m).map(|h| h.len()).unwrap_or(0);
+            // Unsaved edits of the selected model get a trailing "*".
+            let marker = if is_selected && dirty { "*" } else { "" };
+            let name_width = name_width.saturating_sub(marker.len());
+            let display = if history_len > 0 {
+                // " [📝]" takes five columns.
+                let name = truncate_middle(m, name_width.saturating_sub(5));
+                format!("{} [{}]{}", name, if history_len > 1000 { "📝" } else { "📄" }, marker)
+            } else {
+                format!("{}{}", truncate_middle(m, name_width), marker)
+            };
+            ListItem::new(display)
+                .style(if is_selected {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                })
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL)
+            .title(if app.models.len() == app.all_models.len() {
+                format!(" Models ({}) ", app.models.len())
+            } else {
+                format!(" Models ({}/{}) ", app.models.len(), app.all_models.len())
+            }))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// Renders the status bar with key hints or the current status message.
+fn render_status_bar(
+    f: &mut Frame,
+    app: &App,
+    selected_model: &str,
+    total_lines: u16,
+    visible_height: u16,
+    area: Rect,
+) {
+    let mut status = match &app.status_text() {
+        Some(message) => format!(" {} [{}] ", message, selected_model),
+        None => format!(
+            " F1: Help | C-q: Quit | C-n: New | C-c: Clear | C-s: AutoScroll | C-y/C-k: Copy | PgUp/Dn: Scroll | ↑↓: Switch Model [{}] ",
+            selected_model
+        ),
+    };
+    if let Some(host) = app.active_host_name() {
+        status.insert_str(0, &format!(" Host: {} |", host));
+    }
+    if app.histories_loading {
+        status.insert_str(0, " ⟳ loading previous conversations… |");
+    }
+    if !app.pending_prompts.is_empty() {
+        status.insert_str(0, &format!(" Queued: {} |", app.pending_prompts.len()));
+    }
+    if app.ephemeral {
+        status.insert_str(0, " ⚠ NOT SAVED |");
+    }
+    if app.status_session {
+        let duration = format_session_duration(app.start_time.elapsed());
+        status.insert_str(0, &format!(" Session {} |", duration));
+    }
+    if app.status_clock {
+        status.insert_str(0, &format!(" {} |", Local::now().format("%H:%M:%S")));
+    }
+    if app.debug_keys {
+        let max_scroll = total_lines.saturating_sub(visible_height);
+        let last_key = app.debug_last_key.as_deref().unwrap_or("-");
+        // Timings are from the previous frame; this one is still rendering.
+        status.push_str(&format!(
+            "| Ollama: {} | Scroll: {}/{} | Render: {} ({}µs) | Append: {}µs | Key: {} ",
+            app.server_version.as_deref().unwrap_or("?"),
+            app.scroll,
+            max_scroll,
+            app.render_count,
+            app.last_render_us,
+            app.last_append_us,
+            last_key
+        ));
+    }
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().bg(Color::White).fg(Color::Black)),
+        area,
+    );
+}
+
+/// Renders the recent-models switcher as a centered popup.
+fn render_mru_switcher(f: &mut Frame, app: &App, selected: usize) {
+    let order = app.mru_order();
+    let items: Vec<ListItem> = order
+        .iter()
+        .map(|model| {
+            let ago = app
+                .last_activity
+                .get(model)
+                .map(|t| format!(" · {}", format_ago(t.elapsed())))
+                .unwrap_or_default();
+            ListItem::new(Line::from(vec![
+                Span::raw(model.clone()),
+                Span::styled(ago, Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+    let height = (order.len() as u16).saturating_add(2);
+    let area = centered_rect(50, height, f.area());
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Recent (Enter: switch, Esc: cancel) "),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> "),
+        area,
+        &mut state,
+    );
+}
+
+/// Renders the host picker as a centered popup.
+fn render_host_picker(f: &mut Frame, app: &App, selected: usize) {
+    let items: Vec<ListItem> = app
+        .hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| {
+            let marker = if i == app.active_host { " (active)" } else { "" };
+            ListItem::new(Line::from(vec![
+                Span::raw(host.name.clone()),
+                Span::styled(
+                    format!(" · {}{}", host.url, marker),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let height = (app.hosts.len() as u16).saturating_add(2);
+    let area = centered_rect(60, height, f.area());
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Hosts (Enter: switch, Esc: cancel) "),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> "),
+        area,
+        &mut state,
+    );
+}
+
+/// Most matches the model filter shows at once; more scroll.
+const MODEL_FILTER_ROWS: u16 = 10;
+
+/// Renders the model filter as a centered popup, with the matched
+/// characters of each model highlighted.
+fn render_model_filter(f: &mut Frame, app: &App, filter: &ModelFilter) {
+    let matched = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut items: Vec<ListItem> = filter
+        .filtered_indices
+        .iter()
+        .filter_map(|&i| app.models.get(i))
+        .map(|model| {
+            let indices = model_match(model, &filter.query, app.fuzzy_filter)
+                .map(|(_, indices)| indices)
+                .unwrap_or_default();
+            let spans: Vec<Span> = model
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    let style = if indices.contains(&i) { matched } else { Style::default() };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let mut state = ListState::default();
+    if items.is_empty() {
+        items.push(ListItem::new(Span::styled(
+            "No matching models",
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else {
+        state.select(Some(filter.selected));
+    }
+    let height = (items.len() as u16).min(MODEL_FILTER_ROWS).saturating_add(2);
+    let area = centered_rect(60, height, f.area());
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" Filter: {}▏(Enter: switch, Esc: cancel) ", filter.query)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> "),
+        area,
+        &mut state,
+    );
+}
+
+/// Renders the quit confirmation as a centered popup.
+fn render_quit_confirmation(f: &mut Frame, message: &str) {
+    // Long save paths wrap, so size the popup for the wrapped message.
+    let width = centered_rect(60, 0, f.area()).width.saturating_sub(2).max(1);
+    let lines = message.chars().count().div_ceil(usize::from(width)).max(1) as u16;
+    let area = centered_rect(60, lines.saturating_add(2), f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(message)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red))
+                    .title(" Quit? (y/C-q: quit, other keys: stay) "),
+            ),
+        area,
+    );
+}
+
+/// Renders the log manager as a centered popup.
+fn render_log_manager(f: &mut Frame, manager: &LogManager) {
+    let mut items: Vec<ListItem> = manager
+        .groups
+        .iter()
+        .map(|group| {
+            ListItem::new(Line::from(vec![
+                Span::raw(group.model.clone()),
+                Span::styled(
+                    format!(
+                        " · {} file{} · {}",
+                        group.files,
+                        if group.files == 1 { "" } else { "s" },
+                        format_size(group.bytes)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new(Span::styled(
+            "No saved logs",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let title = match manager.groups.get(manager.selected) {
+        Some(group) if manager.confirm_delete => {
+            format!(" Delete {} logs of {}? (y/n) ", group.files, group.model)
+        }
+        _ => " Logs (d: delete, Esc: close) ".to_string(),
+    };
+    let total: u64 = manager.groups.iter().map(|g| g.bytes).sum();
+    let total = Line::from(format!(" Total: {} ", format_size(total))).right_aligned();
+    let height = (items.len() as u16).saturating_add(2);
+    let area = centered_rect(60, height, f.area());
+    let mut state = ListState::default();
+    state.select((!manager.groups.is_empty()).then_some(manager.selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(if manager.confirm_delete {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default()
+                    })
+                    .title(title)
+                    .title_bottom(total),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> "),
+        area,
+        &mut state,
+    );
+}
+
+/// Renders the model info popup, centered, with an error line if the
+/// details could not be fetched.
+fn render_model_info(f: &mut Frame, popup: &ModelInfoPopup) {
+    let label = |name: &str| Span::styled(format!("{}: ", name), Style::default().fg(Color::Cyan));
+    let unknown = || "unknown".to_string();
+    let lines: Vec<Line> = match &popup.info {
+        Ok(info) => vec![
+            Line::from(vec![label("Family"), Span::raw(info.family.clone().unwrap_or_else(unknown))]),
+            Line::from(vec![
+                label("Parameters"),
+                Span::raw(info.parameter_size.clone().unwrap_or_else(unknown)),
+            ]),
+            Line::from(vec![
+                label("Quantization"),
+                Span::raw(info.quantization.clone().unwrap_or_else(unknown)),
+            ]),
+            Line::from(vec![
+                label("Context length"),
+                Span::raw(info.context_length.map_or_else(unknown, |n| format!("{} tokens", n))),
+            ]),
+            Line::from(vec![label("License"), Span::raw(info.license.clone().unwrap_or_else(unknown))]),
+        ],
+        Err(e) => vec![Line::from(Span::styled(
+            format!("⚠ Could not fetch the details: {}", e),
+            Style::default().fg(Color::Red),
+        ))],
+    };
+    let area = centered_rect(60, 9, f.area());
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .scroll((popup.scroll, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} (Esc: close) ", popup.model)),
+            ),
+        area,
+    );
+}
+
+/// Formats a file size like `512 B`, `1.2 KB` or `3.4 MB`.
+pub(crate) fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Returns the names of the files attached to `input` with `/file`: every
+/// [`ATTACHMENT_PREFIX`] line directly followed by a code fence.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::attached_files;
+///
+/// let input = "Why?\nFile: src/main.rs\n```rust\nfn main() {}\n```\n";
+/// assert_eq!(attached_files(input), ["src/main.rs"]);
+/// assert!(attached_files("File: src/main.rs").is_empty());
+/// ```
+pub fn attached_files(input: &str) -> Vec<&str> {
+    let lines: Vec<&str> = input.lines().collect();
+    lines
+        .windows(2)
+        .filter(|pair| pair[1].starts_with("```"))
+        .filter_map(|pair| pair[0].strip_prefix(ATTACHMENT_PREFIX))
+        .collect()
+}
+
+/// Returns the row (relative to the top of the visible history) at which
+/// the marker for a bookmarked scroll position is drawn.
+///
+/// The bookmark is scaled onto the visible height like a scrollbar thumb,
+/// so the top and bottom rows stand for the first and last scroll position.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::bookmark_marker_row;
+///
+/// assert_eq!(bookmark_marker_row(0, 100, 10), 0);
+/// assert_eq!(bookmark_marker_row(90, 100, 10), 9);
+/// assert_eq!(bookmark_marker_row(45, 100, 10), 4);
+/// ```
+pub fn bookmark_marker_row(bookmark: u16, total_lines: u16, visible_height: u16) -> u16 {
+    let last_row = visible_height.saturating_sub(1);
+    let max_scroll = total_lines.saturating_sub(visible_height);
+    if max_scroll == 0 {
+        return 0;
+    }
+    let bookmark = bookmark.min(max_scroll) as u32;
+    (bookmark * last_row as u32 / max_scroll as u32) as u16
+}
+
+/// Returns a rectangle of `percent_x` width and `height` rows centered in `area`.
+pub fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width.saturating_mul(percent_x) / 100;
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    }
+}
+
+/// Formats an elapsed duration as a short relative time like `5m ago`.
+fn format_ago(elapsed: std::time::Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+/// Formats how long the session has been running, as `M:SS` for the first
+/// hour and `H:MM:SS` after that.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::format_session_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(format_session_duration(Duration::from_secs(75)), "1:15");
+/// assert_eq!(format_session_duration(Duration::from_secs(3725)), "1:02:05");
+/// ```
+pub fn format_session_duration(elapsed: std::time::Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours == 0 {
+        format!("{}:{:02}", minutes, seconds)
+    } else {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+/// A fenced code block extracted from the raw conversation history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// Language tag from the opening fence, if one was given.
+    pub lang: Option<String>,
+    /// Rest of the opening fence line after the language, trimmed, e.g.
+    /// `// src/main.rs`.
+    pub info: String,
+    /// Source code between the fences, without the fences themselves.
+    pub code: String,
+}
+
+/// Extracts all complete fenced code blocks from the conversation history.
+///
+/// Uses the same fence rules as [`parse_history`], so every block that is
+/// rendered with a frame is returned here in document order. Unterminated
+/// fences are ignored.
+pub fn extract_code_blocks(history: &str) -> Vec<CodeBlock> {
+    CODE_BLOCK_RE
+        .captures_iter(history)
+        .map(|caps| CodeBlock {
+            lang: caps.name("lang").map(|m| m.as_str().to_string()),
+            info: caps.name("info").map_or("", |m| m.as_str().trim()).to_string(),
+            code: caps.name("code").map_or("", |m| m.as_str()).to_string(),
+        })
+        .collect()
+}
+
+/// Guesses the language of a code block whose fence has no language tag.
+///
+/// Recognizes Rust, Python, JavaScript, shell scripts and JSON by typical
+/// keywords and patterns. The guess is conservative: at least two distinct
+/// markers of one language must match, more than of any other, otherwise
+/// `None` is returned and the block stays labeled `code`.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::detect_language;
+///
+/// assert_eq!(detect_language("fn main() {\n    println!(\"Hi\");\n}"), Some("rust"));
+/// assert_eq!(detect_language("{\"name\": \"llama\"}"), Some("json"));
+/// assert_eq!(detect_language("hello world"), None);
+/// ```
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim();
+    if trimmed.starts_with("#!") {
+        let shebang = trimmed.lines().next().unwrap_or("");
+        if shebang.ends_with("sh") {
+            return Some("bash");
+        }
+        if shebang.contains("python") {
+            return Some("python");
+        }
+    }
+    let json_like = matches!(
+        (trimmed.chars().next(), trimmed.chars().last()),
+        (Some('{'), Some('}')) | (Some('['), Some(']'))
+    );
+    if json_like && looks_like_json(trimmed) {
+        return Some("json");
+    }
+
+    let mut scores: Vec<(&str, usize)> = LANGUAGE_MARKERS
+        .iter()
+        .map(|(lang, patterns)| (*lang, patterns.iter().filter(|p| p.is_match(code)).count()))
+        .collect();
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    match scores.as_slice() {
+        [(lang, best), (_, second), ..] if *best >= 2 && best > second => Some(lang),
+        _ => None,
+    }
+}
+
+/// Whether `text`, already known to be wrapped in braces or brackets, looks
+/// like JSON: quoted keys or values, and nothing a program would contain.
+fn looks_like_json(text: &str) -> bool {
+    JSON_KEY_RE.is_match(text) && !text.contains(';') && !text.contains('=')
+}
+
+/// Line written by `App::send_query` after every completed turn.
+pub const TURN_SEPARATOR: &str = "---";
+
+/// Prefix of the timing footer line written at the end of every answer.
+pub const FOOTER_PREFIX: &str = "⏱ ";
+
+/// Prefix of the line that ends a turn whose request failed.
+pub const ERROR_PREFIX: &str = "⚠ Error: ";
+
+/// Prefix of the lines of a `[request debug]` block, which shows what was
+/// sent to the model (see `App::echo_requests`).
+pub const REQUEST_DEBUG_PREFIX: &str = "┆ ";
+
+/// Removes the `[request debug]` blocks from `history`, for saving or
+/// copying it.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::strip_request_debug;
+///
+/// let history = "┆ [request debug] model: m\n┆ Hi\n\nYOU: Hi\n";
+/// assert_eq!(strip_request_debug(history), "\nYOU: Hi\n");
+/// ```
+pub fn strip_request_debug(history: &str) -> String {
+    history
+        .split_inclusive('\n')
+        .filter(|line| !line.starts_with(REQUEST_DEBUG_PREFIX))
+        .collect()
+}
+
+/// Prefix of the line naming a file attached with `/file`, followed by the
+/// file's code block.
+pub const ATTACHMENT_PREFIX: &str = "File: ";
+
+/// Length of the turn divider when no render width is known.
+pub const DEFAULT_DIVIDER_WIDTH: u16 = 40;
+
+/// Text emphasis applied over the whole transcript, for readability.
+///
+/// Only modifiers change, never colors, so it combines with any styling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Emphasis {
+    /// Spans keep their own modifiers.
+    #[default]
+    Normal,
+    /// Every span is bold, for low vision.
+    Bold,
+    /// Every span is dimmed, for reduced contrast.
+    Dim,
+}
+
+impl Emphasis {
+    /// Returns the emphasis after this one, cycling back to
+    /// [`Emphasis::Normal`].
+    pub fn next(self) -> Self {
+        match self {
+            Emphasis::Normal => Emphasis::Bold,
+            Emphasis::Bold => Emphasis::Dim,
+            Emphasis::Dim => Emphasis::Normal,
+        }
+    }
+
+    /// Short name shown in the status bar.
+    pub fn name(self) -> &'static str {
+        match self {
+            Emphasis::Normal => "normal",
+            Emphasis::Bold => "all bold",
+            Emphasis::Dim => "dim",
+        }
+    }
+
+    /// Applies the emphasis to every span of `text`.
+    pub fn apply(self, text: &mut Text) {
+        let modifier = match self {
+            Emphasis::Normal => return,
+            Emphasis::Bold => Modifier::BOLD,
+            Emphasis::Dim => Modifier::DIM,
+        };
+        for span in text.lines.iter_mut().flat_map(|line| line.spans.iter_mut()) {
+            span.style = span.style.add_modifier(modifier);
+        }
+    }
+}
+
+/// Rendering options that influence how the conversation history is styled.
+///
+/// [`parse_history`] uses the defaults; the main UI passes the options that
+/// depend on application state through [`parse_history_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Model currently selected in the list. AI turns produced by a
+    /// different model are labeled with that model's name; when `None`,
+    /// every recorded model tag is shown.
+    pub current_model: Option<String>,
+    /// Width available for the conversation text. Turn dividers span this
+    /// width; when `None` they use [`DEFAULT_DIVIDER_WIDTH`].
+    pub width: Option<u16>,
+    /// Indices of exchanges (see [`split_exchanges`]) rendered as a single
+    /// `▸ <prompt>` line.
+    pub collapsed: BTreeSet<usize>,
+    /// Exchange highlighted for folding, if any.
+    pub focused_exchange: Option<usize>,
+    /// Answer the last exchange had before it was regenerated. When set, a
+    /// word diff against the current answer is shown below it.
+    pub previous_answer: Option<String>,
+    /// Reference-style link definitions by lower-case label (see
+    /// [`link_definitions`]). [`parse_history_layout`] adds the definitions
+    /// found in the history.
+    pub link_definitions: HashMap<String, String>,
+    /// Icons rendered in front of the turn labels.
+    pub labels: Labels,
+    /// Compact transcript: no blank lines at the start of an exchange or
+    /// before an answer, and runs of blank lines shown as one.
+    pub compact: bool,
+    /// Whether code blocks get a full frame or only a gutter.
+    pub code_block_style: CodeBlockStyle,
+    /// Emphasis applied over the styled transcript.
+    pub emphasis: Emphasis,
+    /// Screen-reader-friendly text: code blocks are introduced and ended
+    /// by words instead of frames, and dividers, gutters and bullets are
+    /// left out.
+    pub plain: bool,
+    /// Put the exchange's number, as in `#3`, in front of its first line.
+    pub number_turns: bool,
+    /// Which turns are right-aligned (see [`is_rtl`]).
+    pub text_direction: TextDirection,
+}
+
+/// Whether `line` looks like part of a diagram or an aligned table: it has
+/// box-drawing characters, a gap of three or more spaces inside the text,
+/// or consists mostly of ASCII art characters such as `+-|/\_`.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::looks_preformatted;
+///
+/// assert!(looks_preformatted("┌──────┐"));
+/// assert!(looks_preformatted("name    size"));
+/// assert!(looks_preformatted("+--+--+"));
+/// assert!(!looks_preformatted("Just a sentence, with - a dash."));
+/// ```
+pub fn looks_preformatted(line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.chars().count() < 3 || trimmed == TURN_SEPARATOR {
+        return false;
+    }
+    if trimmed.chars().any(|c| ('\u{2500}'..='\u{259F}').contains(&c)) || trimmed.contains("   ") {
+        return true;
+    }
+    let art = trimmed.chars().filter(|c| "+-|/\\_=<>^*.:'`".contains(*c)).count();
+    let visible = trimmed.chars().filter(|c| !c.is_whitespace()).count();
+    art * 2 > visible
+}
+
+/// Renders a preformatted line (see [`looks_preformatted`]) so wrapping
+/// leaves it alone: spaces become no-break spaces, which are neither
+/// trimmed nor wrapped at, and a line wider than `width` is cut with `…`.
+fn preformatted_line(line: &str, width: Option<u16>) -> String {
+    let line = line.trim_end().replace(' ', "\u{a0}");
+    match width.map(usize::from) {
+        Some(width) if width > 0 && line.chars().count() > width => {
+            let mut cut: String = line.chars().take(width - 1).collect();
+            cut.push('…');
+            cut
+        }
+        _ => line,
+    }
+}
+
+/// Whether `c` belongs to a right-to-left script: Hebrew, Arabic, Syriac,
+/// Thaana, N'Ko and their presentation forms.
+fn is_rtl_char(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+/// Whether `text` is written mostly in a right-to-left script, i.e. it
+/// has more right-to-left letters than other letters. Digits, spaces and
+/// punctuation do not count either way.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::is_rtl;
+///
+/// assert!(is_rtl("מה שלומך? (ok)"));
+/// assert!(!is_rtl("Hello, שלום"));
+/// assert!(!is_rtl("42"));
+/// ```
+pub fn is_rtl(text: &str) -> bool {
+    let (rtl, ltr) = text
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .fold((0, 0), |(rtl, ltr), c| if is_rtl_char(c) { (rtl + 1, ltr) } else { (rtl, ltr + 1) });
+    rtl > ltr
+}
+
+/// Whether the turn starting at the first of `lines` is right-aligned. The
+/// turn ends before the next label or divider; its own label does not
+/// count towards the direction.
+fn turn_is_rtl<'a>(mut lines: impl Iterator<Item = &'a str>, direction: TextDirection) -> bool {
+    match direction {
+        TextDirection::Ltr => false,
+        TextDirection::Rtl => true,
+        TextDirection::Auto => {
+            let Some(first) = lines.next() else {
+                return false;
+            };
+            let first = first
+                .strip_prefix("YOU:")
+                .or_else(|| split_ai_label(first).map(|(_, rest)| rest))
+                .unwrap_or(first);
+            let mut turn = first.to_string();
+            for line in lines.take_while(|l| {
+                l.trim() != TURN_SEPARATOR && !l.starts_with("YOU:") && split_ai_label(l).is_none()
+            }) {
+                turn.push('\n');
+                turn.push_str(line);
+            }
+            is_rtl(&turn)
+        }
+    }
+}
+
+/// Splits an AI label line into its optional model tag and the message text.
+///
+/// Recognizes both the plain `AI: text` form and the tagged
+/// `AI [model]: text` form written by `App::send_query`. Returns `None` if
+/// the line does not start with an AI label.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::split_ai_label;
+///
+/// assert_eq!(split_ai_label("AI: Hi"), Some((None, " Hi")));
+/// assert_eq!(split_ai_label("AI [llama3]: Hi"), Some((Some("llama3"), " Hi")));
+/// assert_eq!(split_ai_label("YOU: Hi"), None);
+/// ```
+pub fn split_ai_label(line: &str) -> Option<(Option<&str>, &str)> {
+    if let Some(rest) = line.strip_prefix("AI [") {
+        if let Some(end) = rest.find("]:") {
+            return Some((Some(&rest[..end]), &rest[end + 2..]));
+        }
+    }
+    line.strip_prefix("AI:").map(|rest| (None, rest))
+}
+
+/// Parses conversation history and converts it into a formatted Ratatui Text object.
+///
+/// This function processes the raw conversation history string and applies syntax
+/// highlighting for markdown elements, particularly code blocks. It uses regex
+/// pattern matching to identify code blocks and delegates regular text processing
+/// to [`process_styled_text`].
+///
+/// # Arguments
+///
+/// * `history` - The raw conversation history string containing user and AI messages
+///
+/// # Returns
+///
+/// A formatted [`Text`] object ready for rendering with Ratatui, containing:
+/// - Syntax-highlighted code blocks with language-specific borders
+/// - Styled user/AI message labels with appropriate colors
+/// - Markdown formatting for headers and emphasis
+///
+/// # Code Block Processing
+///
+/// Code blocks are detected using the regex pattern:
+/// ```regex
+/// (?s)```(?P<lang>\w+)?\n(?P<code>.*?)```
+/// ```
+///
+/// Each code block is rendered with:
+/// - Language-specific header: `┌── rust ──`
+/// - Yellow-colored borders and prefixes (with
+///   [`CodeBlockStyle::Minimal`] only a `▎` gutter and a dim `rust` label)
+/// - Preserved indentation and formatting
+/// - Consistent visual separation from regular text
+///
+/// # Performance
+///
+/// Uses single-pass regex processing with efficient string slicing to minimize
+/// allocations. The function handles large conversation histories gracefully
+/// without significant performance degradation.
+///
+/// # Example Input/Output
+///
+/// ```text
+/// Input: "YOU: Hello\n\nAI: Here's some code:\n\n```rust\nfn main() {}\n```"
+/// Output: Formatted Text with colored labels and bordered code block
+/// ```
+pub fn parse_history<'a>(history: &'a str) -> Text<'a> {
+    parse_history_with(history, &RenderOptions::default())
+}
+
+/// Parses conversation history like [`parse_history`], honoring `options`.
+///
+/// A trailing turn separator is not rendered, so dividers only ever appear
+/// between two turns.
+pub fn parse_history_with<'a>(history: &'a str, options: &RenderOptions) -> Text<'a> {
+    parse_history_layout(history, options).0
+}
+
+/// Like [`parse_history_with`], but also returns the index of the first
+/// rendered line of every exchange, e.g. to scroll an exchange into view.
+pub fn parse_history_layout<'a>(
+    history: &'a str,
+    options: &RenderOptions,
+) -> (Text<'a>, Vec<usize>) {
+    let history = strip_trailing_separator(history);
+    let mut definitions = link_definitions(history);
+    definitions.extend(options.link_definitions.clone());
+    let options = &RenderOptions {
+        link_definitions: definitions,
+        ..options.clone()
+    };
+    let mut text = Text::default();
+    let mut offsets = Vec::new();
+    let mut consumed = 0;
+
+    for (index, exchange) in split_exchanges(history).into_iter().enumerate() {
+        consumed += exchange.len();
+        let start = text.lines.len();
+        offsets.push(start);
+        if options.collapsed.contains(&index) {
+            let marker = if options.plain { "Collapsed:" } else { "▸" };
+            text.push_line(Line::from(Span::styled(
+                format!("{} {}", marker, exchange_title(exchange)),
+                Style::default().fg(Color::Cyan),
+            )));
+            if strip_trailing_separator(exchange).len() != exchange.len() {
+                process_styled_text_with(TURN_SEPARATOR, &mut text, options);
+            }
+        } else {
+            parse_segment(exchange, &mut text, options);
+        }
+        if options.compact {
+            compact_lines(&mut text.lines, start, &options.labels);
+        }
+        if options.number_turns {
+            let is_blank = |line: &Line| line.spans.iter().all(|s| s.content.trim().is_empty());
+            if let Some(line) = text.lines[start..].iter_mut().find(|l| !is_blank(l)) {
+                let number = Span::styled(format!("#{} ", index + 1), Style::default().fg(Color::DarkGray));
+                line.spans.insert(0, number);
+            }
+        }
+        if options.focused_exchange == Some(index) {
+            if let Some(line) = text.lines.get_mut(start) {
+                line.style = line.style.add_modifier(Modifier::REVERSED);
+            }
+        }
+    }
+    // Blank text after the last exchange is not an exchange, but it is
+    // still part of the transcript.
+    if consumed < history.len() {
+        let start = text.lines.len();
+        parse_segment(&history[consumed..], &mut text, options);
+        if options.compact {
+            compact_lines(&mut text.lines, start, &options.labels);
+        }
+    }
+    if let Some(previous) = &options.previous_answer {
+        let last = offsets.len().checked_sub(1);
+        let answer = split_exchanges(history).last().and_then(|e| exchange_parts(e));
+        if let (Some(index), Some((_, answer))) = (last, answer) {
+            if !options.collapsed.contains(&index) {
+                text.lines.extend(diff_lines(previous, answer));
+            }
+        }
+    }
+    options.emphasis.apply(&mut text);
+    (text, offsets)
+}
+
+/// Removes blank lines from `lines[from..]` for the compact transcript.
+///
+/// Leading blank lines and blank lines right before an AI label are
+/// dropped, and every other run of blank lines is reduced to one. Code
+/// block lines carry a frame and are never blank, so code keeps its
+/// spacing.
+fn compact_lines(lines: &mut Vec<Line>, from: usize, labels: &Labels) {
+    let is_blank = |line: &Line| line.spans.iter().all(|s| s.content.trim().is_empty());
+    let tail = lines.split_off(from);
+    let before_label: Vec<bool> = (0..tail.len())
+        .map(|i| tail.get(i + 1).is_some_and(|line| is_ai_label(line, labels)))
+        .collect();
+    let mut previous_blank = true;
+    for (line, before_label) in tail.into_iter().zip(before_label) {
+        let blank = is_blank(&line);
+        if blank && (previous_blank || before_label) {
+            continue;
+        }
+        previous_blank = blank;
+        lines.push(line);
+    }
+}
+
+/// Whether a rendered line starts an AI turn, icon and turn number
+/// included.
+fn is_ai_label(line: &Line, labels: &Labels) -> bool {
+    let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    let text = text.trim_start();
+    let text = text
+        .strip_prefix('#')
+        .and_then(|rest| rest.split_once(' '))
+        .filter(|(number, _)| number.chars().all(|c| c.is_ascii_digit()))
+        .map_or(text, |(_, rest)| rest);
+    let text = text.strip_prefix(labels.ai_icon.as_str()).unwrap_or(text);
+    split_ai_label(text.trim_start()).is_some()
+}
+
+/// Returns the rendered line on which the last exchange's answer starts,
+/// or `None` if it has no answer yet.
+///
+/// `text` and `offsets` are the output of [`parse_history_layout`].
+pub fn answer_start_line(text: &Text, offsets: &[usize], labels: &Labels) -> Option<usize> {
+    let start = *offsets.last()?;
+    (start..text.lines.len()).find(|&i| is_ai_label(&text.lines[i], labels))
+}
+
+/// Renders a word diff from `old` to `new`: added text green, removed text
+/// red and crossed out.
+pub fn diff_lines(old: &str, new: &str) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Δ Changes vs previous answer:",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    let mut current = Vec::new();
+    for op in diff_words(old, new) {
+        let (text, style) = match op {
+            DiffOp::Same(text) => (text, Style::default()),
+            DiffOp::Added(text) => (text, Style::default().fg(Color::Green)),
+            DiffOp::Removed(text) => (
+                text,
+                Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT),
+            ),
+        };
+        let mut parts = text.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            if !part.is_empty() {
+                current.push(Span::styled(part.to_string(), style));
+            }
+            if parts.peek().is_some() {
+                lines.push(Line::from(std::mem::take(&mut current)));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(Line::from(current));
+    }
+    lines
+}
+
+/// Renders one exchange, framing its fenced code blocks in the configured
+/// [`CodeBlockStyle`].
+///
+/// A trailing fence that has no closing ```` ``` ```` yet, as while an
+/// answer is streaming, is framed too, without a bottom border, so the code
+/// does not jump into a frame once the fence closes. Only the last exchange
+/// can end in such a fence: [`split_exchanges`] ignores separators inside
+/// it.
+fn parse_segment<'a>(
+    history: &'a str,
+    text: &mut Text<'a>,
+    options: &RenderOptions,
+) {
+    let mut last_match_end = 0;
+
+    for caps in CODE_BLOCK_RE.captures_iter(history) {
+        let full_match = caps.get(0).unwrap();
+        if full_match.start() > last_match_end {
+            process_styled_text_with(
+                &history[last_match_end..full_match.start()],
+                text,
+                options,
+            );
+        }
+        let code_content = caps.name("code").map_or("", |m| m.as_str());
+        push_code_block(text, caps.name("lang").map(|m| m.as_str()), code_content, options, true);
+        last_match_end = full_match.end();
+    }
+    let rest = &history[last_match_end..];
+    if let Some(caps) = OPEN_FENCE_RE.captures(rest) {
+        let fence = caps.get(0).unwrap();
+        if fence.start() > 0 {
+            process_styled_text_with(&rest[..fence.start()], text, options);
+        }
+        let code_content = caps.name("code").map_or("", |m| m.as_str());
+        push_code_block(text, caps.name("lang").map(|m| m.as_str()), code_content, options, false);
+    } else if !rest.is_empty() {
+        process_styled_text_with(rest, text, options);
+    }
+}
+
+/// Renders a code block; an unclosed block gets no bottom border.
+///
+/// Without a language tag the language is guessed with
+/// [`detect_language`].
+fn push_code_block<'a>(
+    text: &mut Text<'a>,
+    lang: Option<&'a str>,
+    code: &'a str,
+    options: &RenderOptions,
+    closed: bool,
+) {
+    let lang = lang.or_else(|| detect_language(code)).unwrap_or("code");
+    if options.plain {
+        text.push_line(Line::from(format!("Code ({}):", lang)));
+        for line in code.lines() {
+            text.push_line(Line::from(line));
+        }
+        if closed {
+            text.push_line(Line::from("End of code."));
+        }
+        return;
+    }
+    if options.code_block_style == CodeBlockStyle::Minimal {
+        let gutter = || Span::styled("▎ ", Style::default().fg(Color::Yellow));
+        text.push_line(Line::from(vec![
+            gutter(),
+            Span::styled(lang, Style::default().fg(Color::DarkGray)),
+        ]));
+        for line in code.lines() {
+            text.push_line(Line::from(vec![gutter(), Span::raw(line)]));
+        }
+        return;
+    }
+    text.push_line(Line::from(Span::styled(
+        format!(" ┌── {} ──", lang),
+        Style::default().fg(Color::Yellow),
+    )));
+    for line in code.lines() {
+        text.push_line(Line::from(vec![
+            Span::styled(" │ ", Style::default().fg(Color::Yellow)),
+            Span::raw(line),
+        ]));
+    }
+    if closed {
+        text.push_line(Line::from(Span::styled(
+            " └──────────",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+}
+
+/// Splits the conversation history into exchanges.
+///
+/// Every exchange ends after a [`TURN_SEPARATOR`] line; separators inside
+/// fenced code blocks do not count. The text after the last separator (an
+/// exchange still in progress) forms the final exchange unless it is blank.
+/// Joining the returned slices yields the history up to that blank tail.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::split_exchanges;
+///
+/// let history = "\nYOU: a\n\nAI: b\n---\n\nYOU: c\n\nAI: d\n---\n";
+/// let exchanges = split_exchanges(history);
+/// assert_eq!(exchanges.len(), 2);
+/// assert!(exchanges[1].contains("YOU: c"));
+/// ```
+pub fn split_exchanges(history: &str) -> Vec<&str> {
+    let mut exchanges = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_fence = false;
+    for line in history.split_inclusive('\n') {
+        offset += line.len();
+        // Fences may start mid-line (`AI: ```rust`), as in `parse_history`.
+        if line.matches("```").count() % 2 == 1 {
+            in_fence = !in_fence;
+        } else if !in_fence && line.trim() == TURN_SEPARATOR {
+            exchanges.push(&history[start..offset]);
+            start = offset;
+        }
+    }
+    if !history[start..].trim().is_empty() {
+        exchanges.push(&history[start..]);
+    }
+    exchanges
+}
+
+/// Splits an exchange into its prompt and its answer.
+///
+/// The prompt is the text after `YOU:` up to the `AI:` label line; the
+/// answer is everything after the label, without the timing footer and
+/// trailing separator.
+/// Returns `None` if the exchange has no `YOU:` or `AI:` line.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::exchange_parts;
+///
+/// let parts = exchange_parts("\nYOU: Hi\nthere\n\nAI [llama3]: Hello\n---\n");
+/// assert_eq!(parts, Some(("Hi\nthere", "Hello")));
+/// ```
+pub fn exchange_parts(exchange: &str) -> Option<(&str, &str)> {
+    let mut prompt_start = None;
+    let mut offset = 0;
+    for line in exchange.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        match prompt_start {
+            None => {
+                if let Some(rest) = content.strip_prefix("YOU:") {
+                    prompt_start = Some(offset + content.len() - rest.trim_start().len());
+                }
+            }
+            Some(start) => {
+                if let Some((_, rest)) = split_ai_label(content) {
+                    let answer_start = offset + content.len() - rest.trim_start().len();
+                    let prompt = exchange[start..offset].trim_end();
+                    let answer = strip_trailing_separator(&exchange[answer_start..]).trim();
+                    let answer = match answer.rfind('\n') {
+                        Some(i) if answer[i + 1..].starts_with(FOOTER_PREFIX) => answer[..i].trim_end(),
+                        _ if answer.starts_with(FOOTER_PREFIX) => "",
+                        _ => answer,
+                    };
+                    return Some((prompt, answer));
+                }
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Returns the text of the most recent non-empty AI answer in `history`,
+/// without its label, timing footer and separator.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::last_answer;
+///
+/// let history = "\nYOU: Hi\n\nAI [llama3]: Hello!\n---\n";
+/// assert_eq!(last_answer(history).as_deref(), Some("Hello!"));
+/// assert_eq!(last_answer(""), None);
+/// ```
+pub fn last_answer(history: &str) -> Option<String> {
+    split_exchanges(history)
+        .into_iter()
+        .rev()
+        .filter_map(exchange_parts)
+        .map(|(_, answer)| answer)
+        .find(|answer| !answer.is_empty())
+        .map(String::from)
+}
+
+/// Returns the one-line title of an exchange: the first line of its prompt.
+pub fn exchange_title(exchange: &str) -> &str {
+    let mut lines = exchange
+        .lines()
+        .filter(|l| !l.starts_with(REQUEST_DEBUG_PREFIX))
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    let first = lines.next().unwrap_or("");
+    first.strip_prefix("YOU:").map(str::trim).unwrap_or(first)
+}
+
+/// Splits regular text into spans, styling inline Markdown the terminal
+/// cannot show as such.
+///
+/// - Images: terminals cannot display them in general, so `![alt](url)`
+///   becomes a styled `🖼 alt (url)` span.
+/// - Reference links: `[text][label]` and `[text][]` whose label is defined
+///   in `options.link_definitions` render as underlined `text`.
+/// - `~~strikethrough~~` is crossed out and `==highlight==` gets a yellow
+///   background. Their text is styled recursively, so they nest with each
+///   other and with links.
+///
+/// Malformed syntax, unterminated markers and unresolved references are
+/// left as plain text. URLs
+/// are not emitted as OSC 8 hyperlinks because ratatui would count the
+/// escape sequence as visible width and break the layout.
+pub fn inline_spans<'a>(text: &'a str, options: &RenderOptions) -> Vec<Span<'a>> {
+    let image_style = Style::default().fg(Color::LightBlue).add_modifier(Modifier::ITALIC);
+    let link_style = Style::default().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED);
+
+    let strike_style = Style::default().add_modifier(Modifier::CROSSED_OUT);
+    let highlight_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+    let mut spans = Vec::new();
+    let mut last_end = 0;
+    for caps in INLINE_RE.captures_iter(text) {
+        let full = caps.get(0).unwrap();
+        let styled: Vec<Span<'a>> = if let Some(url) = caps.get(2) {
+            let alt = caps[1].trim();
+            let label = if alt.is_empty() { "image" } else { alt };
+            vec![Span::styled(format!("🖼 {} ({})", label, url.as_str()), image_style)]
+        } else if let Some(link_text) = caps.get(3) {
+            let link_text = link_text.as_str();
+            let label = match &caps[4] {
+                "" => link_text,
+                label => label,
+            };
+            if !options.link_definitions.contains_key(&label.to_lowercase()) {
+                continue;
+            }
+            vec![Span::styled(link_text, link_style)]
+        } else {
+            let (inner, style) = match caps.get(5) {
+                Some(inner) => (inner, strike_style),
+                None => (caps.get(6).unwrap(), highlight_style),
+            };
+            inline_spans(inner.as_str(), options)
+                .into_iter()
+                .map(|span| span.patch_style(style))
+                .collect()
+        };
+        if full.start() > last_end {
+            spans.push(Span::raw(&text[last_end..full.start()]));
+        }
+        spans.extend(styled);
+        last_end = full.end();
+    }
+    if last_end < text.len() || spans.is_empty() {
+        spans.push(Span::raw(&text[last_end..]));
+    }
+    spans
+}
+
+/// Collects reference-style link definitions (`[label]: url`) from `text`.
+///
+/// Labels are case-insensitive and stored in lower case; the first
+/// definition of a label wins, as in CommonMark.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::link_definitions;
+///
+/// let defs = link_definitions("See [docs][1].\n\n[1]: https://ollama.com");
+/// assert_eq!(defs["1"], "https://ollama.com");
+/// ```
+pub fn link_definitions(text: &str) -> HashMap<String, String> {
+    let mut definitions = HashMap::new();
+    for line in text.lines() {
+        if let Some((label, url)) = split_link_definition(line) {
+            definitions.entry(label.to_lowercase()).or_insert_with(|| url.to_string());
+        }
+    }
+    definitions
+}
+
+/// Splits a `[label]: url` definition line into label and URL.
+fn split_link_definition(line: &str) -> Option<(&str, &str)> {
+    let caps = LINK_DEFINITION_RE.captures(line)?;
+    Some((caps.get(1)?.as_str(), caps.get(2)?.as_str()))
+}
+
+/// Longest key, in characters, that [`split_key_value`] accepts.
+const MAX_KEY_CHARS: usize = 30;
+
+/// Most words a key of [`split_key_value`] may have; longer ones are the
+/// start of a sentence.
+const MAX_KEY_WORDS: usize = 3;
+
+/// Splits a `key: value` line into the key up to and including the colon,
+/// and the value.
+///
+/// Conservative, so prose is left alone: the key (after indentation and an
+/// optional `- ` or `* ` bullet) must start with a letter or digit, be at
+/// most three words of letters, digits and `_-./` and be followed by `: `
+/// and a value. URLs never match, since their colon is not followed by a
+/// space and a key cannot contain one.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::split_key_value;
+///
+/// assert_eq!(split_key_value("- num_ctx: context size"), Some(("- num_ctx:", " context size")));
+/// assert_eq!(split_key_value("The fix is simple: restart it."), None);
+/// assert_eq!(split_key_value("See https://ollama.com: docs"), None);
+/// ```
+pub fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let colon = line.find(": ")?;
+    let value = &line[colon + 1..];
+    let key = line[..colon].trim_start();
+    let key = key
+        .strip_prefix("- ")
+        .or_else(|| key.strip_prefix("* "))
+        .unwrap_or(key);
+    let valid = key.chars().next().is_some_and(char::is_alphanumeric)
+        && !key.ends_with(' ')
+        && key.chars().count() <= MAX_KEY_CHARS
+        && key.split(' ').count() <= MAX_KEY_WORDS
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '.' | '/'))
+        && !value.trim().is_empty();
+    valid.then(|| line.split_at(colon + 1))
+}
+
+/// Returns the definition of a definition-list line (`: definition`).
+fn definition_text(line: &str) -> Option<&str> {
+    line.trim_start()
+        .strip_prefix(": ")
+        .filter(|definition| !definition.trim().is_empty())
+}
+
+/// Summarizes how much of a streaming answer has arrived, e.g.
+/// `· 215 words · 1.2k chars`, or `None` before the first text.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::stream_progress;
+///
+/// assert_eq!(stream_progress(""), None);
+/// assert_eq!(stream_progress("Hello there").as_deref(), Some("· 2 words · 11 chars"));
+/// ```
+pub fn stream_progress(answer: &str) -> Option<String> {
+    let chars = answer.chars().count();
+    if chars == 0 {
+        return None;
+    }
+    let words = answer.split_whitespace().count();
+    let chars = if chars >= 1000 {
+        format!("{:.1}k", chars as f64 / 1000.0)
+    } else {
+        chars.to_string()
+    };
+    Some(format!(
+        "· {} word{} · {} chars",
+        words,
+        if words == 1 { "" } else { "s" },
+        chars
+    ))
+}
+
+/// Formats the timing footer of a turn, without [`FOOTER_PREFIX`].
+///
+/// Uses the server-reported breakdown when available, e.g.
+/// `load 2.1s · prompt 0.3s · gen 1.4s @ 18 tok/s`, and falls back to the
+/// measured wall-clock time otherwise.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::client::GenerationTimings;
+/// use lazyllama::ui::format_turn_footer;
+/// use std::time::{Duration, Instant};
+///
+/// let wall = Duration::from_millis(3200);
+/// assert_eq!(format_turn_footer(None, wall), "3.2s");
+/// ```
+pub fn format_turn_footer(timings: Option<&GenerationTimings>, wall_clock: Duration) -> String {
+    let timings = match timings {
+        Some(t) if !t.is_empty() => t,
+        _ => return format!("{:.1}s", wall_clock.as_secs_f64()),
+    };
+    let mut parts = Vec::new();
+    if let Some(load) = timings.load {
+        parts.push(format!("load {:.1}s", load.as_secs_f64()));
+    }
+    if let Some(prompt) = timings.prompt_eval {
+        parts.push(format!("prompt {:.1}s", prompt.as_secs_f64()));
+    }
+    if let Some(eval) = timings.eval {
+        let mut part = format!("gen {:.1}s", eval.as_secs_f64());
+        if let Some(count) = timings.eval_count.filter(|_| !eval.is_zero()) {
+            part.push_str(&format!(" @ {:.0} tok/s", count as f64 / eval.as_secs_f64()));
+        }
+        parts.push(part);
+    }
+    parts.join(" · ")
+}
+
+/// Removes a final [`TURN_SEPARATOR`] line (and trailing whitespace) from `history`.
+fn strip_trailing_separator(history: &str) -> &str {
+    let trimmed = history.trim_end();
+    match trimmed.strip_suffix(TURN_SEPARATOR) {
+        Some(rest) if rest.is_empty() || rest.ends_with('\n') => rest,
+        _ => history,
+    }
+}
+
+/// Processes regular text line-by-line and applies styling for labels and markdown headers.
+///
+/// This function handles non-code text formatting, applying appropriate colors and
+/// styles to different types of content including conversation labels, markdown
+/// headers, and regular text. It preserves the original text structure while
+/// adding visual styling.
+///
+/// # Arguments
+///
+/// * `text` - The raw text string to be processed and styled
+/// * `target` - Mutable reference to the Text object where styled content is appended
+///
+/// # Styling Rules
+///
+/// - **Turn Separators**: [`TURN_SEPARATOR`] lines become a dim full-width `─` divider
+/// - **Timing Footers**: Lines starting with [`FOOTER_PREFIX`] are dimmed
+/// - **Errors**: Lines starting with [`ERROR_PREFIX`] are red
+/// - **Request Debug**: Lines starting with [`REQUEST_DEBUG_PREFIX`] are dimmed
+/// - **Images**: `![alt](url)` is shown as a `🖼 alt (url)` placeholder
+/// - **Reference Links**: `[text][label]` is underlined and its `[label]: url`
+///   definition dimmed, if the label is in `options.link_definitions`
+/// - **Strikethrough/Highlight**: `~~text~~` is crossed out and `==text==`
+///   highlighted
+/// - **Headers**: Lines starting with `###` are converted to bullet points (`•`) in bold white
+/// - **Key/Value Lines**: The key of `key: value` lines (see
+///   [`split_key_value`]) is bold yellow
+/// - **Definition Lists**: A term followed by `: definition` lines is bold
+///   yellow and the definitions are indented instead of prefixed with `:`
+/// - **Label Icons**: [`RenderOptions::labels`] icons are shown before the
+///   `YOU:` and `AI` labels; the labels themselves are matched as written
+/// - **User Messages**: "YOU:" prefix is styled in bold magenta, rest in default color.
+///   Further lines of a multi-line prompt get a magenta `│ ` gutter so their
+///   indentation survives line wrapping (which trims leading whitespace)
+/// - **AI Messages**: "AI:" prefix is styled in bold cyan, rest in default color.
+///   A recorded model tag (`AI [model]:`) is shown in dim gray when it
+///   differs from [`RenderOptions::current_model`]
+/// - **Regular Text**: Rendered without special styling in default terminal colors
+///
+/// # Text Processing
+///
+/// The function processes each line individually and:
+/// 1. Trims whitespace to detect line types
+/// 2. Creates appropriate styled spans based on content
+/// 3. Preserves original text after removing formatting markers
+/// 4. Combines spans into cohesive line objects
+/// 
+/// # Color Scheme
+///
+/// - Headers: White with bold modifier
+/// - User labels: Magenta with bold modifier
+/// - AI labels: Cyan with bold modifier
+/// - Regular text: Default terminal colors
+///
+/// # Side Effects
+///
+/// Appends styled content directly to the provided `target` Text object,
+/// allowing for incremental building of complex formatted documents.
+pub fn process_styled_text<'a>(text: &'a str, target: &mut Text<'a>) {
+    process_styled_text_with(text, target, &RenderOptions::default());
+}
+
+/// Processes regular text like [`process_styled_text`], honoring `options`.
+pub fn process_styled_text_with<'a>(text: &'a str, target: &mut Text<'a>, options: &RenderOptions) {
+    let ai_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let key_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    // Whether the lines so far continue a user prompt.
+    let mut in_user_turn = false;
+    // Whether the current turn is right-aligned. A segment after a code
+    // block continues its turn, so it is judged by its own text.
+    let mut right_aligned = turn_is_rtl(text.lines(), options.text_direction);
+    // A run of two or more lines that look like a diagram or table is
+    // kept as is, even where prose around it wraps.
+    let candidates: Vec<bool> = text.lines().map(looks_preformatted).collect();
+    let preformatted = |i: usize| {
+        candidates[i]
+            && (i.checked_sub(1).is_some_and(|p| candidates[p]) || candidates.get(i + 1) == Some(&true))
+    };
+    let mut lines = text.lines().enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        let trimmed = line.trim();
+        let mut spans = Vec::new();
+        if trimmed == TURN_SEPARATOR || split_ai_label(line).is_some() {
+            in_user_turn = false;
+        }
+        if line.starts_with("YOU:") || split_ai_label(line).is_some() {
+            let rest = std::iter::once(line).chain(lines.clone().map(|(_, l)| l));
+            right_aligned = turn_is_rtl(rest, options.text_direction);
+        }
+        let preformatted = preformatted(index)
+            && !line.starts_with("YOU:")
+            && split_ai_label(line).is_none()
+            && !trimmed.starts_with(FOOTER_PREFIX)
+            && !trimmed.starts_with(ERROR_PREFIX);
+        // Dividers, footers, diagrams and diagnostics keep their place.
+        let align = right_aligned
+            && !preformatted
+            && trimmed != TURN_SEPARATOR
+            && !trimmed.starts_with(FOOTER_PREFIX)
+            && !trimmed.starts_with(ERROR_PREFIX)
+            && !line.starts_with(REQUEST_DEBUG_PREFIX);
+        if trimmed == TURN_SEPARATOR && options.plain {
+            spans.push(Span::raw(""));
+        } else if trimmed == TURN_SEPARATOR {
+            let width = options.width.unwrap_or(DEFAULT_DIVIDER_WIDTH) as usize;
+            spans.push(Span::styled(
+                "─".repeat(width),
+                Style::default().fg(Color::DarkGray),
+            ));
+        } else if trimmed.starts_with(FOOTER_PREFIX) {
+            spans.push(Span::styled(trimmed, Style::default().fg(Color::DarkGray)));
+        } else if trimmed.starts_with(ERROR_PREFIX) {
+            spans.push(Span::styled(trimmed, Style::default().fg(Color::Red)));
+        } else if line.starts_with(REQUEST_DEBUG_PREFIX) {
+            spans.push(Span::styled(line, Style::default().fg(Color::DarkGray)));
+        } else if in_user_turn && !trimmed.is_empty() && !line.starts_with("YOU:") {
+            if !options.plain {
+                spans.push(Span::styled("│ ", Style::default().fg(Color::Magenta)));
+            }
+            spans.push(Span::raw(line));
+        } else if preformatted {
+            spans.push(Span::raw(preformatted_line(line, options.width)));
+        } else if trimmed.starts_with("###") {
+            let bullet = if options.plain { "" } else { "● " };
+            spans.push(Span::styled(
+                format!("{}{}", bullet, trimmed.trim_start_matches('#').trim()),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else if let Some(rest) = line.strip_prefix("YOU:") {
+            in_user_turn = true;
+            let user_style = Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD);
+            if !options.labels.user_icon.is_empty() {
+                spans.push(Span::styled(format!("{} ", options.labels.user_icon), user_style));
+            }
+            spans.push(Span::styled("YOU:", user_style));
+            spans.extend(inline_spans(rest, options));
+        } else if let Some((model, rest)) = split_ai_label(line) {
+            let show_tag = model
+                .is_some_and(|m| options.current_model.as_deref() != Some(m));
+            if !options.labels.ai_icon.is_empty() {
+                spans.push(Span::styled(format!("{} ", options.labels.ai_icon), ai_style));
+            }
+            match model {
+                Some(model) if show_tag => {
+                    spans.push(Span::styled("AI", ai_style));
+                    spans.push(Span::styled(
+                        format!(" [{}]", model),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                    spans.push(Span::styled(": ", ai_style));
+                }
+                _ => spans.push(Span::styled("AI: ", ai_style)),
+            }
+            spans.extend(inline_spans(rest, options));
+        } else if split_link_definition(line)
+            .is_some_and(|(label, _)| options.link_definitions.contains_key(&label.to_lowercase()))
+        {
+            spans.push(Span::styled(line, Style::default().fg(Color::DarkGray)));
+        } else if let Some(definition) = definition_text(line) {
+            spans.push(Span::raw("    "));
+            spans.extend(inline_spans(definition, options));
+        } else if !trimmed.is_empty() && lines.peek().is_some_and(|(_, next)| definition_text(next).is_some()) {
+            // The term of a definition list.
+            spans.push(Span::styled(line, key_style));
+        } else if let Some((key, value)) = split_key_value(line) {
+            spans.push(Span::styled(key, key_style));
+            spans.extend(inline_spans(value, options));
+        } else {
+            spans.extend(inline_spans(line, options));
         }
-        target.push_line(Line::from(spans));
+        let line = Line::from(spans);
+        target.push_line(if align { line.right_aligned() } else { line });
     }
 }
 
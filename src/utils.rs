@@ -36,16 +36,203 @@
 //! - **Persistence**: Both combined and per-model history files
 //! - **Error Handling**: Graceful degradation when storage is unavailable
 
-use anyhow::Result;
+use crate::clipboard::clean_markdown;
+use crate::config::LogFormat;
+use crate::error::{LazyLlamaError, Result};
+use crate::ui::{
+    exchange_parts, extract_code_blocks, split_ai_label, split_exchanges, strip_request_debug, CodeBlock,
+    TURN_SEPARATOR,
+};
 use chrono::Local;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Matches log file names: `{name}_{timestamp}.txt` (or `.md`, `.json`),
+/// optionally with the counter [`archive_conversation_in`] appends.
+static LOG_FILE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+)_\d{4}-\d{2}-\d{2}_\d{2}-\d{2}-\d{2}(?:_\d+)?\.(?:txt|md|json)$").unwrap()
+});
+
+/// Returns the LazyLlama data directory, creating it if necessary.
+///
+/// This is `~/.local/share/lazyllama/` on Linux and the equivalent local
+/// application data directory on other platforms.
+///
+/// # Errors
+///
+/// Returns [`LazyLlamaError::NoDataDir`] if the platform has no data
+/// directory, or [`LazyLlamaError::Io`] if it cannot be created.
+pub fn data_dir() -> Result<PathBuf> {
+    let mut dir = dirs::data_local_dir().ok_or(LazyLlamaError::NoDataDir)?;
+    dir.push("lazyllama");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Divider between exchanges in saved logs, in place of the internal
+/// [`TURN_SEPARATOR`].
+pub const EXPORT_DIVIDER: &str = "----";
+
+/// Prepares `history` for a saved log: request debug lines are dropped and
+/// turn separators become [`EXPORT_DIVIDER`] lines.
+///
+/// Lines inside code blocks are left alone, and every other line is kept,
+/// so bookmarks still point at the same lines.
+/// [`history_from_export`] turns a log back into a history.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::utils::normalize_for_export;
+///
+/// let history = "\nYOU: Hi\n\nAI [llama3]: Hello\n---\n";
+/// assert_eq!(normalize_for_export(history), "\nYOU: Hi\n\nAI [llama3]: Hello\n----\n");
+/// ```
+pub fn normalize_for_export(history: &str) -> String {
+    replace_dividers(&strip_request_debug(history), TURN_SEPARATOR, EXPORT_DIVIDER)
+}
+
+/// Converts `\r\n` and lone `\r` line endings to `\n`, so text from
+/// other platforms renders without stray carriage returns.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::utils::normalize_line_endings;
+///
+/// assert_eq!(normalize_line_endings("line1\r\nline2\r"), "line1\nline2\n");
+/// ```
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Reverses [`normalize_for_export`] for a log read back from disk, so the
+/// exchanges split as before. Logs saved with plain separators pass
+/// unchanged.
+pub fn history_from_export(log: &str) -> String {
+    replace_dividers(log, EXPORT_DIVIDER, TURN_SEPARATOR)
+}
+
+/// One exchange of a JSON log (see [`format_log`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogExchange {
+    /// The prompt as sent.
+    pub prompt: String,
+    /// Model the answer is labeled with, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The answer, without its timing footer.
+    pub answer: String,
+}
+
+/// A conversation as written to a JSON log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonLog {
+    /// Model of a per-model log; `None` in the combined log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The exchanges, oldest first.
+    pub exchanges: Vec<LogExchange>,
+}
+
+/// Converts `history` to the contents of a log file in `format`.
+///
+/// `txt` is [`normalize_for_export`]'s readable form, `md` the clean
+/// Markdown `Ctrl+Y` copies and `json` a [`JsonLog`] of `model`'s
+/// exchanges.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::config::LogFormat;
+/// use lazyllama::utils::format_log;
+///
+/// let history = "\nYOU: Hi\n\nAI [llama3]: Hello\n---\n";
+/// assert!(format_log(history, None, LogFormat::Txt).ends_with("Hello\n----\n"));
+/// assert!(format_log(history, Some("llama3"), LogFormat::Json).contains("\"prompt\": \"Hi\""));
+/// ```
+pub fn format_log(history: &str, model: Option<&str>, format: LogFormat) -> String {
+    match format {
+        LogFormat::Txt => normalize_for_export(history),
+        LogFormat::Md => format!("{}\n", clean_markdown(&strip_request_debug(history))),
+        LogFormat::Json => {
+            let history = strip_request_debug(history);
+            let exchanges = split_exchanges(&history)
+                .into_iter()
+                .filter_map(|exchange| {
+                    let (prompt, answer) = exchange_parts(exchange)?;
+                    let model = exchange.lines().find_map(split_ai_label).and_then(|(model, _)| model);
+                    Some(LogExchange {
+                        prompt: prompt.to_string(),
+                        model: model.map(str::to_string),
+                        answer: answer.to_string(),
+                    })
+                })
+                .collect();
+            let log = JsonLog {
+                model: model.map(str::to_string),
+                exchanges,
+            };
+            serde_json::to_string_pretty(&log).expect("a log of strings always serializes") + "\n"
+        }
+    }
+}
+
+/// Turns a log read back from `path` into a history, by the format its
+/// extension names (see [`format_log`]).
+///
+/// JSON logs are rebuilt in the format `send_query` writes, without
+/// timing footers; a malformed one yields an empty history.
+pub fn history_from_log(path: &Path, log: &str) -> String {
+    if path.extension().is_none_or(|ext| ext != LogFormat::Json.extension()) {
+        return history_from_export(log);
+    }
+    let Ok(log) = serde_json::from_str::<JsonLog>(log) else {
+        return String::new();
+    };
+    log.exchanges
+        .iter()
+        .map(|exchange| {
+            let label = exchange.model.as_deref().map_or("AI".to_string(), |m| format!("AI [{}]", m));
+            format!("\nYOU: {}\n\n{}: {}\n{}\n", exchange.prompt, label, exchange.answer, TURN_SEPARATOR)
+        })
+        .collect()
+}
+
+/// Replaces the lines of `text` that are exactly `from`, outside code
+/// fences, with `to`.
+fn replace_dividers(text: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        // Fences may start mid-line, as in `split_exchanges`.
+        if line.matches("```").count() % 2 == 1 {
+            in_fence = !in_fence;
+        } else if !in_fence && line.trim() == from {
+            result.push_str(to);
+            result.push_str(&line[line.trim_end().len()..]);
+            continue;
+        }
+        result.push_str(line);
+    }
+    result
+}
 
 /// Saves conversation history to a timestamped file in the local data directory.
 ///
 /// This function persists the provided conversation history to a new text file
 /// in the application's data directory. The file is placed under
 /// `~/.local/share/lazyllama/chat_YYYY-MM-DD_HH-MM-SS.txt` using the current
-/// timestamp for unique identification.
+/// timestamp for unique identification. [`save_history_to_file_as`] writes
+/// the other log formats.
 ///
 /// # Arguments
 ///
@@ -54,11 +241,12 @@ use std::fs;
 /// # Returns
 ///
 /// Returns `Ok(())` on successful file write or if the history is empty.
-/// Returns an `anyhow::Error` if directory creation or file writing fails.
+/// Returns a [`LazyLlamaError`] if directory creation or file writing fails.
 ///
 /// # Behavior
 ///
 /// - **Empty Check**: Returns immediately if history string is empty
+/// - **Clean Text**: Writes [`normalize_for_export`]'s readable form
 /// - **Directory Creation**: Creates the lazyllama directory if it doesn't exist
 /// - **File Naming**: Uses timestamp format `YYYY-MM-DD_HH-MM-SS` for uniqueness
 /// - **Atomic Write**: Uses `fs::write` for atomic file creation
@@ -90,17 +278,39 @@ use std::fs;
 /// }
 /// ```
 pub fn save_history_to_file(history: &str) -> Result<()> {
+    save_history_to_file_as(history, LogFormat::Txt)
+}
+
+/// Saves `history` like [`save_history_to_file`], in `format` (see
+/// [`format_log`]); the file extension follows the format.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written.
+pub fn save_history_to_file_as(history: &str, format: LogFormat) -> Result<()> {
     if history.is_empty() {
         return Ok(());
     }
-    let mut log_dir =
-        dirs::data_local_dir().ok_or_else(|| anyhow::anyhow!("Data dir not found"))?;
-    log_dir.push("lazyllama");
-    fs::create_dir_all(&log_dir)?;
-    let filename = format!("chat_{}.txt", Local::now().format("%Y-%m-%d_%H-%M-%S"));
-    log_dir.push(filename);
-    fs::write(log_dir, history)?;
-    Ok(())
+    save_history_to_file_in(&data_dir()?, history, format).map(|_| ())
+}
+
+/// Writes `history` like [`save_history_to_file`], but to `dir`, and
+/// returns the path of the written file (`None` if `history` is empty).
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written.
+pub fn save_history_to_file_in(dir: &Path, history: &str, format: LogFormat) -> Result<Option<PathBuf>> {
+    if history.is_empty() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    let filename = format!("chat_{}.{}", Local::now().format("%Y-%m-%d_%H-%M-%S"), format.extension());
+    let path = dir.join(filename);
+    fs::write(&path, format_log(history, None, format))?;
+    Ok(Some(path))
 }
 
 /// Saves separate conversation history files for each AI model.
@@ -116,8 +326,8 @@ pub fn save_history_to_file(history: &str) -> Result<()> {
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on successful completion or an `anyhow::Error` if directory
-/// creation or any file write operation fails.
+/// Returns `Ok(())` on successful completion or a [`LazyLlamaError`] if
+/// directory creation or any file write operation fails.
 ///
 /// # File Naming
 ///
@@ -125,11 +335,13 @@ pub fn save_history_to_file(history: &str) -> Result<()> {
 /// 
 /// - **Model Name Sanitization**: Replaces `:`, `/`, `\` with `_` for filesystem compatibility
 /// - **Timestamp Format**: `YYYY-MM-DD_HH-MM-SS` for consistent sorting
-/// - **Extension**: Always `.txt` for universal compatibility
+/// - **Extension**: Always `.txt` for universal compatibility; see
+///   [`save_model_histories_as`] for the other log formats
 ///
 /// # Behavior
 ///
 /// - **Empty History Skip**: Only creates files for models with non-empty histories
+/// - **Clean Text**: Writes [`normalize_for_export`]'s readable form
 /// - **Atomic Writes**: Uses `fs::write` for atomic file creation per model
 /// - **Single Timestamp**: All model files from one session share the same timestamp
 /// - **Directory Reuse**: Creates the lazyllama directory once for all files
@@ -165,23 +377,730 @@ pub fn save_history_to_file(history: &str) -> Result<()> {
 /// The function handles model names that may contain characters problematic
 /// for certain filesystems, ensuring cross-platform compatibility.
 pub fn save_model_histories(model_histories: &std::collections::HashMap<String, String>) -> Result<()> {
-    let mut log_dir =
-        dirs::data_local_dir().ok_or_else(|| anyhow::anyhow!("Data dir not found"))?;
-    log_dir.push("lazyllama");
-    fs::create_dir_all(&log_dir)?;
-    
+    save_model_histories_as(model_histories, LogFormat::Txt)
+}
+
+/// Saves the per-model files of [`save_model_histories`] in `format` (see
+/// [`format_log`]); the file extensions follow the format.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or a file cannot
+/// be written.
+pub fn save_model_histories_as(model_histories: &HashMap<String, String>, format: LogFormat) -> Result<()> {
+    save_model_histories_in(&data_dir()?, model_histories, format)
+}
+
+/// Writes the per-model files of [`save_model_histories`] to `dir`.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or a file cannot
+/// be written.
+pub fn save_model_histories_in(
+    dir: &Path,
+    model_histories: &HashMap<String, String>,
+    format: LogFormat,
+) -> Result<()> {
+    fs::create_dir_all(dir)?;
     let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
     
     for (model_name, history) in model_histories {
         if !history.is_empty() {
-            let safe_model_name = model_name.replace([':', '/', '\\'], "_");
-            let filename = format!("{}_{}.txt", safe_model_name, timestamp);
-            let mut file_path = log_dir.clone();
-            file_path.push(filename);
-            fs::write(file_path, history)?;
+            let filename = format!("{}_{}.{}", safe_model_name(model_name), timestamp, format.extension());
+            let file_path = dir.join(filename);
+            fs::write(file_path, format_log(history, Some(model_name), format))?;
         }
     }
     Ok(())
 }
 
+/// Archives one model's conversation to its own file in `dir`.
+///
+/// The file is named like the per-model files of [`save_model_histories`]
+/// (`{safe_model_name}_{timestamp}.txt`); if that name is already taken, a
+/// counter is appended so no earlier archive is overwritten. The text is
+/// written in [`normalize_for_export`]'s readable form.
+///
+/// # Returns
+///
+/// The path of the written file, or `None` if `history` is empty.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written.
+pub fn archive_conversation_in(dir: &Path, model: &str, history: &str) -> Result<Option<PathBuf>> {
+    if history.is_empty() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    let stem = format!(
+        "{}_{}",
+        safe_model_name(model),
+        Local::now().format("%Y-%m-%d_%H-%M-%S")
+    );
+    let mut path = dir.join(format!("{}.txt", stem));
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = dir.join(format!("{}_{}.txt", stem, counter));
+    }
+    fs::write(&path, normalize_for_export(history))?;
+    Ok(Some(path))
+}
+
+/// Name of the file in the data directory that lists recently used models.
+pub const MRU_FILE: &str = "recent_models.txt";
 
+/// Reads the recently used models from [`MRU_FILE`] in `dir`, most recent
+/// first.
+///
+/// A missing or unreadable file yields an empty list, so startup never
+/// fails because of it.
+pub fn load_mru_order_in(dir: &Path) -> Vec<String> {
+    fs::read_to_string(dir.join(MRU_FILE))
+        .map(|content| {
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the recently used models from the default [`data_dir`].
+pub fn load_mru_order() -> Vec<String> {
+    data_dir().map(|dir| load_mru_order_in(&dir)).unwrap_or_default()
+}
+
+/// Writes `order` (most recent first) to [`MRU_FILE`] in `dir`, one model
+/// per line.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written.
+pub fn save_mru_order_in(dir: &Path, order: &[String]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut content = order.join("\n");
+    content.push('\n');
+    fs::write(dir.join(MRU_FILE), content)?;
+    Ok(())
+}
+
+/// Writes `order` to [`MRU_FILE`] in the default [`data_dir`].
+///
+/// # Errors
+///
+/// See [`save_mru_order_in`].
+pub fn save_mru_order(order: &[String]) -> Result<()> {
+    save_mru_order_in(&data_dir()?, order)
+}
+
+
+/// Name of the file in the data directory that stores the bookmarks.
+pub const BOOKMARKS_FILE: &str = "bookmarks.txt";
+
+/// Reads the bookmarked scroll positions per model from [`BOOKMARKS_FILE`]
+/// in `dir`.
+///
+/// Each line holds a model name and a line number separated by a tab.
+/// Malformed lines are skipped and a missing file yields no bookmarks.
+pub fn load_bookmarks_in(dir: &Path) -> HashMap<String, u16> {
+    let content = fs::read_to_string(dir.join(BOOKMARKS_FILE)).unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let (model, scroll) = line.split_once('\t')?;
+            Some((model.to_string(), scroll.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Reads the bookmarks from the default [`data_dir`].
+pub fn load_bookmarks() -> HashMap<String, u16> {
+    data_dir().map(|dir| load_bookmarks_in(&dir)).unwrap_or_default()
+}
+
+/// Writes `bookmarks` to [`BOOKMARKS_FILE`] in `dir`, sorted by model.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written.
+pub fn save_bookmarks_in(dir: &Path, bookmarks: &HashMap<String, u16>) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut lines: Vec<String> = bookmarks
+        .iter()
+        .map(|(model, scroll)| format!("{}\t{}\n", model, scroll))
+        .collect();
+    lines.sort();
+    fs::write(dir.join(BOOKMARKS_FILE), lines.concat())?;
+    Ok(())
+}
+
+/// Writes `bookmarks` to [`BOOKMARKS_FILE`] in the default [`data_dir`].
+///
+/// # Errors
+///
+/// See [`save_bookmarks_in`].
+pub fn save_bookmarks(bookmarks: &HashMap<String, u16>) -> Result<()> {
+    save_bookmarks_in(&data_dir()?, bookmarks)
+}
+
+/// Name of the file in the data directory that stores the input drafts.
+pub const DRAFTS_FILE: &str = "drafts.toml";
+
+/// An unsent input, as saved by [`save_drafts`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draft {
+    /// The text typed so far.
+    pub text: String,
+    /// Cursor position in characters.
+    pub cursor: usize,
+}
+
+/// Reads the input drafts per model from [`DRAFTS_FILE`] in `dir`.
+///
+/// Cursors past the end of their text are moved to the end. A missing or
+/// malformed file yields no drafts.
+pub fn load_drafts_in(dir: &Path) -> HashMap<String, Draft> {
+    let content = fs::read_to_string(dir.join(DRAFTS_FILE)).unwrap_or_default();
+    let mut drafts: HashMap<String, Draft> = toml::from_str(&content).unwrap_or_default();
+    for draft in drafts.values_mut() {
+        draft.cursor = draft.cursor.min(draft.text.chars().count());
+    }
+    drafts
+}
+
+/// Reads the input drafts from the default [`data_dir`].
+pub fn load_drafts() -> HashMap<String, Draft> {
+    data_dir().map(|dir| load_drafts_in(&dir)).unwrap_or_default()
+}
+
+/// Writes the non-empty `drafts` to [`DRAFTS_FILE`] in `dir`, sorted by
+/// model, as a TOML table per model.
+///
+/// Without any, the file is removed, so no stale draft comes back.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created or the file cannot
+/// be written or removed.
+pub fn save_drafts_in(dir: &Path, drafts: &HashMap<String, Draft>) -> Result<()> {
+    let drafts: BTreeMap<&String, &Draft> = drafts
+        .iter()
+        .filter(|(_, draft)| !draft.text.is_empty())
+        .collect();
+    let path = dir.join(DRAFTS_FILE);
+    if drafts.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(dir)?;
+    let content = toml::to_string(&drafts).map_err(std::io::Error::other)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Writes `drafts` to [`DRAFTS_FILE`] in the default [`data_dir`].
+///
+/// # Errors
+///
+/// See [`save_drafts_in`].
+pub fn save_drafts(drafts: &HashMap<String, Draft>) -> Result<()> {
+    save_drafts_in(&data_dir()?, drafts)
+}
+
+/// Returns the file name [`save_model_histories`] uses for `model`, without
+/// the timestamp.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::utils::safe_model_name;
+///
+/// assert_eq!(safe_model_name("gpu/llama3:8b"), "gpu_llama3_8b");
+/// ```
+pub fn safe_model_name(model: &str) -> String {
+    model.replace([':', '/', '\\'], "_")
+}
+
+/// A saved log file, as listed by [`list_logs_grouped`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFileInfo {
+    /// Location of the file.
+    pub path: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Lists the log files in `dir`, grouped by the model they belong to.
+///
+/// Keys are model names as they appear in file names, i.e. with `:`, `/`
+/// and `\` replaced by `_`; the combined logs of
+/// [`save_history_to_file`] are listed under `chat`. Files within a group
+/// are sorted by name, which is oldest first. Other files are ignored and
+/// an unreadable directory yields an empty map.
+pub fn list_logs_grouped_in(dir: &Path) -> HashMap<String, Vec<LogFileInfo>> {
+    let mut groups: HashMap<String, Vec<LogFileInfo>> = HashMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return groups;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(caps) = name.to_str().and_then(|n| LOG_FILE_RE.captures(n)) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            groups.entry(caps[1].to_string()).or_default().push(LogFileInfo {
+                path: entry.path(),
+                size: metadata.len(),
+            });
+        }
+    }
+    for files in groups.values_mut() {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    groups
+}
+
+/// Lists the log files in the default [`data_dir`], grouped by model.
+///
+/// See [`list_logs_grouped_in`].
+pub fn list_logs_grouped() -> HashMap<String, Vec<LogFileInfo>> {
+    data_dir().map(|dir| list_logs_grouped_in(&dir)).unwrap_or_default()
+}
+
+/// Deletes every log file of `model` in `dir` and returns how many were
+/// deleted.
+///
+/// `model` may be given as installed (`llama3:8b`) or as listed by
+/// [`list_logs_grouped_in`] (`llama3_8b`).
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be deleted; files deleted before that
+/// stay deleted.
+pub fn delete_logs_for_model_in(dir: &Path, model: &str) -> Result<usize> {
+    let files = list_logs_grouped_in(dir)
+        .remove(&safe_model_name(model))
+        .unwrap_or_default();
+    for file in &files {
+        fs::remove_file(&file.path)?;
+    }
+    Ok(files.len())
+}
+
+/// Deletes every log file of `model` in the default [`data_dir`].
+///
+/// # Errors
+///
+/// See [`delete_logs_for_model_in`].
+pub fn delete_logs_for_model(model: &str) -> Result<usize> {
+    delete_logs_for_model_in(&data_dir()?, model)
+}
+
+/// Deletes the oldest combined logs of [`save_history_to_file`]
+/// (`chat_*`) in `dir` so that at most `keep` remain, and returns how
+/// many were deleted.
+///
+/// Per-model logs are left alone.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be deleted; files deleted before that
+/// stay deleted.
+pub fn prune_old_logs_in(dir: &Path, keep: usize) -> Result<usize> {
+    let files = list_logs_grouped_in(dir).remove("chat").unwrap_or_default();
+    // Sorted oldest first.
+    let excess = files.len().saturating_sub(keep);
+    for file in &files[..excess] {
+        fs::remove_file(&file.path)?;
+    }
+    Ok(excess)
+}
+
+/// Prunes the combined logs in the default [`data_dir`] down to `keep`.
+///
+/// # Errors
+///
+/// See [`data_dir`] and [`prune_old_logs_in`].
+pub fn prune_old_logs(keep: usize) -> Result<usize> {
+    prune_old_logs_in(&data_dir()?, keep)
+}
+
+/// Program that opens a directory in the platform's file manager.
+#[cfg(target_os = "macos")]
+const FILE_MANAGER: &str = "open";
+/// Program that opens a directory in the platform's file manager.
+#[cfg(target_os = "windows")]
+const FILE_MANAGER: &str = "explorer";
+/// Program that opens a directory in the platform's file manager.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const FILE_MANAGER: &str = "xdg-open";
+
+/// Opens `dir` in the platform's file manager (`open`, `explorer` or
+/// `xdg-open`) without waiting for it.
+///
+/// The handler's output is discarded so it cannot garble the TUI.
+///
+/// # Errors
+///
+/// Returns [`LazyLlamaError::Io`] if `dir` is not a directory or the
+/// handler cannot be started.
+pub fn open_in_file_manager(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} is not a directory", dir.display()),
+        )
+        .into());
+    }
+    let mut child = Command::new(FILE_MANAGER)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the handler once it exits instead of leaving a zombie behind.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Opens the default [`data_dir`] in the platform's file manager.
+///
+/// # Errors
+///
+/// See [`data_dir`] and [`open_in_file_manager`].
+pub fn open_data_dir() -> Result<()> {
+    open_in_file_manager(&data_dir()?)
+}
+
+/// Shell and flag that run the command line of [`pipe_through`].
+#[cfg(target_os = "windows")]
+const SHELL: [&str; 2] = ["cmd", "/C"];
+/// Shell and flag that run the command line of [`pipe_through`].
+#[cfg(not(target_os = "windows"))]
+const SHELL: [&str; 2] = ["sh", "-c"];
+
+/// How long [`pipe_through`] waits for a command by default.
+pub const PIPE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs the shell command line `command` with `input` on its stdin and
+/// returns what it wrote to stdout.
+///
+/// The command is killed if it has not finished after `timeout`.
+///
+/// # Errors
+///
+/// Returns [`LazyLlamaError::Io`] if the command cannot be started, times
+/// out, exits unsuccessfully (with the first line of its stderr) or
+/// prints something other than UTF-8 text.
+pub fn pipe_through(command: &str, input: &str, timeout: Duration) -> Result<String> {
+    let mut child = Command::new(SHELL[0])
+        .arg(SHELL[1])
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Fed and drained from threads, so a command that writes before it has
+    // read all of its input cannot deadlock on a full pipe. A command that
+    // ignores its input makes the write fail, which is fine.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let drain = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            pipe.read_to_end(&mut bytes).map(|_| bytes)
+        })
+    };
+    let stdout = drain(Box::new(child.stdout.take().expect("stdout is piped")));
+    let stderr = drain(Box::new(child.stderr.take().expect("stderr is piped")));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("{} did not finish within {} s", command, timeout.as_secs_f32()),
+            )
+            .into());
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let joined = |handle: thread::JoinHandle<std::io::Result<Vec<u8>>>| {
+        handle
+            .join()
+            .unwrap_or_else(|_| Err(std::io::Error::other("reader thread panicked")))
+    };
+    let stdout = joined(stdout)?;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&joined(stderr)?).into_owned();
+        let reason = stderr.lines().next().unwrap_or("").trim().to_string();
+        let message = if reason.is_empty() {
+            format!("{} failed ({})", command, status)
+        } else {
+            format!("{} failed ({}): {}", command, status, reason)
+        };
+        return Err(std::io::Error::other(message).into());
+    }
+    String::from_utf8(stdout).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} did not print text", command),
+        )
+        .into()
+    })
+}
+
+/// Largest part of a file, in bytes, that [`read_attachment`] reads.
+///
+/// Roughly 8k tokens, so an attached file leaves room in common context
+/// windows.
+pub const MAX_ATTACHMENT_BYTES: usize = 32 * 1024;
+
+/// Returns the Markdown code fence language for `path`, inferred from its
+/// extension, or `""` if it is unknown.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::utils::language_for_path;
+/// use std::path::Path;
+///
+/// assert_eq!(language_for_path(Path::new("src/main.rs")), "rust");
+/// assert_eq!(language_for_path(Path::new("Makefile")), "make");
+/// assert_eq!(language_for_path(Path::new("notes.xyz")), "");
+/// ```
+pub fn language_for_path(path: &Path) -> &'static str {
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("Makefile" | "makefile" | "GNUmakefile") => return "make",
+        Some("Dockerfile") => return "dockerfile",
+        _ => {}
+    }
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "rb" => "ruby",
+        "php" => "php",
+        "lua" => "lua",
+        "sh" | "bash" => "bash",
+        "zsh" => "zsh",
+        "ps1" => "powershell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "xml" => "xml",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        _ => "",
+    }
+}
+
+/// A text file read by [`read_attachment`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The file's contents, cut to the size limit.
+    pub content: String,
+    /// Size of the whole file in bytes.
+    pub size: u64,
+    /// Whether `content` is only the beginning of the file.
+    pub truncated: bool,
+}
+
+/// Reads the text file at `path` for attaching it to a prompt.
+///
+/// Files larger than `max_bytes` are cut after the last complete line that
+/// fits, so the model never sees half a line or a broken character.
+///
+/// # Errors
+///
+/// Returns [`LazyLlamaError::Io`] if the file cannot be read, e.g. because
+/// it does not exist or is a directory, or is not UTF-8 text.
+pub fn read_attachment(path: &Path, max_bytes: usize) -> Result<Attachment> {
+    let bytes = fs::read(path)?;
+    let size = bytes.len() as u64;
+    let mut content = String::from_utf8(bytes).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} is not a text file", path.display()),
+        )
+    })?;
+    let truncated = content.len() > max_bytes;
+    if truncated {
+        let mut end = max_bytes;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let end = content[..end].rfind('\n').map_or(end, |i| i + 1);
+        content.truncate(end);
+    }
+    Ok(Attachment {
+        content,
+        size,
+        truncated,
+    })
+}
+
+/// Returns the usual file extension for a code fence language, or `None`
+/// if it is unknown. The inverse of [`language_for_path`].
+pub fn extension_for_language(lang: &str) -> Option<&'static str> {
+    let extension = match lang.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "javascript" | "js" => "js",
+        "jsx" => "jsx",
+        "typescript" | "ts" => "ts",
+        "tsx" => "tsx",
+        "go" => "go",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "csharp" | "cs" => "cs",
+        "java" => "java",
+        "kotlin" => "kt",
+        "swift" => "swift",
+        "ruby" | "rb" => "rb",
+        "php" => "php",
+        "lua" => "lua",
+        "bash" | "sh" | "shell" => "sh",
+        "zsh" => "zsh",
+        "powershell" => "ps1",
+        "sql" => "sql",
+        "html" => "html",
+        "css" => "css",
+        "xml" => "xml",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "markdown" | "md" => "md",
+        _ => return None,
+    };
+    Some(extension)
+}
+
+/// Relative file path with an extension, as named in a fence line or a
+/// first-line comment.
+static FILE_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[\w.-]+(/[\w.-]+)*\.\w+$").unwrap());
+
+/// Infers the file name a code block was written for.
+///
+/// Looks at the rest of the fence line (```` ```rust // src/foo.rs ````)
+/// and at a comment on the first code line (`// src/foo.rs`,
+/// `# foo.py`, `-- schema.sql`, `<!-- index.html -->`). Only relative
+/// paths without `..` are accepted, so exports stay inside their
+/// directory.
+///
+/// # Example
+///
+/// ```
+/// use lazyllama::ui::extract_code_blocks;
+/// use lazyllama::utils::infer_file_name;
+///
+/// let blocks = extract_code_blocks("```rust // src/lib.rs\npub fn f() {}\n```\n```python\n# tool.py\n```");
+/// assert_eq!(infer_file_name(&blocks[0]).as_deref(), Some("src/lib.rs"));
+/// assert_eq!(infer_file_name(&blocks[1]).as_deref(), Some("tool.py"));
+/// ```
+pub fn infer_file_name(block: &CodeBlock) -> Option<String> {
+    let first_line = block.code.lines().next().unwrap_or("");
+    [block.info.as_str(), first_line].into_iter().find_map(|line| {
+        let name = line
+            .trim()
+            .trim_start_matches(['/', '#', '-', '*', '<', '!', ';'])
+            .trim_end_matches(['/', '*', '>', '-'])
+            .trim()
+            .trim_start_matches("File:")
+            .trim()
+            .trim_start_matches("./");
+        let safe = FILE_NAME_RE.is_match(name) && !name.split('/').any(|part| part == "..");
+        safe.then(|| name.to_string())
+    })
+}
+
+/// Writes every fenced code block of `history` to its own file in `dir`
+/// and returns the written paths.
+///
+/// Files are named by [`infer_file_name`], or `block_<n>.<ext>` with the
+/// extension of the block's language (`txt` if unknown). Existing files
+/// are never overwritten: a taken name gets an index appended, as in
+/// `main_2.rs`. Missing directories are created.
+///
+/// # Errors
+///
+/// Returns [`LazyLlamaError::Io`] if a directory or file cannot be
+/// written; files written before the failure are kept.
+pub fn export_code_blocks(history: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for (index, block) in extract_code_blocks(history).iter().enumerate() {
+        let name = infer_file_name(block).unwrap_or_else(|| {
+            let lang = block.lang.as_deref().unwrap_or("");
+            let extension = extension_for_language(lang).unwrap_or("txt");
+            format!("block_{}.{}", index + 1, extension)
+        });
+        let path = free_path(&dir.join(name));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut code = block.code.clone();
+        if !code.ends_with('\n') {
+            code.push('\n');
+        }
+        fs::write(&path, code)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Returns `path`, or the first `<stem>_<n>.<ext>` variant of it that does
+/// not exist yet.
+fn free_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("block");
+    let extension = path.extension().and_then(|e| e.to_str());
+    (2..)
+        .map(|n| {
+            let name = match extension {
+                Some(extension) => format!("{}_{}.{}", stem, n, extension),
+                None => format!("{}_{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
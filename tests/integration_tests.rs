@@ -10,6 +10,7 @@
 //! - **Model Buffer Management**: Per-model state isolation and persistence
 //! - **History Parsing**: Complex conversation parsing with edge cases
 //! - **UI State Synchronization**: Coordination between UI and application state
+//! - **Headless Streaming**: `send_query` end-to-end with a scripted client and `TestBackend`
 //!
 //! ## Integration Test Principles
 //!
@@ -18,8 +19,28 @@
 //! - Validate complete user workflows and data flow paths
 //! - Test error conditions and graceful degradation scenarios
 
+// Some of the older tests spell out checks clippy considers redundant.
+#![allow(
+    clippy::assertions_on_constants,
+    clippy::bool_comparison,
+    clippy::collapsible_if,
+    clippy::collapsible_match,
+    clippy::len_zero,
+    clippy::useless_vec
+)]
+
 use std::collections::HashMap;
 
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use lazyllama::app::{App, NO_TOKENS_AFTER, NO_TOKENS_HINT};
+use lazyllama::client::{startup_client, write_model_list, GenerationTimings, ModelInfo, ScriptedClient};
+use lazyllama::config::{AutoscrollMode, Config, Host};
+use lazyllama::error::LazyLlamaError;
+use lazyllama::ui::{ERROR_PREFIX, FOOTER_PREFIX};
+use std::time::{Duration, Instant};
+use ratatui::{backend::TestBackend, style::Color, Terminal};
+
 // For integration tests, we import the library as an external crate
 // Note: In real integration tests, one would use `use lazyllama::*`,
 // but since this is a binary application, we use direct modules
@@ -455,4 +476,1504 @@ fn test_error_recovery_scenarios() {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Terminal backed by ratatui's in-memory `TestBackend`.
+type TestTerminal = Terminal<TestBackend>;
+
+/// Creates a headless terminal of the given size.
+fn test_terminal(width: u16, height: u16) -> TestTerminal {
+    Terminal::new(TestBackend::new(width, height)).expect("TestBackend never fails")
+}
+
+/// Flattens the rendered `TestBackend` buffer into one string per row.
+fn buffer_lines(terminal: &TestTerminal) -> Vec<String> {
+    let buffer = terminal.backend().buffer();
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+        .collect()
+}
+
+/// Removes the timing footers, whose wall-clock fallback is not deterministic.
+fn without_footers(history: &str) -> String {
+    history
+        .split_inclusive('\n')
+        .filter(|line| !line.starts_with(FOOTER_PREFIX))
+        .collect()
+}
+
+/// Tests the complete streaming path of `send_query` without Ollama.
+///
+/// A scripted client streams a canned response in several chunks; the
+/// resulting history must contain the prompt, the reassembled answer and the
+/// turn separator, and the request must reach the client unchanged.
+#[tokio::test]
+async fn test_send_query_streams_scripted_response() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["Hel", "lo ", "there"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.cursor_pos = 2;
+    app.send_query(&mut terminal).await.unwrap();
+
+    assert_eq!(without_footers(&app.history), "\nYOU: Hi\n\nAI [mock]: Hello there\n---\n");
+    assert!(app.input.is_empty());
+    assert_eq!(app.cursor_pos, 0);
+    assert!(!app.is_loading);
+    assert_eq!(app.model_histories.get("mock"), Some(&app.history));
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].model, "mock");
+    assert_eq!(requests[0].prompt, "Hi");
+}
+
+/// Tests that a streamed answer is visible in the rendered terminal buffer.
+#[tokio::test]
+async fn test_send_query_renders_into_test_backend() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["The answer ", "is 42"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Question?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+
+    let lines = buffer_lines(&terminal);
+    assert!(lines.iter().any(|l| l.contains("YOU: Question?")), "{:#?}", lines);
+    assert!(lines.iter().any(|l| l.contains("The answer is 42")), "{:#?}", lines);
+    assert!(lines.iter().any(|l| l.contains("Models (1)")), "{:#?}", lines);
+}
+
+/// Tests that the indentation of a multi-line prompt survives rendering.
+#[tokio::test]
+async fn test_multi_line_prompt_renders_indented() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["ok"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "if x {\n    return;\n}".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("YOU: if x {\n    return;\n}\n"));
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+
+    let lines = buffer_lines(&terminal);
+    assert!(lines.iter().any(|l| l.contains("│     return;")), "{:#?}", lines);
+}
+
+/// Tests that focus mode hides the model list and status bar and that
+/// leaving it restores the normal layout.
+#[tokio::test]
+async fn test_focus_mode_layout() {
+    let client = ScriptedClient::new(vec!["mock".to_string(), "other".to_string()])
+        .with_response(vec!["Focused answer"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+    app.input = "Question?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    app.focus_mode = true;
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    assert!(lines[0].contains("LazyLlama [mock]"), "{:#?}", lines);
+    assert!(lines.iter().any(|l| l.contains("Focused answer")), "{:#?}", lines);
+    assert!(!lines.iter().any(|l| l.contains("Models (")), "{:#?}", lines);
+    assert!(!lines.iter().any(|l| l.contains("F1: Help")), "{:#?}", lines);
+
+    // Switching models still works and shows up in the title.
+    app.select_next_model();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[0].contains("LazyLlama [other]"));
+
+    app.focus_mode = false;
+    app.select_previous_model();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    assert!(lines.iter().any(|l| l.contains("Models (2)")), "{:#?}", lines);
+    assert!(lines[23].contains("F1: Help"), "{:#?}", lines);
+    assert!(lines.iter().any(|l| l.contains("Focused answer")), "{:#?}", lines);
+}
+
+#[tokio::test]
+async fn test_send_query_records_client_error() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_error("connection refused");
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.ends_with(&format!("{}connection refused\n---\n", ERROR_PREFIX)));
+    assert!(!app.is_loading);
+    assert_eq!(app.last_failed_prompt.as_deref(), Some("Hi"));
+    assert!(matches!(app.last_error, Some(LazyLlamaError::Connection(_))));
+    assert_eq!(app.model_histories["mock"], app.history);
+}
+
+/// Tests that an unknown model is reported as `ModelNotFound`, naming the
+/// model.
+#[tokio::test]
+async fn test_send_query_reports_missing_model() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_error("model \"mock\" not found, try pulling it first");
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    match &app.last_error {
+        Some(err @ LazyLlamaError::ModelNotFound { model, .. }) => {
+            assert_eq!(model, "mock");
+            assert!(err.to_string().contains("try pulling it first"));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+/// Tests that retrying replaces the failed exchange with a fresh answer and
+/// that the retry hint disappears once something is typed.
+#[tokio::test]
+async fn test_retry_after_error() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_error("connection refused")
+        .with_response(vec!["Recovered"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.can_retry());
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].contains("press r to retry"));
+
+    app.insert_char('x');
+    assert!(!app.can_retry());
+    app.backspace();
+    assert!(!app.can_retry());
+
+    app.last_failed_prompt = Some("Hi".to_string());
+    app.retry(&mut terminal).await.unwrap();
+    assert_eq!(without_footers(&app.history), "\nYOU: Hi\n\nAI [mock]: Recovered\n---\n");
+    assert_eq!(app.last_failed_prompt, None);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+/// Tests that an answer of only whitespace chunks is reported as an empty
+/// response instead of an empty turn, and that retrying it raises the
+/// temperature.
+#[tokio::test]
+async fn test_empty_response_offers_retry() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec![" ", "\n\n", "  "])
+        .with_response(vec!["Hello"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.empty_response);
+    assert!(app.can_retry());
+    assert_eq!(app.history, format!("\nYOU: Hi\n\nAI [mock]: \n{}empty response\n---\n", ERROR_PREFIX));
+    assert!(!app.history.contains(FOOTER_PREFIX));
+    assert_eq!(
+        app.status_text().as_deref(),
+        Some("Empty response, press r to retry with a higher temperature")
+    );
+
+    app.retry(&mut terminal).await.unwrap();
+    assert!(!app.empty_response);
+    assert_eq!(without_footers(&app.history), "\nYOU: Hi\n\nAI [mock]: Hello\n---\n");
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].options.temperature, None);
+    assert_eq!(requests[1].options.temperature, Some(0.9));
+}
+
+/// Tests that `\r\n` and lone `\r` in streamed output are stored and
+/// rendered as plain line breaks, also when split between chunks.
+#[tokio::test]
+async fn test_stream_normalizes_line_endings() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["line1\r", "\nline2\r", "", "done"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(!app.history.contains('\r'), "{:?}", app.history);
+    assert_eq!(without_footers(&app.history), "\nYOU: Hi\n\nAI [mock]: line1\nline2\ndone\n---\n");
+
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    let first = lines.iter().position(|l| l.contains("line1")).unwrap();
+    assert!(lines[first + 1].contains("line2"), "{:#?}", lines);
+    assert!(lines.iter().all(|l| !l.contains('\r')));
+}
+
+/// Tests that reloading the model list adds pulled models, drops removed
+/// ones with their buffers and keeps the selection.
+#[tokio::test]
+async fn test_reload_models_merges_changes() {
+    let client = ScriptedClient::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    let installed = client.installed_models();
+    let mut app = App::new_with_client(client).await;
+    app.select_model("b");
+    app.input = "draft".to_string();
+    app.model_histories.insert("c".to_string(), "\nYOU: Hi\n\nAI [c]: Hello\n---\n".to_string());
+    app.model_inputs.insert("c".to_string(), "unsent".to_string());
+
+    *installed.lock().unwrap() = vec!["b".to_string(), "d".to_string()];
+    app.reload_models().await;
+    assert_eq!(app.models, ["b", "d"]);
+    assert_eq!(app.list_state.selected(), Some(0));
+    assert_eq!(app.input, "draft");
+    assert_eq!(app.status_message.as_deref(), Some("2 models (refreshed)"));
+    assert!(app.model_inputs.contains_key("d") && !app.model_inputs.contains_key("a"));
+    assert!(!app.model_histories.contains_key("a"));
+    assert!(!app.model_inputs.contains_key("c"));
+    assert!(!app.all_model_histories().contains_key("c"));
+
+    // The selected model is removed: another one is selected.
+    *installed.lock().unwrap() = vec!["d".to_string()];
+    app.reload_models().await;
+    assert_eq!(app.models, ["d"]);
+    assert_eq!(app.list_state.selected(), Some(0));
+    assert!(app.input.is_empty());
+    assert_eq!(app.model_inputs.keys().collect::<Vec<_>>(), ["d"]);
+}
+
+/// Tests that `keep_unavailable_models` keeps the buffers of a model that
+/// is missing from the server until it is back.
+#[tokio::test]
+async fn test_refresh_keeps_unavailable_models_when_configured() {
+    let client = ScriptedClient::new(vec!["a".to_string(), "b".to_string()]);
+    let installed = client.installed_models();
+    let config = Config {
+        keep_unavailable_models: true,
+        ..Default::default()
+    };
+    let mut app = App::builder().client(client).config(config).build();
+    app.refresh_models().await.unwrap();
+    app.model_histories.insert("b".to_string(), "\nYOU: Hi\n".to_string());
+
+    *installed.lock().unwrap() = vec!["a".to_string()];
+    app.refresh_models().await.unwrap();
+    assert_eq!(app.models, ["a"]);
+    assert_eq!(app.model_histories.get("b").map(String::as_str), Some("\nYOU: Hi\n"));
+
+    *installed.lock().unwrap() = vec!["a".to_string(), "b".to_string()];
+    app.refresh_models().await.unwrap();
+    assert!(app.select_model("b"));
+    assert_eq!(app.history, "\nYOU: Hi\n");
+}
+
+/// Tests that prompt templates only affect what the model receives.
+///
+/// The per-model template wins over the global one, the request carries the
+/// wrapped prompt, and the `YOU:` line in the history keeps the raw text.
+#[tokio::test]
+async fn test_send_query_applies_prompt_template() {
+    let client = ScriptedClient::new(vec!["mock".to_string(), "other".to_string()])
+        .with_response(vec!["ok"])
+        .with_response(vec!["ok"]);
+    let requests = client.request_log();
+    let config = Config {
+        prompt_template: Some("Answer concisely. {prompt}".to_string()),
+        model_templates: HashMap::from([(
+            "other".to_string(),
+            "{prompt}\nRespond in Markdown.".to_string(),
+        )]),
+        ..Default::default()
+    };
+    let mut app = App::builder().client(client).config(config).build();
+    app.refresh_models().await.unwrap();
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Why?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("\nYOU: Why?\n"));
+    assert!(!app.history.contains("Answer concisely"));
+
+    app.select_model("other");
+    app.input = "How?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].prompt, "Answer concisely. Why?");
+    assert_eq!(requests[1].prompt, "How?\nRespond in Markdown.");
+}
+
+/// Tests that the greeting primes only the first message of each model's
+/// conversation, visibly in the history.
+#[tokio::test]
+async fn test_greeting_primes_first_message() {
+    let client = ScriptedClient::new(vec!["mock".to_string(), "other".to_string()])
+        .with_response(vec!["ok"])
+        .with_response(vec!["ok"])
+        .with_response(vec!["ok"]);
+    let requests = client.request_log();
+    let config = Config {
+        greeting: Some("Be terse.".to_string()),
+        ..Default::default()
+    };
+    let mut app = App::builder().client(client).config(config).build();
+    app.refresh_models().await.unwrap();
+    let mut terminal = test_terminal(80, 24);
+
+    for prompt in ["Why?", "How?"] {
+        app.input = prompt.to_string();
+        app.send_query(&mut terminal).await.unwrap();
+    }
+    assert!(app.history.starts_with("\nYOU: Be terse.\n\nWhy?\n"));
+    app.select_model("other");
+    app.input = "What?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    let prompts: Vec<String> = requests.lock().unwrap().iter().map(|r| r.prompt.clone()).collect();
+    assert_eq!(prompts, ["Be terse.\n\nWhy?", "How?", "Be terse.\n\nWhat?"]);
+}
+
+/// Tests that a template without `{prompt}` falls back to the raw prompt.
+#[tokio::test]
+async fn test_send_query_ignores_template_without_placeholder() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["ok"]);
+    let requests = client.request_log();
+    let config = Config {
+        prompt_template: Some("Answer concisely.".to_string()),
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+    let mut app = App::builder().client(client).config(config).build();
+    app.refresh_models().await.unwrap();
+
+    app.input = "Why?".to_string();
+    app.send_query(&mut test_terminal(80, 24)).await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].prompt, "Why?");
+}
+
+/// Tests that regenerating replaces the last answer and, when enabled,
+/// keeps the old one so the UI can render a diff.
+#[tokio::test]
+async fn test_regenerate_replaces_last_answer() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["One"])
+        .with_response(vec!["The answer is 41"])
+        .with_response(vec!["The answer is 42"]);
+    let requests = client.request_log();
+    let config = Config {
+        regenerate_diff: true,
+        ..Default::default()
+    };
+    let mut app = App::builder().client(client).config(config).build();
+    app.refresh_models().await.unwrap();
+    let mut terminal = test_terminal(80, 30);
+
+    app.input = "First".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    app.input = "Question?".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    app.input = "draft".to_string();
+    app.regenerate(&mut terminal).await.unwrap();
+
+    assert_eq!(
+        without_footers(&app.history),
+        "\nYOU: First\n\nAI [mock]: One\n---\n\nYOU: Question?\n\nAI [mock]: The answer is 42\n---\n"
+    );
+    assert_eq!(app.input, "draft");
+    assert_eq!(app.previous_answer.as_deref(), Some("The answer is 41"));
+    assert_eq!(requests.lock().unwrap()[2].prompt, "Question?");
+
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    assert!(lines.iter().any(|l| l.contains("Changes vs previous answer")), "{:#?}", lines);
+}
+
+#[tokio::test]
+async fn test_regenerate_without_diff_and_empty_history() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["a"])
+        .with_response(vec!["b"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.regenerate(&mut terminal).await.unwrap();
+    assert!(app.history.is_empty());
+    assert_eq!(app.status_message.as_deref(), Some("Nothing to regenerate"));
+
+    app.input = "q".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    app.regenerate(&mut terminal).await.unwrap();
+    assert_eq!(without_footers(&app.history), "\nYOU: q\n\nAI [mock]: b\n---\n");
+    assert!(app.previous_answer.is_none());
+}
+
+/// Tests that server-reported timings from the final chunk end up in the
+/// turn footer, and that the footer is not treated as part of the answer.
+#[tokio::test]
+async fn test_send_query_writes_timing_footer() {
+    let timings = GenerationTimings {
+        load: Some(Duration::from_millis(2100)),
+        prompt_eval: Some(Duration::from_millis(300)),
+        eval: Some(Duration::from_millis(1400)),
+        eval_count: Some(25),
+    };
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_timed_response(vec!["Hi"], timings)
+        .with_response(vec!["Hello"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Q".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(
+        app.history,
+        "\nYOU: Q\n\nAI [mock]: Hi\n⏱ load 2.1s · prompt 0.3s · gen 1.4s @ 18 tok/s\n---\n"
+    );
+    let exchanges = lazyllama::ui::split_exchanges(&app.history);
+    assert_eq!(lazyllama::ui::exchange_parts(exchanges[0]), Some(("Q", "Hi")));
+
+    app.input = "Q2".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    let last_line = app.history.lines().rev().nth(1).unwrap();
+    assert!(last_line.starts_with(FOOTER_PREFIX) && last_line.ends_with('s'), "{}", last_line);
+}
+
+/// Tests the output of `--list-models`: one installed model per line.
+#[tokio::test]
+async fn test_write_model_list() {
+    let client = ScriptedClient::new(vec!["llama3:8b".to_string(), "qwen2.5-coder:7b".to_string()]);
+    let mut out = Vec::new();
+    write_model_list(&client, &mut out).await.unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "llama3:8b\nqwen2.5-coder:7b\n");
+}
+
+/// Tests that `--list-models` asks the first configured host, like the
+/// interactive session.
+#[tokio::test]
+async fn test_startup_client_uses_first_host() {
+    let hosts = vec![
+        Host { name: "gpu".to_string(), url: "http://gpu:11434".to_string() },
+        Host { name: "local".to_string(), url: "http://localhost:11434".to_string() },
+    ];
+    let factory = |url: &str| -> anyhow::Result<Box<dyn lazyllama::client::ModelClient>> {
+        Ok(Box::new(ScriptedClient::new(vec![format!("model@{}", url)])))
+    };
+    let client = startup_client(&hosts, &factory);
+    let mut out = Vec::new();
+    write_model_list(client.as_ref(), &mut out).await.unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "model@http://gpu:11434\n");
+}
+
+/// Tests that an ephemeral session is flagged in the status bar.
+#[test]
+fn test_ephemeral_status_indicator() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).ephemeral(true).build();
+    let mut terminal = test_terminal(120, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].starts_with(" ⚠ NOT SAVED | F1: Help"));
+
+    app.focus_mode = true;
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[0].contains("NOT SAVED"));
+}
+
+
+/// Tests that a long input line shows the `‹` indicator once scrolled.
+#[test]
+fn test_input_scroll_indicators_rendered() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    let mut terminal = test_terminal(40, 24);
+    app.insert_str(&"x".repeat(60));
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    // The input box is the last three rows above the status bar.
+    assert!(lines[22].contains('‹'), "{:#?}", lines);
+    assert!(!lines[22].contains('›'), "{:#?}", lines);
+
+    app.move_cursor_home();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    assert!(lines[22].contains('›') && !lines[22].contains('‹'), "{:#?}", lines);
+}
+
+/// Tests that the streaming progress is shown while an answer arrives and
+/// cleared once the turn is complete.
+#[tokio::test]
+async fn test_stream_progress_shown_while_streaming() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["Hello ", "streaming world"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    // The last frame drawn while streaming shows the full answer's size.
+    let lines = buffer_lines(&terminal);
+    let progress = "AI is thinking... · 3 words · 21 chars";
+    assert!(lines.iter().any(|l| l.contains(progress)), "{:#?}", lines);
+
+    assert_eq!(app.stream_start, None);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    assert!(!lines.iter().any(|l| l.contains("words")), "{:#?}", lines);
+}
+
+/// Tests that trailing blank lines of an answer are trimmed once the turn
+/// is complete, and kept when trimming is turned off.
+#[tokio::test]
+async fn test_trailing_blank_lines_trimmed() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["Answer\n", "\n\n  \n", "\n"])
+        .with_response(vec!["Kept\n\n"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Q1".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(without_footers(&app.history), "\nYOU: Q1\n\nAI [mock]: Answer\n---\n");
+
+    app.trim_response = false;
+    app.input = "Q2".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(without_footers(&app.history).ends_with("AI [mock]: Kept\n\n\n---\n"));
+}
+
+/// Tests that render and append timings are recorded only in debug mode.
+#[tokio::test]
+async fn test_debug_render_timings() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["a", "b"]);
+    let mut app = App::builder()
+        .client(client)
+        .models(vec!["mock".to_string()])
+        .debug_keys(true)
+        .build();
+    let mut terminal = test_terminal(200, 24);
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let status = &buffer_lines(&terminal)[23];
+    assert!(status.contains("µs) | Append: "), "{}", status);
+    assert!(app.render_count > 0);
+
+    let mut quiet = App::builder().models(vec!["mock".to_string()]).build();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut quiet)).unwrap();
+    assert_eq!((quiet.render_count, quiet.last_render_us), (0, 0));
+    assert!(!buffer_lines(&terminal)[23].contains("Render:"));
+}
+
+
+/// Tests that switching hosts reloads the model list from the new host's
+/// client and keeps conversations of equally named models apart.
+#[tokio::test]
+async fn test_switch_host_keeps_buffers_per_host() {
+    let config = Config::parse(
+        "[[hosts]]\nname = \"laptop\"\nurl = \"http://laptop\"\n\n\
+         [[hosts]]\nname = \"gpu\"\nurl = \"http://gpu\"\n",
+    )
+    .unwrap();
+    let mut app = App::builder()
+        .config(config)
+        .client_factory(|url| {
+            let models = match url {
+                "http://gpu" => vec!["llama3".to_string(), "big".to_string()],
+                _ => vec!["llama3".to_string()],
+            };
+            Ok(Box::new(ScriptedClient::new(models).with_response(vec![url.to_string()])))
+        })
+        .build();
+    app.refresh_models().await.unwrap();
+    let mut terminal = test_terminal(120, 24);
+
+    assert_eq!(app.active_host_name(), Some("laptop"));
+    assert_eq!(app.models, ["llama3"]);
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("http://laptop"));
+
+    app.open_host_picker();
+    app.host_picker_next();
+    app.confirm_host_picker().await;
+    assert_eq!(app.active_host_name(), Some("gpu"));
+    assert_eq!(app.models, ["llama3", "big"]);
+    assert!(app.history.is_empty());
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].contains("Host: gpu"));
+
+    app.switch_host(0).await;
+    assert!(app.history.contains("http://laptop"));
+    let histories = app.all_model_histories();
+    assert!(histories["laptop/llama3"].contains("http://laptop"));
+    assert_eq!(histories["gpu/llama3"], "");
+}
+
+/// Tests that the thinking hint appears in the input title only after the
+/// configured delay without a first token.
+#[tokio::test]
+async fn test_thinking_hint_after_delay() {
+    let config = Config::parse("thinking_hint = \"hang on\"").unwrap();
+    let mut app = App::builder().config(config).models(vec!["mock".to_string()]).build();
+    let mut terminal = test_terminal(100, 24);
+    app.is_loading = true;
+
+    app.waiting_since = Some(Instant::now());
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(!buffer_lines(&terminal).iter().any(|l| l.contains("hang on")));
+
+    app.waiting_since = Some(Instant::now() - Duration::from_secs(11));
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal).iter().any(|l| l.contains("(hang on)")));
+
+    app.thinking_hint_after = Duration::ZERO;
+    assert_eq!(app.active_thinking_hint(), None);
+}
+
+/// Tests that a loaded model whose first token is late gets the no-tokens
+/// hint, and that a missing running-models list is ignored.
+#[tokio::test]
+async fn test_no_tokens_hint() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_running_models(vec!["mock".to_string()])
+        .with_response(vec!["one"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.model_was_loaded);
+    assert!(app.history.contains("AI [mock]: one"));
+    app.is_loading = true;
+    app.waiting_since = Instant::now().checked_sub(NO_TOKENS_AFTER);
+    assert_eq!(app.active_thinking_hint(), Some(NO_TOKENS_HINT));
+
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["ok"]);
+    let mut app = App::new_with_client(client).await;
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(!app.model_was_loaded);
+    assert!(app.history.contains("AI [mock]: ok"));
+}
+
+/// Tests that right after a restart a model that is already loaded, and
+/// was never queried by this session, gets no hint while its first token
+/// is on time.
+#[tokio::test]
+async fn test_no_tokens_hint_after_restart() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_running_models(vec!["mock".to_string()])
+        .with_response(vec!["one"]);
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+    assert!(app.last_activity.is_empty());
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.model_was_loaded);
+    app.is_loading = true;
+    app.thinking_hint_after = Duration::ZERO;
+    app.waiting_since = Some(Instant::now());
+    assert_eq!(app.active_thinking_hint(), None);
+}
+
+/// Tests that plain mode renders without box drawing, colors or a
+/// reversed cursor cell, and puts the terminal cursor in the input.
+#[test]
+fn test_plain_rendering() {
+    let mut app = App::builder()
+        .config(Config::parse("plain = true").unwrap())
+        .models(vec!["mock".to_string()])
+        .build();
+    app.history = "\nYOU: Code?\n\nAI [mock]: ### Here\n```rust\nfn main() {}\n```\n---\n\nYOU: Next\n".to_string();
+    app.input = "ab".to_string();
+    app.cursor_pos = 1;
+    let mut terminal = test_terminal(80, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+
+    let lines = buffer_lines(&terminal);
+    let screen = lines.join("\n");
+    for decoration in ['┌', '│', '└', '─', '●', '⠋'] {
+        assert!(!screen.contains(decoration), "{} in {:#?}", decoration, lines);
+    }
+    assert!(screen.contains("Code (rust):"), "{:#?}", lines);
+    assert!(screen.contains("End of code."));
+    assert!(lines[0].contains("LazyLlama"));
+    let buffer = terminal.backend().buffer();
+    assert!(buffer.content.iter().all(|cell| cell.modifier.is_empty() && cell.bg == Color::Reset));
+
+    let input_row = lines.iter().position(|l| l.contains("> Input")).unwrap() as u16 + 1;
+    let input_x = lines[input_row as usize].find("ab").unwrap() as u16;
+    let cursor = terminal.get_cursor_position().unwrap();
+    assert_eq!((cursor.x, cursor.y), (input_x + 1, input_row));
+}
+
+/// Tests that queued prompts are sent in order after the current one, and
+/// that a failed request stops the queue.
+#[tokio::test]
+async fn test_pending_prompts_sent_in_order() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["a"])
+        .with_response(vec!["b"])
+        .with_error("connection refused")
+        .with_response(vec!["d"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+
+    for prompt in ["B", "C", "D"] {
+        app.input = prompt.to_string();
+        app.queue_prompt();
+    }
+    assert!(app.input.is_empty());
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].contains("Queued: 3"));
+
+    app.input = "A".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    let prompts: Vec<_> = requests.lock().unwrap().iter().map(|r| r.prompt.clone()).collect();
+    assert_eq!(prompts, ["A", "B", "C"]);
+    assert!(app.history.contains("AI [mock]: b"));
+    // The prompt left after the failure goes back into the input.
+    assert!(app.pending_prompts.is_empty());
+    assert_eq!(app.input, "D");
+
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 4);
+    assert!(app.history.contains("AI [mock]: d"));
+}
+
+/// Tests that text typed while an answer streams is queued with Enter and
+/// sent after it, and that keys only the event loop handles are kept.
+#[tokio::test]
+async fn test_keys_queue_prompt_while_streaming() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["a"])
+        .with_response(vec!["b"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+    let mut typed = vec![
+        KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE),
+        KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+        KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+        KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE),
+        KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE),
+    ];
+    app.key_source = Some(Box::new(move || std::mem::take(&mut typed)));
+
+    app.input = "A".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    let prompts: Vec<_> = requests.lock().unwrap().iter().map(|r| r.prompt.clone()).collect();
+    assert_eq!(prompts, ["A", "hi"]);
+    assert!(app.history.contains("AI [mock]: b"));
+    assert!(app.input.is_empty());
+    assert_eq!(app.deferred_keys, [KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE)]);
+}
+
+/// Tests that `/clear`, confirmed while an answer streams, is refused
+/// instead of clearing the history under the answer.
+#[tokio::test]
+async fn test_clear_refused_while_streaming() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["a", "b"]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+    let mut typed: Vec<KeyEvent> = "/clear"
+        .chars()
+        .map(|c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+        .collect();
+    typed.push(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    typed.push(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    app.key_source = Some(Box::new(move || std::mem::take(&mut typed)));
+
+    app.input = "A".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    assert!(app.history.contains("AI [mock]: ab"));
+    assert!(app.last_failed_prompt.is_none());
+    assert_eq!(app.input, "/clear");
+    assert!(app.pending_prompts.is_empty());
+    assert_eq!(app.status_message.as_deref(), Some("/clear can be sent once the answer is complete"));
+}
+
+/// Tests that an input over the context limit turns the input box red and
+/// shows the estimate, without blocking the send.
+#[tokio::test]
+async fn test_context_limit_warning() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["ok"]);
+    let mut app = App::builder()
+        .client(client)
+        .config(Config::parse("context_limit = 2").unwrap())
+        .models(vec!["mock".to_string()])
+        .build();
+    let mut terminal = test_terminal(100, 24);
+
+    app.input = "A rather long prompt".to_string();
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    let title = lines.iter().find(|l| l.contains("> Input")).unwrap();
+    assert!(title.contains("~5 of 2 tokens"));
+    // The border cell right before the title.
+    let x = title.find(" > Input").map(|i| title[..i].chars().count()).unwrap() as u16 - 1;
+    let y = lines.iter().position(|l| l.contains("> Input")).unwrap() as u16;
+    assert_eq!(terminal.backend().buffer()[(x, y)].fg, Color::Red);
+
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("AI [mock]: ok"));
+}
+
+/// Tests that the background reload fills in the last saved conversation
+/// of each model without replacing one started in the meantime.
+#[tokio::test]
+async fn test_history_reload_after_startup() {
+    let dir = tempfile::TempDir::new().unwrap();
+    lazyllama::utils::archive_conversation_in(dir.path(), "a:1", "old a").unwrap();
+    lazyllama::utils::archive_conversation_in(dir.path(), "a:1", "newer a").unwrap();
+    lazyllama::utils::archive_conversation_in(dir.path(), "b", "old b").unwrap();
+    let mut app = App::builder()
+        .models(vec!["a:1".to_string(), "b".to_string()])
+        .data_dir(dir.path())
+        .build();
+    app.model_histories.insert("b".to_string(), "current b".to_string());
+
+    app.start_history_reload();
+    assert!(app.histories_loading);
+    let mut terminal = test_terminal(120, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].contains("loading previous conversations"));
+
+    while app.histories_loading {
+        app.poll_history_reload();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(app.history, "newer a");
+    assert_eq!(app.model_histories["a:1"], "newer a");
+    assert_eq!(app.model_histories["b"], "current b");
+    assert!(!app.poll_history_reload());
+}
+
+/// Tests that an adjusted temperature is sent with the following requests
+/// of that model only.
+#[tokio::test]
+async fn test_temperature_sent_with_request() {
+    let client = ScriptedClient::new(vec!["a".to_string(), "b".to_string()]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(80, 24);
+
+    app.raise_temperature();
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    app.select_next_model();
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].options.temperature, Some(0.9));
+    assert_eq!(requests[1].options.temperature, None);
+}
+
+/// Tests that a bookmark is marked on the right border of the history.
+#[tokio::test]
+async fn test_bookmark_marker_rendered() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    let mut terminal = test_terminal(80, 24);
+    app.history = (0..60).map(|i| format!("line {}\n", i)).collect();
+    app.autoscroll = false;
+    app.scroll = 0;
+    app.toggle_bookmark();
+
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let lines = buffer_lines(&terminal);
+    let first_row = lines.iter().position(|l| l.contains("Conversation History")).unwrap() + 1;
+    assert!(lines[first_row].trim_end().ends_with('◆'));
+    assert_eq!(lines.iter().filter(|l| l.contains('◆')).count(), 1);
+}
+
+/// Tests that the server version is stored, shown in debug mode and used
+/// to reject features the server is too old for.
+#[tokio::test]
+async fn test_server_version_gates_features() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_version("0.4.2");
+    let mut app = App::new_with_client(client).await;
+    assert_eq!(app.server_version.as_deref(), Some("0.4.2"));
+    assert!(app.require_server_version("Streaming", "0.4").is_ok());
+    let err = app.require_server_version("Chat API", "0.5.0").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Chat API needs Ollama 0.5.0 or newer, but the server runs 0.4.2"
+    );
+
+    app.debug_keys = true;
+    let mut terminal = test_terminal(200, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(buffer_lines(&terminal)[23].contains("| Ollama: 0.4.2 |"));
+
+    let unknown = App::new_with_client(ScriptedClient::new(vec!["mock".to_string()])).await;
+    assert_eq!(unknown.server_version, None);
+    assert!(unknown.require_server_version("Chat API", "0.5.0").is_ok());
+}
+
+/// Tests that `generate_into` streams chunks into the sink with the model's
+/// template applied and leaves the UI state alone.
+#[tokio::test]
+async fn test_generate_into_sink() {
+    let config = Config::parse("prompt_template = \"Briefly: {prompt}\"\n").unwrap();
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_response(vec!["Fo", "", "o"])
+        .with_error("model \"mock\" not found");
+    let requests = client.request_log();
+    let app = App::builder()
+        .client(client)
+        .config(config)
+        .models(vec!["mock".to_string()])
+        .build();
+
+    let mut chunks = Vec::new();
+    let answer = app
+        .generate_into("mock", "Hi", |chunk| chunks.push(chunk.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(answer, "Foo");
+    assert_eq!(chunks, ["Fo", "o"]);
+    assert_eq!(requests.lock().unwrap()[0].prompt, "Briefly: Hi");
+    assert!(app.history.is_empty());
+    assert!(!app.is_loading);
+
+    let err = app.generate_into("mock", "Hi", |_| {}).await.unwrap_err();
+    assert!(matches!(err, LazyLlamaError::ModelNotFound { .. }));
+}
+
+/// Tests that `/file` on the last input line attaches the file instead of
+/// sending, and that the input title names the attached file.
+#[tokio::test]
+async fn test_file_command_attaches_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    std::fs::write(&path, "pub fn answer() -> u32 {\n    42\n}\n").unwrap();
+    let client = ScriptedClient::new(vec!["mock".to_string()]);
+    let requests = client.request_log();
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+
+    app.input = format!("Why 42?\n/file {}", path.display());
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(requests.lock().unwrap().is_empty());
+    assert_eq!(
+        app.input,
+        format!("Why 42?\nFile: {}\n```rust\npub fn answer() -> u32 {{\n    42\n}}\n```\n", path.display())
+    );
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let title = buffer_lines(&terminal).into_iter().find(|l| l.contains("> Input")).unwrap();
+    assert!(title.contains("📎"), "{}", title);
+
+    app.input.push_str("/file /does/not/exist");
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.status_message.as_deref().unwrap().starts_with("Cannot attach /does/not/exist"));
+    assert!(app.input.ends_with("```\n/file /does/not/exist"));
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+/// Tests that in debug mode `F6` echoes the templated request into the
+/// transcript, and that the echo is left out of saved histories.
+#[tokio::test]
+async fn test_request_debug_echo() {
+    let config = Config::parse("prompt_template = \"Briefly: {prompt}\"\n").unwrap();
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["Ok"]);
+    let mut app = App::builder()
+        .client(client)
+        .config(config.clone())
+        .models(vec!["mock".to_string()])
+        .build();
+    let mut terminal = test_terminal(80, 24);
+
+    app.toggle_echo_requests();
+    assert!(!app.echo_requests);
+
+    let mut app = App::builder()
+        .client(ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["Ok"]))
+        .config(config)
+        .models(vec!["mock".to_string()])
+        .debug_keys(true)
+        .build();
+    app.toggle_echo_requests();
+    assert!(app.echo_requests);
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    assert!(app.history.starts_with(
+        "┆ [request debug] model: mock, temperature: server default\n┆ Briefly: Hi\n\nYOU: Hi\n"
+    ));
+    assert!(lazyllama::ui::strip_request_debug(&app.history).starts_with("\nYOU: Hi\n\nAI [mock]: Ok"));
+    assert!(!app.all_model_histories()["mock"].contains("[request debug]"));
+    assert_eq!(lazyllama::ui::exchange_title(&app.history), "Hi");
+}
+
+/// Tests that with `resume_interrupted` a broken answer stream is re-issued
+/// with the partial answer and continued, and that the attempts are bounded.
+#[tokio::test]
+async fn test_resume_interrupted_stream() {
+    let client = ScriptedClient::new(vec!["mock".to_string()])
+        .with_interrupted_response(vec!["1, 2,"], "connection reset")
+        .with_response(vec![" 3"])
+        .with_interrupted_response(vec!["x"], "connection reset")
+        .with_error("connection refused")
+        .with_error("connection refused");
+    let requests = client.request_log();
+    let mut app = App::builder()
+        .client(client)
+        .config(Config::parse("resume_interrupted = true").unwrap())
+        .models(vec!["mock".to_string()])
+        .build();
+    let mut terminal = test_terminal(100, 24);
+
+    app.input = "Count to 3".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("AI [mock]: 1, 2, [reconnected]  3\n"));
+    assert!(!app.history.contains("Error"));
+    let resumed = requests.lock().unwrap()[1].prompt.clone();
+    assert!(resumed.starts_with("Count to 3\n\n"));
+    assert!(resumed.ends_with("\n\n1, 2,"));
+
+    app.input = "Again".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 5);
+    assert!(app.history.contains("AI [mock]: x [reconnected] \n⚠ Error: connection refused"));
+    assert_eq!(app.last_failed_prompt.as_deref(), Some("Again"));
+}
+
+/// Tests that every answer gets a copy button at its end and that a click
+/// on it is routed to that answer.
+#[test]
+fn test_copy_buttons_hit_their_answer() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    app.history = "\nYOU: One\n\nAI [mock]: First\n---\n\nYOU: Two\n\nAI [mock]: Second\n---\n\nYOU: Three\n"
+        .to_string();
+    let mut terminal = test_terminal(100, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+
+    let lines = buffer_lines(&terminal);
+    let indices: Vec<usize> = app.copy_regions.iter().map(|&(_, i)| i).collect();
+    assert_eq!(indices, [0, 1]);
+    for (rect, _) in &app.copy_regions {
+        assert!(lines[rect.y as usize].ends_with("[copy]│"), "{:#?}", lines);
+    }
+    let second = lines.iter().position(|l| l.contains("Second")).unwrap() as u16;
+    assert!(app.copy_regions[1].0.y > second);
+
+    let (rect, _) = app.copy_regions[1];
+    assert!(!app.click(rect.x - 1, rect.y));
+    assert!(app.status_message.is_none());
+    assert!(app.click(rect.x + 2, rect.y));
+    let status = app.status_message.clone().unwrap();
+    assert!(status == "Copied answer to clipboard" || status.starts_with("Copy failed"), "{}", status);
+}
+
+/// Tests that `/goto N` scrolls turn N's first line to the top of the
+/// history, and that an out-of-range turn keeps the command for correction.
+#[tokio::test]
+async fn test_goto_turn_command() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    let long_answer = "line\n".repeat(20);
+    app.history = ["One", "Two", "Three"]
+        .iter()
+        .map(|p| format!("\nYOU: {}\n\nAI [mock]: {}---\n", p, long_answer))
+        .collect();
+    let mut terminal = test_terminal(100, 24);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(app.autoscroll);
+
+    app.input = "/goto 9".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(app.input, "/goto 9");
+    assert_eq!(app.status_message.as_deref(), Some("No turn 9, turns are 1 to 3"));
+
+    app.input = "/goto 2".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.input.is_empty());
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    assert!(!app.autoscroll);
+    let lines = buffer_lines(&terminal);
+    let title = lines.iter().position(|l| l.contains("Conversation History")).unwrap();
+    // The exchange starts with a blank line, then its prompt.
+    assert!(lines[title + 2].contains("YOU: Two"), "{:#?}", lines);
+}
+
+/// Tests that `/pipe` replaces its line with the processed last answer
+/// without sending anything, and keeps the line when the command fails.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_pipe_command() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]);
+    let requests = client.request_log();
+    let mut app = App::builder()
+        .models(vec!["mock".to_string()])
+        .client(client)
+        .build();
+    app.history = "\nYOU: Hi\n\nAI [mock]: fn main() {}\n---\n".to_string();
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Tidy this:\n/pipe tr a-z A-Z".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(app.input, "Tidy this:\nFN MAIN() {}");
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+
+    app.input = "/pipe exit 1".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(app.input, "/pipe exit 1");
+    assert!(app.status_message.as_deref().unwrap().starts_with("Pipe failed"));
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+/// Tests that `/clear` waits for confirmation when it is listed in
+/// `confirm_commands`, runs when sent again, and runs at once otherwise.
+#[tokio::test]
+async fn test_clear_command_confirmation() {
+    let client = ScriptedClient::new(vec!["mock".to_string()]);
+    let requests = client.request_log();
+    let mut app = App::builder()
+        .models(vec!["mock".to_string()])
+        .client(client)
+        .build();
+    let history = "\nYOU: Hi\n\nAI [mock]: Hello\n---\n".to_string();
+    app.history = history.clone();
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "/clear".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(app.history, history);
+    assert_eq!(app.input, "/clear");
+    assert_eq!(app.pending_command.as_deref(), Some("/clear"));
+    assert_eq!(app.status_message.as_deref(), Some("Send /clear again to confirm"));
+
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.is_empty());
+    assert!(app.input.is_empty());
+    assert_eq!(app.pending_command, None);
+
+    app.history = history.clone();
+    app.confirm_commands.clear();
+    app.input = "/clear".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.is_empty());
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+/// Tests that configured answer caps are sent with the request, per model
+/// before the global one, and that an answer reaching its cap is marked.
+#[tokio::test]
+async fn test_num_predict_sent_and_marked() {
+    let timings = GenerationTimings {
+        eval: Some(Duration::from_millis(500)),
+        eval_count: Some(8),
+        ..GenerationTimings::default()
+    };
+    let client = ScriptedClient::new(vec!["small".to_string(), "big".to_string()])
+        .with_timed_response(vec!["cut"], timings)
+        .with_response(vec!["full"]);
+    let requests = client.request_log();
+    let config = Config::parse("num_predict = 100\n[num_predicts]\nsmall = 8\n").unwrap();
+    let mut app = App::builder()
+        .client(client)
+        .config(config)
+        .models(vec!["small".to_string(), "big".to_string()])
+        .build();
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Q".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(app.history.contains("@ 16 tok/s [truncated at 8 tokens]\n"), "{}", app.history);
+
+    app.select_next_model();
+    app.input = "Q".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert!(!app.history.contains("truncated"));
+
+    app.lower_num_predict();
+    assert_eq!(app.num_predict_for("big"), Some(64));
+    app.raise_num_predict();
+    assert_eq!(app.num_predict_for("big"), Some(128));
+
+    let caps: Vec<_> = requests.lock().unwrap().iter().map(|r| r.options.num_predict).collect();
+    assert_eq!(caps, [Some(8), Some(100)]);
+}
+
+/// With `AutoscrollMode::AnswerStart` a long answer is shown from its
+/// first line, while the default mode shows its tail.
+#[test]
+fn test_autoscroll_to_answer_start() {
+    let answer: Vec<String> = (1..=40).map(|i| format!("line {}", i)).collect();
+    let history = format!("\nYOU: Tell me more\n\nAI [mock]: {}\n", answer.join("\n"));
+    for (mode, first, last) in [
+        (AutoscrollMode::Bottom, false, true),
+        (AutoscrollMode::AnswerStart, true, false),
+    ] {
+        let mut app = App::builder().models(vec!["mock".to_string()]).build();
+        app.autoscroll_mode = mode;
+        app.history = history.clone();
+        app.is_loading = true;
+
+        let screen = lazyllama::testing::render_snapshot(&mut app, 80, 24);
+        assert_eq!(screen.contains("AI:  line 1 "), first, "{:?}\n{}", mode, screen);
+        assert_eq!(screen.contains("line 40"), last, "{:?}\n{}", mode, screen);
+        assert!(app.autoscroll);
+    }
+
+    // A short answer still sits at the bottom like before.
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    app.autoscroll_mode = AutoscrollMode::AnswerStart;
+    app.history = "\nYOU: Hi\n\nAI [mock]: Hello\n".to_string();
+    lazyllama::testing::render_snapshot(&mut app, 80, 24);
+    assert_eq!(app.scroll, 0);
+}
+
+/// Tests that a box-drawn diagram keeps its indentation and spacing while
+/// the prose around it still wraps.
+#[test]
+fn test_render_preformatted_diagram() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    app.history = "\nYOU: Draw it\n\nAI [mock]: Here is how the service is laid out, in a sentence long enough to wrap.\n\
+        +--------+      +--------+\n\
+        | client | ---> | server |\n\
+        +--------+      +--------+\n\
+        \u{20}   ┌──────────┐\n\
+        \u{20}   │  cache   │\n\
+        \u{20}   └──────────┘\n\
+        The client calls the server.\n---\n"
+        .to_string();
+    app.save_current_model_buffers();
+
+    let screen = lazyllama::testing::render_snapshot(&mut app, 80, 24).replace('\u{a0}', " ");
+    let rows: Vec<&str> = screen
+        .lines()
+        .filter_map(|row| row.split_once("││").and_then(|(_, pane)| pane.strip_suffix('│')))
+        .map(str::trim_end)
+        .collect();
+    let row = |needle: &str| rows.iter().position(|r| r.contains(needle)).unwrap();
+    assert_eq!(rows[row("client")], "| client | ---> | server |");
+    assert_eq!(rows[row("cache")], "    │  cache   │");
+    assert_eq!(rows[row("cache") - 1], "    ┌──────────┐");
+    assert!(row("long enough") > row("Here is"), "{}", screen);
+}
+
+/// Tests that the last request is kept and turned into a `curl` command
+/// with the endpoint of the active host and the request's JSON body.
+#[tokio::test]
+async fn test_last_request_as_curl() {
+    let config = Config {
+        hosts: vec![lazyllama::config::Host {
+            name: "gpu".to_string(),
+            url: "http://gpu-box:11434".to_string(),
+        }],
+        ..Config::default()
+    };
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["Sure"]);
+    let mut app = App::builder()
+        .models(vec!["mock".to_string()])
+        .client(client)
+        .config(config)
+        .build();
+    let mut terminal = test_terminal(80, 24);
+    assert!(app.last_request.is_none());
+    app.copy_request_as_curl();
+    assert_eq!(app.status_message.as_deref(), Some("No request to copy"));
+
+    app.raise_temperature();
+    app.input = "Say 'hi'".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+
+    let request = app.last_request.clone().unwrap();
+    let curl = request.curl_command(&app.hosts[app.active_host].url);
+    assert!(curl.starts_with("curl -N http://gpu-box:11434/api/generate -d '"), "{}", curl);
+    let body = curl["curl -N http://gpu-box:11434/api/generate -d ".len()..].replace("'\\''", "'");
+    let body: serde_json::Value = serde_json::from_str(body.trim_matches('\'')).unwrap();
+    assert_eq!(body["model"], "mock");
+    assert_eq!(body["prompt"], "Say 'hi'");
+    assert!((body["options"]["temperature"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+}
+
+/// Tests that a prompt the selected model fails on is answered by the
+/// first working fallback, with a note, and that every fallback is tried
+/// only once.
+#[tokio::test]
+async fn test_fallback_models() {
+    let config = Config {
+        fallback_models: vec!["big".to_string(), "gone".to_string(), "small".to_string()],
+        ..Config::default()
+    };
+    let client = ScriptedClient::new(vec!["big".to_string(), "small".to_string()])
+        .with_error("model \"big\" not found, try pulling it first")
+        .with_error("model \"gone\" not found, try pulling it first")
+        .with_response(vec!["Hello"])
+        .with_error("connection refused")
+        .with_error("connection refused")
+        .with_error("connection refused");
+    let requests = client.request_log();
+    let mut app = App::builder()
+        .models(vec!["big".to_string(), "small".to_string()])
+        .client(client)
+        .config(config)
+        .build();
+    let mut terminal = test_terminal(80, 24);
+
+    app.input = "Hi".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    let models: Vec<String> = requests.lock().unwrap().iter().map(|r| r.model.clone()).collect();
+    assert_eq!(models, ["big", "gone", "small"]);
+    assert!(
+        app.history.contains("AI [small]: [big failed] [gone failed] Hello"),
+        "{}",
+        app.history
+    );
+    // The label names the model that answered, not the one asked first.
+    assert!(!app.history.contains("AI [big]"));
+    assert!(app.last_error.is_none());
+
+    requests.lock().unwrap().clear();
+    app.input = "Again".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    assert!(app.history.contains(ERROR_PREFIX));
+    assert_eq!(app.last_failed_prompt.as_deref(), Some("Again"));
+}
+
+/// Tests that the code block template puts the cursor on its inner empty
+/// line, gives the fences lines of their own and remembers the language
+/// of the last code block sent.
+#[tokio::test]
+async fn test_insert_code_block() {
+    let config = Config {
+        code_language: "rust".to_string(),
+        ..Config::default()
+    };
+    let client = ScriptedClient::new(vec!["mock".to_string()]).with_response(vec!["Ok"]);
+    let mut app = App::builder()
+        .models(vec!["mock".to_string()])
+        .client(client)
+        .config(config)
+        .build();
+    let mut terminal = test_terminal(80, 24);
+
+    app.insert_str("Why does this fail? Thanks");
+    app.cursor_pos = "Why does this fail?".len();
+    app.insert_code_block();
+    assert_eq!(app.input, "Why does this fail?\n```rust\n\n```\n Thanks");
+    assert_eq!(lazyllama::ui::input_cursor_line(&app.input, app.cursor_pos), 2);
+    app.insert_str("fn main() {}");
+    assert_eq!(app.input, "Why does this fail?\n```rust\nfn main() {}\n```\n Thanks");
+
+    app.input = "Check:\n```python\nprint(1)\n```".to_string();
+    app.send_query(&mut terminal).await.unwrap();
+    app.insert_code_block();
+    assert_eq!(app.input, "```python\n\n```");
+    assert_eq!(app.cursor_pos, "```python\n".len());
+}
+
+/// Anchors the screen layout of a simple conversation at 80x24, so visual
+/// regressions show up as a snapshot diff.
+#[test]
+fn test_render_snapshot_simple_conversation() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    app.history = "\nYOU: Hello\n\nAI [mock]: Hi! How can I help?\n---\n".to_string();
+    app.input = "Next".to_string();
+    app.cursor_pos = 4;
+    app.save_current_model_buffers();
+
+    let screen = lazyllama::testing::render_snapshot(&mut app, 80, 24);
+    // The blank after 📄 is the second cell the wide emoji covers.
+    let expected = [
+        r"",
+        r"             | |    __ _  ______  __| |    | | __ _ _ __ ___   __ _",
+        r"             | |   / _` ||_  /\ \/ /| |    | |/ _` | '_ ` _ \ / _` |",
+        r"             | |__| (_| | / /  \  / | |___ | | (_| | | | | | | (_| |",
+        r"             |_____\__,_|/___| /_/  |_____||_|\__,_|_| |_| |_|\__,_|",
+        r"",
+        r"",
+        r"┌ Models (1) ──────┐┌ Conversation History [AUTOSCROLL]  ──────────────────────┐",
+        r"│>> mock [📄 ]      ││                                                          │",
+        r"│                  ││YOU: Hello                                                │",
+        r"│                  ││                                                          │",
+        r"│                  ││AI:  Hi! How can I help?                            [copy]│",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  ││                                                          │",
+        r"│                  │└──────────────────────────────────────────────────────────┘",
+        r"│                  │┌ > Input ─────────────────────────────────────────────────┐",
+        r"│                  ││Next                                                      │",
+        r"└──────────────────┘└──────────────────────────────────────────────────────────┘",
+        r" F1: Help | C-q: Quit | C-n: New | C-c: Clear | C-s: AutoScroll | C-y/C-k: Copy",
+    ]
+    .join("\n");
+    assert_eq!(screen, expected, "\n{}", screen);
+}
+
+/// Tests that the model info popup shows the fetched details, caches them,
+/// and shows an error line when the fetch fails.
+#[tokio::test]
+async fn test_model_info_popup() {
+    let info = ModelInfo {
+        family: Some("llama".to_string()),
+        parameter_size: Some("8.0B".to_string()),
+        quantization: Some("Q4_0".to_string()),
+        context_length: Some(8192),
+        license: Some("META LLAMA 3 COMMUNITY LICENSE AGREEMENT".to_string()),
+    };
+    let client = ScriptedClient::new(vec!["llama3".to_string(), "other".to_string()])
+        .with_model_info("llama3", info.clone());
+    let mut app = App::new_with_client(client).await;
+    let mut terminal = test_terminal(100, 24);
+
+    app.open_model_info().await;
+    assert_eq!(app.model_info_cache.get("llama3"), Some(&info));
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let screen = buffer_lines(&terminal).join("\n");
+    assert!(screen.contains("Quantization: Q4_0"), "{}", screen);
+    assert!(screen.contains("Context length: 8192 tokens"));
+    app.close_model_info();
+
+    app.select_next_model();
+    app.open_model_info().await;
+    assert!(!app.model_info_cache.contains_key("other"));
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let screen = buffer_lines(&terminal).join("\n");
+    assert!(screen.contains("⚠ Could not fetch the details: model other not found"), "{}", screen);
+}
+
+/// Tests that a terminal below the minimum size shows only the size notice,
+/// and that focus mode, which needs less room, still renders there.
+#[test]
+fn test_terminal_too_small_notice() {
+    let mut app = App::builder().models(vec!["mock".to_string()]).build();
+    app.history = "\nYOU: Hi\n\nAI [mock]: Hello\n---\n".to_string();
+    assert_eq!(lazyllama::ui::min_terminal_size(&app), (40, 14));
+
+    for (width, height) in [(39, 24), (80, 13), (10, 2)] {
+        let mut terminal = test_terminal(width, height);
+        terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+        let screen = buffer_lines(&terminal).join("\n");
+        assert!(screen.replace('\n', " ").contains("Terminal"), "{}", screen);
+        if width >= 39 {
+            assert!(screen.contains("Terminal too small (need ≥ 40×14)"), "{}", screen);
+        }
+        assert!(!screen.contains("Conversation History") && !screen.contains('┌'), "{}", screen);
+        assert!(app.copy_regions.is_empty());
+    }
+
+    app.focus_mode = true;
+    let mut terminal = test_terminal(80, 13);
+    terminal.draw(|f| lazyllama::ui::ui(f, &mut app)).unwrap();
+    let screen = buffer_lines(&terminal).join("\n");
+    assert!(screen.contains("Hello") && !screen.contains("Terminal too small"), "{}", screen);
+}
@@ -9,8 +9,14 @@
 //! - `test_ui`: User interface rendering, text parsing, and display formatting
 //! - `test_utils`: File system operations, logging, and utility functions
 //! - `test_main`: Main application entry point and event loop testing
+//! - `test_clipboard`: Copy helpers and clipboard escape sequences
+//! - `test_config`: Configuration file parsing and snippet hints
+//! - `test_diff`: Word diff for regenerated answers
 
 pub mod test_app;
 pub mod test_ui;
 pub mod test_utils;
-pub mod test_main;
\ No newline at end of file
+pub mod test_main;
+pub mod test_clipboard;
+pub mod test_config;
+pub mod test_diff;
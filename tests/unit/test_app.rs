@@ -19,11 +19,16 @@
 //! - Validates state consistency after operations
 //! - Ensures proper handling of edge cases and boundary conditions
 
+use chrono::{Local, TimeZone};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
-use ratatui::widgets::ListState;
-use ollama_rs::Ollama;
-use lazyllama::app::App;
+use lazyllama::app::{
+    estimate_tokens, explain_prompt, filter_models, App, PollMode, Role, ACTIVE_POLL_INTERVAL,
+    DEFAULT_POLL_INTERVAL, DEFAULT_TEMPERATURE, EXPLAIN_MAX_CHARS, IDLE_POLL_INTERVAL,
+    MAX_TEMPERATURE, MIN_TEMPERATURE, SHARED_DRAFT_KEY,
+};
+use lazyllama::config::{CodeBlockStyle, Config, Snippet};
+use lazyllama::utils::Draft;
 
 
 /// Creates a test App instance without Ollama API calls
@@ -38,7 +43,7 @@ use lazyllama::app::App;
 /// - Default selected model (index 0)
 /// - Empty input and history buffers
 /// - Default cursor and scroll positions
-/// - Mock Ollama client (no network calls)
+/// - Default Ollama client that is never contacted
 /// - Current timestamp for timing-sensitive operations
 /// 
 /// # Usage
@@ -49,31 +54,9 @@ use lazyllama::app::App;
 /// assert_eq!(app.input, "H");
 /// ```
 fn create_test_app() -> App {
-    App {
-        models: vec!["test_model_1".to_string(), "test_model_2".to_string()],
-        list_state: {
-            let mut state = ListState::default();
-            state.select(Some(0));
-            state
-        },
-        input: String::new(),
-        cursor_pos: 0,
-        history: String::new(),
-        model_inputs: HashMap::new(),
-        model_cursors: HashMap::new(),
-        model_histories: HashMap::new(),
-        model_scrolls: HashMap::new(),
-        scroll: 0,
-        autoscroll: true,
-        is_loading: false,
-        ollama: Ollama::default(),
-        start_time: Instant::now(),
-        last_cursor_blink: Instant::now(),
-        cursor_visible: true,
-        debug_keys: false,
-        debug_last_key: None,
-        render_count: 0,
-    }
+    App::builder()
+        .models(vec!["test_model_1".to_string(), "test_model_2".to_string()])
+        .build()
 }
 
 /// Tests character insertion functionality in the input buffer.
@@ -247,6 +230,26 @@ fn test_move_cursor_home_end() {
     assert_eq!(app.cursor_pos, 11);
 }
 
+/// Tests that Home/End go to the start/end of the line first and then to
+/// the whole input.
+#[test]
+fn test_home_end_by_line() {
+    let mut app = create_test_app();
+    app.input = "first\nabcdefghijklmnop\nlast".to_string();
+
+    app.cursor_pos = 6 + 13;
+    app.move_cursor_home();
+    assert_eq!(app.cursor_pos, 6);
+    app.move_cursor_home();
+    assert_eq!(app.cursor_pos, 0);
+
+    app.cursor_pos = 6 + 3;
+    app.move_cursor_end();
+    assert_eq!(app.cursor_pos, 6 + 16);
+    app.move_cursor_end();
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+}
+
 #[test]
 fn test_word_navigation() {
     let mut app = create_test_app();
@@ -467,4 +470,874 @@ fn test_unicode_text_editing() {
     app.delete_forward();
     assert_eq!(app.input, "🦀");
     assert_eq!(app.cursor_pos, 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_poll_mode_parse() {
+    assert_eq!(PollMode::parse("adaptive"), Some(PollMode::Adaptive));
+    assert_eq!(PollMode::parse(" Adaptive "), Some(PollMode::Adaptive));
+    assert_eq!(PollMode::parse("250"), Some(PollMode::Fixed(Duration::from_millis(250))));
+    assert_eq!(PollMode::parse("0"), None);
+    assert_eq!(PollMode::parse("fast"), None);
+    assert_eq!(PollMode::default(), PollMode::Fixed(DEFAULT_POLL_INTERVAL));
+}
+
+/// Tests the poll timeout chosen for the event loop.
+///
+/// Fixed mode always returns its interval; adaptive mode polls quickly while
+/// loading and otherwise waits for the next cursor blink, capped at the idle
+/// interval.
+#[test]
+fn test_poll_interval() {
+    let mut app = create_test_app();
+    assert_eq!(app.poll_interval(), DEFAULT_POLL_INTERVAL);
+
+    app.poll_mode = PollMode::Adaptive;
+    app.is_loading = true;
+    assert_eq!(app.poll_interval(), ACTIVE_POLL_INTERVAL);
+
+    app.is_loading = false;
+    app.last_cursor_blink = Instant::now();
+    assert_eq!(app.poll_interval(), IDLE_POLL_INTERVAL);
+
+    // Blink due in less than the idle cap: wake up in time for it
+    app.last_cursor_blink = Instant::now() - Duration::from_millis(400);
+    assert!(app.poll_interval() <= Duration::from_millis(100));
+
+    // Blink overdue: poll without waiting
+    app.last_cursor_blink = Instant::now() - Duration::from_millis(600);
+    assert_eq!(app.poll_interval(), Duration::ZERO);
+}
+
+/// Tests that quiet mode keeps the cursor still and stops the event loop
+/// from waking up for the spinner or blink.
+#[test]
+fn test_quiet_disables_animations() {
+    let mut app = create_test_app();
+    app.quiet = true;
+    app.poll_mode = PollMode::Adaptive;
+    app.cursor_visible = false;
+    app.last_cursor_blink = Instant::now() - Duration::from_millis(600);
+    assert!(!app.update_cursor_blink());
+    assert!(app.cursor_visible);
+    assert_eq!(app.poll_interval(), IDLE_POLL_INTERVAL);
+
+    app.is_loading = true;
+    assert_eq!(app.poll_interval(), IDLE_POLL_INTERVAL);
+}
+
+/// Tests that the status clock only asks for redraws when enabled, and
+/// that it never makes adaptive polling slower than the idle interval.
+#[test]
+fn test_status_clock_ticks() {
+    let mut app = create_test_app();
+    assert!(!app.update_status_clock());
+
+    app.status_clock = true;
+    assert!(app.update_status_clock());
+    app.clock_second -= 1;
+    assert!(app.update_status_clock());
+
+    app.status_clock = false;
+    app.status_session = true;
+    app.clock_second -= 1;
+    assert!(app.update_status_clock());
+
+    app.poll_mode = PollMode::Adaptive;
+    app.last_cursor_blink = Instant::now();
+    assert!(app.poll_interval() <= IDLE_POLL_INTERVAL);
+}
+
+/// Tests most-recently-used ordering of models.
+///
+/// The selected model comes first, followed by used models newest first;
+/// models that were never used are omitted.
+#[test]
+fn test_mru_order() {
+    let mut app = create_test_app();
+    app.models = vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()];
+    app.list_state.select(Some(0));
+    let now = Instant::now();
+    app.last_activity.insert("b".to_string(), now - Duration::from_secs(60));
+    app.last_activity.insert("c".to_string(), now - Duration::from_secs(5));
+    app.last_activity.insert("a".to_string(), now);
+
+    assert_eq!(app.mru_order(), vec!["a", "c", "b"]);
+}
+
+#[test]
+fn test_mru_switcher_cycle_and_confirm() {
+    let mut app = create_test_app();
+    app.models = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    app.list_state.select(Some(0));
+    let now = Instant::now();
+    app.last_activity.insert("a".to_string(), now);
+    app.last_activity.insert("b".to_string(), now - Duration::from_secs(10));
+    app.last_activity.insert("c".to_string(), now - Duration::from_secs(5));
+
+    // Opening highlights the previously used model
+    app.cycle_mru_switcher();
+    assert_eq!(app.mru_selection, Some(1));
+    app.cycle_mru_switcher();
+    assert_eq!(app.mru_selection, Some(2));
+    app.cycle_mru_switcher();
+    assert_eq!(app.mru_selection, Some(0));
+    app.cycle_mru_switcher_back();
+    assert_eq!(app.mru_selection, Some(2));
+
+    app.confirm_mru_switcher();
+    assert_eq!(app.mru_selection, None);
+    assert_eq!(app.list_state.selected(), Some(1)); // "b"
+}
+
+#[test]
+fn test_mru_switcher_needs_history() {
+    let mut app = create_test_app();
+    app.cycle_mru_switcher();
+    assert_eq!(app.mru_selection, None);
+    assert!(app.status_message.is_some());
+
+    app.last_activity.insert("test_model_2".to_string(), Instant::now());
+    app.cycle_mru_switcher();
+    app.cancel_mru_switcher();
+    assert_eq!(app.mru_selection, None);
+    assert_eq!(app.list_state.selected(), Some(0));
+}
+
+#[test]
+fn test_select_model_by_name() {
+    let mut app = create_test_app();
+    app.input = "draft".to_string();
+    assert!(app.select_model("test_model_2"));
+    assert_eq!(app.list_state.selected(), Some(1));
+    assert_eq!(app.model_inputs.get("test_model_1"), Some(&"draft".to_string()));
+    assert!(!app.select_model("missing"));
+    assert_eq!(app.list_state.selected(), Some(1));
+}
+
+#[test]
+fn test_insert_str_at_cursor() {
+    let mut app = create_test_app();
+    app.input = "Hello world".to_string();
+    app.cursor_pos = 6;
+    app.insert_str("dear ");
+    assert_eq!(app.input, "Hello dear world");
+    assert_eq!(app.cursor_pos, 11);
+}
+
+#[test]
+fn test_insert_str_multibyte() {
+    let mut app = create_test_app();
+    app.input = "äö".to_string();
+    app.cursor_pos = 1;
+    app.insert_str("ü€");
+    assert_eq!(app.input, "äü€ö");
+    assert_eq!(app.cursor_pos, 3);
+}
+
+/// Tests that pasted or typed carriage returns become line breaks and the
+/// cursor counts the normalized text.
+#[test]
+fn test_insert_normalizes_line_endings() {
+    let mut app = create_test_app();
+    app.insert_str("line1\r\nline2\r");
+    assert_eq!(app.input, "line1\nline2\n");
+    assert_eq!(app.cursor_pos, 12);
+    app.insert_char('\r');
+    assert_eq!(app.input, "line1\nline2\n\n");
+    assert_eq!(app.cursor_pos, 13);
+}
+
+/// Tests that the fuzzy filter ranks the best match first, that substring
+/// matching is stricter, and that a query without matches lists nothing.
+#[test]
+fn test_filter_models() {
+    let models: Vec<String> = ["llama3:8b", "codellama:7b", "mistral:7b", "codellama:13b"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    assert_eq!(filter_models(&models, "cl13", true).first(), Some(&3));
+    assert_eq!(filter_models(&models, "", true), [0, 1, 2, 3]);
+    assert!(filter_models(&models, "zzz", true).is_empty());
+    assert!(filter_models(&models, "cl13", false).is_empty());
+    assert_eq!(filter_models(&models, "LLAMA", false), [0, 1, 3]);
+}
+
+/// Tests typing into the model filter, switching to the highlighted match
+/// and keeping the filter open when nothing matches.
+#[test]
+fn test_model_filter_selects_match() {
+    let mut app = App::builder()
+        .models(vec!["llama3:8b".to_string(), "codellama:13b".to_string()])
+        .build();
+    app.open_model_filter();
+    assert_eq!(app.model_filter.as_ref().unwrap().filtered_indices, [0, 1]);
+    for c in "xyz".chars() {
+        app.model_filter_insert(c);
+    }
+    assert!(app.model_filter.as_ref().unwrap().filtered_indices.is_empty());
+    app.model_filter_next();
+    app.confirm_model_filter();
+    assert!(app.model_filter.is_some());
+
+    for _ in 0..3 {
+        app.model_filter_backspace();
+    }
+    for c in "cl13".chars() {
+        app.model_filter_insert(c);
+    }
+    app.confirm_model_filter();
+    assert!(app.model_filter.is_none());
+    assert_eq!(app.list_state.selected(), Some(1));
+}
+
+/// Tests that `Alt+N` snippets are inserted at the cursor and that empty
+/// slots only produce a status message.
+#[test]
+fn test_insert_snippet() {
+    let mut app = create_test_app();
+    app.snippets = vec![Snippet {
+        name: "Summarize".to_string(),
+        text: "Summarize this: ".to_string(),
+    }];
+    app.input = "text".to_string();
+    app.cursor_pos = 0;
+
+    app.insert_snippet(0);
+    assert_eq!(app.input, "Summarize this: text");
+    assert_eq!(app.cursor_pos, 16);
+    assert!(app.status_message.is_none());
+
+    app.insert_snippet(1);
+    assert_eq!(app.input, "Summarize this: text");
+    assert_eq!(app.status_message.as_deref(), Some("No snippet on Alt+2"));
+}
+
+/// Tests that `Ctrl+D` inserts the timestamp at the cursor and moves the
+/// cursor past it, and that an invalid format inserts nothing.
+#[test]
+fn test_insert_timestamp() {
+    let mut app = create_test_app();
+    let time = Local.with_ymd_and_hms(2026, 3, 14, 9, 26, 53).unwrap();
+    app.input = "Meeting at ü".to_string();
+    app.cursor_pos = 11;
+
+    app.insert_timestamp_at(time);
+    assert_eq!(app.input, "Meeting at 2026-03-14 09:26ü");
+    assert_eq!(app.cursor_pos, 27);
+
+    app.timestamp_format = "%H:%M:%S %Q".to_string();
+    app.insert_timestamp_at(time);
+    assert_eq!(app.input, "Meeting at 2026-03-14 09:26ü");
+    assert_eq!(app.cursor_pos, 27);
+    assert!(app.status_message.unwrap().starts_with("Invalid timestamp_format"));
+}
+
+/// Tests that `Ctrl+E` quotes the highlighted exchange's answer in an
+/// "Explain this:" prompt at the cursor.
+#[test]
+fn test_explain_focused_exchange() {
+    let mut app = create_test_app();
+    app.history = "\nYOU: Q1\n\nAI [m]: First\nline\n⏱ 1.0s\n---\n\
+                   \nYOU: Q2\n\nAI [m]: Second\n---\n"
+        .to_string();
+
+    app.explain_focused_exchange();
+    assert!(app.input.is_empty());
+    assert!(app.status_message.take().unwrap().contains("Alt+"));
+
+    app.focus_previous_exchange();
+    app.focus_previous_exchange();
+    app.explain_focused_exchange();
+    assert_eq!(app.input, "Explain this:\n> First\n> line\n");
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+
+    let long = "word ".repeat(EXPLAIN_MAX_CHARS);
+    let prompt = explain_prompt(&long, EXPLAIN_MAX_CHARS);
+    assert!(prompt.ends_with("word …\n"));
+    assert!(prompt.chars().count() < EXPLAIN_MAX_CHARS + 20);
+}
+
+/// Tests that quitting asks first only with `confirm_quit` set and
+/// conversations above the threshold, and that the answer closes the
+/// confirmation.
+#[test]
+fn test_request_quit_confirmation() {
+    let mut app = create_test_app();
+    app.confirm_quit_after = 10;
+    app.history = "YOU: a long enough conversation".to_string();
+    assert!(app.request_quit());
+
+    app.confirm_quit = true;
+    app.ephemeral = true;
+    assert!(!app.request_quit());
+    assert_eq!(app.quit_confirmation.as_deref(), Some("This session will not be saved."));
+    assert!(!app.answer_quit_confirmation(false));
+    assert!(app.quit_confirmation.is_none());
+
+    app.ephemeral = false;
+    assert!(!app.request_quit());
+    assert!(app.quit_confirmation.as_deref().unwrap().starts_with("The session"));
+    assert!(app.answer_quit_confirmation(true));
+
+    app.history = "YOU: hi".to_string();
+    assert!(app.request_quit());
+}
+
+/// Tests that `Ctrl+P` appends the last answer after the lead-in and puts
+/// the cursor at the end.
+#[test]
+fn test_yank_last_answer() {
+    let mut app = create_test_app();
+    app.yank_last_answer();
+    assert_eq!(app.status_message.take().as_deref(), Some("No answer to yank"));
+
+    app.history = "\nYOU: Q\n\nAI [m]: fn main() {}\n⏱ 1.0s\n---\n".to_string();
+    app.yank_lead_in = "Refactor: ".to_string();
+    app.input = "Now ".to_string();
+    app.cursor_pos = 0;
+    app.yank_last_answer();
+    assert_eq!(app.input, "Now Refactor: fn main() {}");
+    assert!(app.input.ends_with("fn main() {}"));
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+}
+
+/// Tests that yanking appends to the end of a multi-line input, not to the
+/// line the cursor is on.
+#[test]
+fn test_yank_last_answer_multiline_input() {
+    let mut app = create_test_app();
+    app.history = "\nYOU: Q\n\nAI [m]: ANSWER\n⏱ 1.0s\n---\n".to_string();
+    app.yank_lead_in = String::new();
+    app.input = "first\nsecond".to_string();
+    app.cursor_pos = 0;
+    app.yank_last_answer();
+    assert_eq!(app.input, "first\nsecondANSWER");
+    assert_eq!(app.cursor_pos, app.input.chars().count());
+}
+
+/// Tests that per-model drafts and their cursors are collected for saving
+/// and restored into the model buffers.
+#[test]
+fn test_drafts_restore_per_model() {
+    let mut app = create_test_app();
+    app.input = "half a prompt".to_string();
+    app.cursor_pos = 4;
+    app.save_current_model_buffers();
+    let drafts = app.drafts();
+    assert_eq!(drafts["test_model_1"].text, "half a prompt");
+    assert_eq!(drafts["test_model_1"].cursor, 4);
+
+    let mut restarted = create_test_app();
+    restarted.apply_drafts(drafts);
+    assert_eq!((restarted.input.as_str(), restarted.cursor_pos), ("half a prompt", 4));
+    restarted.select_next_model();
+    assert!(restarted.input.is_empty());
+
+    let mut shared = create_test_app();
+    shared.shared_input = true;
+    shared.apply_drafts(HashMap::from([(SHARED_DRAFT_KEY.to_string(), Draft {
+        text: "shared".to_string(),
+        cursor: 99,
+    })]));
+    assert_eq!((shared.input.as_str(), shared.cursor_pos), ("shared", 6));
+    assert_eq!(shared.drafts()[SHARED_DRAFT_KEY].text, "shared");
+}
+
+/// Tests that the builder selects the first preset model and initializes
+/// per-model buffers the same way `refresh_models` does.
+#[test]
+fn test_builder_presets_models() {
+    let app = App::builder()
+        .models(vec!["a".to_string(), "b".to_string()])
+        .poll_mode(PollMode::Adaptive)
+        .debug_keys(true)
+        .build();
+    assert_eq!(app.list_state.selected(), Some(0));
+    assert_eq!(app.model_histories.len(), 2);
+    assert_eq!(app.poll_mode, PollMode::Adaptive);
+    assert!(app.debug_keys);
+}
+
+#[test]
+fn test_builder_without_models() {
+    let app = App::builder().build();
+    assert!(app.models.is_empty());
+    assert_eq!(app.list_state.selected(), None);
+    assert_eq!(app.poll_mode, PollMode::default());
+    assert!(app.snippets.is_empty());
+}
+
+const TWO_TURNS: &str = "\nYOU: One\n\nAI: 1\n---\n\nYOU: Two\n\nAI: 2\n---\n";
+
+/// Tests keyboard navigation between exchanges: Alt+Up starts at the most
+/// recent exchange, and moving past the last one returns to autoscroll.
+#[test]
+fn test_exchange_focus_navigation() {
+    let mut app = create_test_app();
+    app.history = TWO_TURNS.to_string();
+
+    app.focus_previous_exchange();
+    assert_eq!(app.focused_exchange, Some(1));
+    assert!(app.scroll_to_focus);
+    app.focus_previous_exchange();
+    app.focus_previous_exchange();
+    assert_eq!(app.focused_exchange, Some(0));
+
+    app.focus_next_exchange();
+    assert_eq!(app.focused_exchange, Some(1));
+    app.autoscroll = false;
+    app.focus_next_exchange();
+    assert_eq!(app.focused_exchange, None);
+    assert!(app.autoscroll);
+}
+
+#[test]
+fn test_toggle_focused_exchange_per_model() {
+    let mut app = create_test_app();
+    app.history = TWO_TURNS.to_string();
+
+    app.toggle_focused_exchange();
+    assert!(app.collapsed.is_empty());
+    assert!(app.status_message.is_some());
+
+    app.focus_previous_exchange();
+    app.toggle_focused_exchange();
+    assert!(app.collapsed.contains(&1));
+
+    app.select_next_model();
+    assert!(app.collapsed.is_empty());
+    assert_eq!(app.focused_exchange, None);
+    app.select_previous_model();
+    assert!(app.collapsed.contains(&1));
+
+    app.focused_exchange = Some(1);
+    app.toggle_focused_exchange();
+    assert!(app.collapsed.is_empty());
+}
+
+/// Tests that Ctrl+N archives the conversation before clearing it, while
+/// Ctrl+C discards it.
+#[test]
+fn test_new_conversation_archives_history() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut app = App::builder()
+        .models(vec!["test_model_1".to_string()])
+        .data_dir(dir.path())
+        .build();
+
+    app.new_conversation();
+    assert_eq!(app.status_message.as_deref(), Some("Conversation is already empty"));
+
+    app.history = "\nYOU: Hi\n\nAI: Hello\n---\n".to_string();
+    app.collapsed.insert(0);
+    app.new_conversation();
+    assert!(app.history.is_empty());
+    assert!(app.collapsed.is_empty());
+    assert_eq!(app.status_message.as_deref(), Some("Previous conversation saved"));
+    let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(files.len(), 1);
+
+    app.history = "YOU: gone".to_string();
+    app.clear_conversation();
+    assert!(app.history.is_empty());
+    assert_eq!(app.model_histories["test_model_1"], "");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+/// Creates an app with four installed models and a two-model allowlist.
+fn create_allowlisted_app() -> App {
+    let config = Config {
+        models: vec!["d".to_string(), "b".to_string(), "missing".to_string()],
+        ..Default::default()
+    };
+    App::builder()
+        .config(config)
+        .models(vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()])
+        .build()
+}
+
+/// Tests that the allowlist filters and orders the visible models while
+/// buffers are still kept for every installed model by name.
+#[test]
+fn test_model_allowlist_filters_and_orders() {
+    let app = create_allowlisted_app();
+    assert_eq!(app.models, vec!["d", "b"]);
+    assert_eq!(app.all_models.len(), 4);
+    assert_eq!(app.list_state.selected(), Some(0));
+    assert!(app.model_histories.contains_key("a"));
+}
+
+#[test]
+fn test_toggle_show_all_models_keeps_selection_by_name() {
+    let mut app = create_allowlisted_app();
+    app.select_next_model();
+    app.history = "YOU: for b".to_string();
+
+    app.toggle_show_all_models();
+    assert_eq!(app.models, vec!["a", "b", "c", "d"]);
+    assert_eq!(app.list_state.selected(), Some(1));
+    assert_eq!(app.history, "YOU: for b");
+
+    app.select_next_model();
+    app.toggle_show_all_models();
+    assert_eq!(app.models, vec!["d", "b"]);
+    assert_eq!(app.list_state.selected(), Some(0));
+    assert_eq!(app.model_histories["b"], "YOU: for b");
+}
+
+#[test]
+fn test_model_allowlist_without_installed_matches_shows_all() {
+    let config = Config {
+        models: vec!["missing".to_string()],
+        ..Default::default()
+    };
+    let mut app = App::builder().config(config).models(vec!["a".to_string()]).build();
+    assert_eq!(app.models, vec!["a"]);
+
+    let mut plain = create_test_app();
+    plain.toggle_show_all_models();
+    assert_eq!(plain.status_message.as_deref(), Some("No model list configured"));
+    app.toggle_show_all_models();
+    assert!(app.show_all_models);
+}
+
+/// Creates an app with models a, b, c whose order is saved to `path`.
+fn create_reorderable_app(path: &std::path::Path) -> App {
+    App::builder()
+        .models(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        .config_path(path)
+        .build()
+}
+
+/// Tests that moving the top model up or the bottom model down is a no-op.
+#[test]
+fn test_move_model_at_edges_does_nothing() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut app = create_reorderable_app(&path);
+
+    app.move_model_up();
+    assert_eq!(app.models, vec!["a", "b", "c"]);
+    assert_eq!(app.list_state.selected(), Some(0));
+
+    app.list_state.select(Some(2));
+    app.move_model_down();
+    assert_eq!(app.models, vec!["a", "b", "c"]);
+    assert_eq!(app.list_state.selected(), Some(2));
+    assert!(!path.exists());
+}
+
+/// Tests that the selection follows a moved model, buffers stay keyed by
+/// name and the order survives a model refresh.
+#[test]
+fn test_move_model_keeps_selection_and_persists() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut app = create_reorderable_app(&path);
+    app.history = "YOU: for a".to_string();
+
+    app.move_model_down();
+    app.move_model_down();
+    assert_eq!(app.models, vec!["b", "c", "a"]);
+    assert_eq!(app.list_state.selected(), Some(2));
+    assert_eq!(app.history, "YOU: for a");
+
+    app.list_state.select(Some(1));
+    app.move_model_up();
+    assert_eq!(app.models, vec!["c", "b", "a"]);
+    assert_eq!(app.list_state.selected(), Some(0));
+
+    let config = Config::load_from(&path).unwrap();
+    assert_eq!(config.model_order, vec!["c", "b", "a"]);
+    let reloaded = App::builder()
+        .config(config)
+        .models(vec!["a".to_string(), "b".to_string(), "new".to_string(), "c".to_string()])
+        .build();
+    assert_eq!(reloaded.models, vec!["c", "b", "a", "new"]);
+}
+
+/// Tests that an ephemeral session clears conversations and reorders models
+/// without writing any file.
+#[test]
+fn test_ephemeral_session_writes_no_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut app = App::builder()
+        .models(vec!["a".to_string(), "b".to_string()])
+        .data_dir(dir.path())
+        .config_path(dir.path().join("config.toml"))
+        .ephemeral(true)
+        .build();
+    app.history = "YOU: secret".to_string();
+
+    app.new_conversation();
+    assert!(app.history.is_empty());
+    assert_eq!(app.status_message.as_deref(), Some("Conversation cleared (not saved)"));
+
+    app.move_model_down();
+    assert_eq!(app.models, vec!["b", "a"]);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+/// Tests that startup selects the most recently used model that is still
+/// installed, and falls back to the first model.
+#[test]
+fn test_startup_selects_most_recent_installed_model() {
+    let models = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let app = App::builder()
+        .recent_models(vec!["gone".to_string(), "c".to_string(), "b".to_string()])
+        .models(models.clone())
+        .build();
+    assert_eq!(app.list_state.selected(), Some(2));
+    assert_eq!(app.persistent_mru_order(), vec!["c", "gone", "b"]);
+
+    let app = App::builder().recent_models(vec!["gone".to_string()]).models(models).build();
+    assert_eq!(app.list_state.selected(), Some(0));
+}
+
+/// Tests that a duplicate model name yields a single list entry, so no two
+/// entries share one buffer set, and that debug mode reports it.
+#[test]
+fn test_duplicate_model_names_are_merged() {
+    let mut app = App::builder()
+        .models(vec!["a".to_string(), "b".to_string(), "a".to_string()])
+        .debug_keys(true)
+        .build();
+    assert_eq!(app.models, vec!["a", "b"]);
+    assert_eq!(app.model_histories.len(), 2);
+    assert_eq!(app.status_message.as_deref(), Some("Ignored duplicate models: a"));
+
+    app.history = "YOU: for a".to_string();
+    app.select_next_model();
+    assert!(app.history.is_empty());
+    app.select_next_model();
+    assert_eq!(app.history, "YOU: for a");
+}
+
+#[test]
+fn test_copy_last_answer_without_answer() {
+    let mut app = create_test_app();
+    app.history = "\nYOU: Hi\n\nAI [test_model_1]: ".to_string();
+    app.copy_last_answer();
+    assert_eq!(app.status_message.as_deref(), Some("No answer to copy"));
+}
+
+
+/// Tests the estimate-vs-limit comparison: per-model limits win over the
+/// global one, the prompt template counts, and no limit never warns.
+#[test]
+fn test_context_overflow() {
+    let config = Config::parse(
+        "context_limit = 4\nprompt_template = \"Be brief. {prompt}\"\n\n\
+         [context_limits]\ntest_model_2 = 100\n",
+    )
+    .unwrap();
+    let mut app = App::builder()
+        .models(vec!["test_model_1".to_string(), "test_model_2".to_string()])
+        .config(config)
+        .build();
+
+    app.input = "Hi".to_string();
+    // "Be brief. Hi" is 12 characters, i.e. 3 tokens.
+    assert_eq!(app.context_overflow(), None);
+    app.input = "Hi there".to_string();
+    assert_eq!(app.context_overflow(), Some((5, 4)));
+
+    app.select_next_model();
+    app.input = "Hi there".to_string();
+    assert_eq!(app.context_overflow(), None);
+
+    let mut app = create_test_app();
+    app.input = "x".repeat(100_000);
+    assert_eq!(app.context_overflow(), None);
+    assert_eq!(estimate_tokens(&app.input), 25_000);
+}
+
+/// Tests that the draft input follows the model by default and stays put
+/// across a model switch with `shared_input`.
+#[test]
+fn test_shared_input_across_model_switch() {
+    let mut app = create_test_app();
+    app.insert_str("draft");
+    app.select_next_model();
+    assert_eq!(app.input, "");
+    app.select_previous_model();
+    assert_eq!(app.input, "draft");
+
+    let mut app = App::builder()
+        .models(vec!["test_model_1".to_string(), "test_model_2".to_string()])
+        .config(Config::parse("shared_input = true").unwrap())
+        .build();
+    app.insert_str("draft");
+    app.select_next_model();
+    assert_eq!(app.input, "draft");
+    assert_eq!(app.cursor_pos, 5);
+    app.insert_str("!");
+    app.select_previous_model();
+    assert_eq!(app.input, "draft!");
+}
+
+/// Tests the log manager flow: listing per model, declining and then
+/// confirming a deletion.
+#[test]
+fn test_log_manager_deletes_after_confirmation() {
+    let dir = tempfile::TempDir::new().unwrap();
+    lazyllama::utils::archive_conversation_in(dir.path(), "a", "one").unwrap();
+    lazyllama::utils::archive_conversation_in(dir.path(), "b", "two").unwrap();
+    let mut app = App::builder().data_dir(dir.path()).build();
+
+    app.open_log_manager();
+    let manager = app.log_manager.as_ref().unwrap();
+    let models: Vec<&str> = manager.groups.iter().map(|g| g.model.as_str()).collect();
+    assert_eq!(models, ["a", "b"]);
+    assert_eq!(manager.groups[0].bytes, 3);
+
+    app.log_manager_next();
+    app.request_log_deletion();
+    assert!(app.log_manager.as_ref().unwrap().confirm_delete);
+    app.confirm_log_deletion(false);
+    assert_eq!(app.log_manager.as_ref().unwrap().groups.len(), 2);
+
+    app.request_log_deletion();
+    app.confirm_log_deletion(true);
+    let manager = app.log_manager.as_ref().unwrap();
+    assert_eq!(manager.groups.len(), 1);
+    assert_eq!(manager.selected, 0);
+    assert_eq!(app.status_message.as_deref(), Some("Deleted 1 log of b"));
+}
+
+/// Tests that temperature steps stop at the 0.0 and 2.0 boundaries and are
+/// kept per model.
+#[test]
+fn test_temperature_clamped_at_boundaries() {
+    let mut app = create_test_app();
+    let temperature = |app: &App, model: &str| app.model_options[model].temperature;
+
+    app.lower_temperature();
+    assert_eq!(temperature(&app, "test_model_1"), Some(DEFAULT_TEMPERATURE - 0.1));
+    for _ in 0..20 {
+        app.lower_temperature();
+    }
+    assert_eq!(temperature(&app, "test_model_1"), Some(MIN_TEMPERATURE));
+    assert_eq!(app.status_message.as_deref(), Some("Temperature for test_model_1: 0.0"));
+
+    app.select_next_model();
+    for _ in 0..30 {
+        app.raise_temperature();
+    }
+    assert_eq!(temperature(&app, "test_model_2"), Some(MAX_TEMPERATURE));
+    assert_eq!(temperature(&app, "test_model_1"), Some(MIN_TEMPERATURE));
+    app.lower_temperature();
+    assert_eq!(temperature(&app, "test_model_2"), Some(1.9));
+}
+
+/// Tests that a bookmark is toggled on the same line, moved on another line,
+/// jumped to without autoscroll and kept per model.
+#[test]
+fn test_bookmark_toggle_and_jump() {
+    let mut app = create_test_app();
+    app.scroll = 12;
+    app.toggle_bookmark();
+    assert_eq!(app.bookmark, Some(12));
+
+    app.scroll = 30;
+    app.jump_to_bookmark();
+    assert_eq!(app.scroll, 12);
+    assert!(!app.autoscroll);
+
+    app.select_next_model();
+    assert_eq!(app.bookmark, None);
+    app.jump_to_bookmark();
+    assert_eq!(app.status_message.as_deref(), Some("No bookmark set (Ctrl+B)"));
+    app.select_previous_model();
+    assert_eq!(app.bookmark, Some(12));
+
+    app.scroll = 12;
+    app.toggle_bookmark();
+    assert_eq!(app.bookmark, None);
+    assert!(!app.model_bookmarks.contains_key("test_model_1"));
+}
+
+/// Tests that injected histories reach the visible history only for the
+/// selected model, and survive switching models.
+#[test]
+fn test_set_model_history_and_append_message() {
+    let mut app = create_test_app();
+    app.collapsed.insert(0);
+
+    app.set_model_history("test_model_2", "\nYOU: Other\n".to_string());
+    assert!(app.history.is_empty());
+    assert!(app.collapsed.contains(&0));
+
+    app.set_model_history("test_model_1", "\nYOU: Q\n\nAI [test_model_1]: A\n---\n".to_string());
+    assert!(app.history.starts_with("\nYOU: Q\n"));
+    assert!(app.collapsed.is_empty());
+
+    app.append_message(Role::User, "Next");
+    app.append_message(Role::Assistant, "Done");
+    assert!(app.history.ends_with("\nYOU: Next\n\nAI [test_model_1]: Done\n---\n"));
+    assert_eq!(app.model_histories["test_model_1"], app.history);
+
+    app.select_next_model();
+    assert_eq!(app.history, "\nYOU: Other\n");
+    app.select_previous_model();
+    assert!(app.history.ends_with("AI [test_model_1]: Done\n---\n"));
+}
+
+#[test]
+fn test_clear_input_keeps_history() {
+    let mut app = create_test_app();
+    app.history = "\nYOU: Q\n\nAI [test_model_1]: A\n---\n".to_string();
+    app.input = "a long draft".to_string();
+    app.cursor_pos = 7;
+
+    app.clear_input();
+    assert!(app.input.is_empty());
+    assert_eq!(app.cursor_pos, 0);
+    assert_eq!(app.history, "\nYOU: Q\n\nAI [test_model_1]: A\n---\n");
+}
+
+#[test]
+fn test_has_unsaved_buffer() {
+    let mut app = create_test_app();
+    app.save_current_model_buffers();
+    assert!(!app.has_unsaved_buffer());
+
+    app.input = "draft".to_string();
+    assert!(app.has_unsaved_buffer());
+    app.save_current_model_buffers();
+    assert!(!app.has_unsaved_buffer());
+
+    app.history.push_str("\nYOU: Q\n");
+    assert!(app.has_unsaved_buffer());
+    app.save_current_model_buffers();
+    assert!(!app.has_unsaved_buffer());
+
+    app.shared_input = true;
+    app.input = "other".to_string();
+    assert!(!app.has_unsaved_buffer());
+}
+
+/// Tests that reloading applies a changed config file and keeps the
+/// previous settings when the file no longer parses.
+#[test]
+fn test_reload_config_keeps_settings_on_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let mut app = App::builder()
+        .models(vec!["a".to_string()])
+        .config_path(&path)
+        .build();
+
+    std::fs::write(&path, "code_block_style = \"minimal\"\nyank_lead_in = \"Re:\"\n").unwrap();
+    app.reload_config();
+    assert_eq!(app.status_message.as_deref(), Some("Config reloaded"));
+    assert_eq!(app.code_block_style, CodeBlockStyle::Minimal);
+    assert_eq!(app.yank_lead_in, "Re:");
+
+    std::fs::write(&path, "code_block_style = [").unwrap();
+    app.reload_config();
+    assert!(app.status_message.as_deref().unwrap().starts_with("Config error: "));
+    assert_eq!(app.code_block_style, CodeBlockStyle::Minimal);
+    assert_eq!(app.yank_lead_in, "Re:");
+}
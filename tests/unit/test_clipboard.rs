@@ -0,0 +1,86 @@
+//! Unit tests for the Clipboard module (src/clipboard.rs)
+//!
+//! These tests verify that copy helpers produce clean source text without
+//! any of the decorations added by the conversation renderer.
+//!
+//! ## Test Coverage
+//!
+//! - **Code Cleaning**: Fence, frame and gutter removal from code blocks
+//! - **Markdown Cleaning**: Decoration-free conversation export
+//! - **OSC 52**: Escape sequence construction for the terminal clipboard
+
+use lazyllama::clipboard::{clean_code, clean_markdown, osc52_sequence};
+use lazyllama::ui::{extract_code_blocks, parse_history};
+
+const DECORATIONS: [&str; 4] = ["┌", "└", "│", "●"];
+
+/// Flattens rendered `Text` back into plain lines, as a terminal would show it.
+fn rendered_to_string(history: &str) -> String {
+    parse_history(history)
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_clean_code_strips_fences() {
+    let block = "```rust\nfn main() {\n    println!(\"hi\");\n}\n```";
+    assert_eq!(clean_code(block), "fn main() {\n    println!(\"hi\");\n}");
+}
+
+#[test]
+fn test_clean_code_extracted_block_unchanged() {
+    let history = "AI: Example:\n```python\ndef f():\n    return 1\n```\n";
+    let blocks = extract_code_blocks(history);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(clean_code(&blocks[0].code), "def f():\n    return 1");
+}
+
+#[test]
+fn test_clean_code_strips_rendered_frame() {
+    let rendered = rendered_to_string("```rust\nlet x = 1;\n\n    let y = 2;\n```");
+    let cleaned = clean_code(&rendered);
+    assert_eq!(cleaned, "let x = 1;\n\n    let y = 2;");
+    for decoration in DECORATIONS {
+        assert!(!cleaned.contains(decoration), "found {:?} in {:?}", decoration, cleaned);
+    }
+}
+
+/// Tests that copying a rendered conversation yields decoration-free Markdown.
+///
+/// Renders a conversation with a header and a code block, then cleans the
+/// on-screen text and checks that no border or bullet characters remain
+/// while the actual content is preserved.
+#[test]
+fn test_clean_markdown_removes_decorations() {
+    let history = "YOU: Explain\n\nAI: ### Summary\nUse this:\n```rust\nfn main() {}\n```\nDone\n---\n";
+    let cleaned = clean_markdown(&rendered_to_string(history));
+    for decoration in DECORATIONS {
+        assert!(!cleaned.contains(decoration), "found {:?} in {:?}", decoration, cleaned);
+    }
+    assert!(cleaned.contains("### Summary"));
+    assert!(cleaned.contains("fn main() {}"));
+    assert!(cleaned.contains("Done"));
+}
+
+#[test]
+fn test_clean_markdown_keeps_source_text() {
+    let history = "\nYOU: Hi   \n\nAI: Hello!\n---\n";
+    assert_eq!(clean_markdown(history), "YOU: Hi\n\nAI: Hello!\n---");
+}
+
+#[test]
+fn test_clean_markdown_keeps_box_drawing_outside_frames() {
+    let history = "AI: Diagram:\n│ a │ b │";
+    assert_eq!(clean_markdown(history), history);
+}
+
+#[test]
+fn test_osc52_sequence() {
+    assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+    assert_eq!(osc52_sequence("hi"), "\x1b]52;c;aGk=\x07");
+    assert_eq!(osc52_sequence("abc"), "\x1b]52;c;YWJj\x07");
+    assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+}
@@ -0,0 +1,228 @@
+//! Unit tests for the Config module (src/config.rs)
+//!
+//! These tests verify parsing of `config.toml` and the fallback to defaults
+//! when the file or individual keys are missing.
+//!
+//! ## Test Coverage
+//!
+//! - **Parsing**: Snippet tables and default values
+//! - **Loading**: Missing files, valid files and parse errors
+//! - **Hosts**: Named Ollama servers in file order
+//! - **Prompt Templates**: Placeholder validation and substitution
+//! - **Help Overlay**: Snippet hints rendered from the configuration
+
+use lazyllama::config::{
+    apply_template, save_model_order, Config, SendKey, Snippet, DEFAULT_CONFIRM_QUIT_AFTER,
+    DEFAULT_THINKING_HINT, DEFAULT_THINKING_HINT_AFTER, MAX_SNIPPETS,
+};
+use lazyllama::ui::help_text;
+use std::fs;
+use tempfile::TempDir;
+
+const SAMPLE: &str = r#"
+[[snippets]]
+name = "Summarize"
+text = "Summarize this: "
+
+[[snippets]]
+name = "Test"
+text = "Write a test for: "
+"#;
+
+fn snippet(name: &str, text: &str) -> Snippet {
+    Snippet {
+        name: name.to_string(),
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_snippets_in_order() {
+    let config = Config::parse(SAMPLE).unwrap();
+    assert_eq!(
+        config.snippets,
+        vec![
+            snippet("Summarize", "Summarize this: "),
+            snippet("Test", "Write a test for: "),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_empty_config_uses_defaults() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn test_parse_rejects_invalid_snippet() {
+    assert!(Config::parse("[[snippets]]\nname = 1\n").is_err());
+}
+
+#[test]
+fn test_load_missing_file_is_default() {
+    let dir = TempDir::new().unwrap();
+    let config = Config::load_from(&dir.path().join("config.toml")).unwrap();
+    assert!(config.snippets.is_empty());
+}
+
+#[test]
+fn test_load_from_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, SAMPLE).unwrap();
+    let config = Config::load_from(&path).unwrap();
+    assert_eq!(config.snippets.len(), 2);
+}
+
+#[test]
+fn test_load_error_names_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "snippets = [").unwrap();
+    let err = Config::load_from(&path).unwrap_err().to_string();
+    assert!(err.contains("config.toml"));
+}
+
+/// Tests that saving the model order keeps the rest of the file intact.
+#[test]
+fn test_save_model_order_preserves_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lazyllama").join("config.toml");
+    let order = vec!["b".to_string(), "a".to_string()];
+    save_model_order(&path, &order).unwrap();
+    assert_eq!(Config::load_from(&path).unwrap().model_order, order);
+
+    fs::write(&path, format!("# my snippets{}", SAMPLE)).unwrap();
+    save_model_order(&path, &order).unwrap();
+    let source = fs::read_to_string(&path).unwrap();
+    assert!(source.contains("# my snippets"));
+    let config = Config::load_from(&path).unwrap();
+    assert_eq!(config.model_order, order);
+    assert_eq!(config.snippets.len(), 2);
+}
+
+#[test]
+fn test_save_model_order_rejects_invalid_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "snippets = [").unwrap();
+    assert!(save_model_order(&path, &["a".to_string()]).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "snippets = [");
+}
+
+/// Tests that the help overlay lists only snippets reachable by a shortcut.
+#[test]
+fn test_help_text_lists_snippets() {
+    let snippets: Vec<Snippet> = (1..=MAX_SNIPPETS + 1)
+        .map(|i| snippet(&format!("Snippet {}", i), "text"))
+        .collect();
+    let lines: Vec<String> = help_text(&snippets)
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+    assert!(lines.iter().any(|l| l.starts_with("Alt+1") && l.ends_with("Snippet 1")));
+    assert!(lines.iter().any(|l| l.starts_with("Alt+9") && l.ends_with("Snippet 9")));
+    assert!(!lines.iter().any(|l| l.contains("Snippet 10")));
+}
+
+#[test]
+fn test_help_text_without_snippets() {
+    let text = help_text(&[]);
+    let last: String = text.lines.last().unwrap().spans.iter().map(|s| s.content.as_ref()).collect();
+    assert!(last.contains("No snippets configured"));
+}
+
+#[test]
+fn test_parse_label_icons() {
+    let config = Config::parse("[labels]\nai_icon = \"🦙\"\n").unwrap();
+    assert_eq!(config.labels.ai_icon, "🦙");
+    assert!(config.labels.user_icon.is_empty());
+}
+
+#[test]
+fn test_parse_hosts_in_order() {
+    let config = Config::parse(
+        "[[hosts]]\nname = \"laptop\"\nurl = \"http://localhost:11434\"\n\n\
+         [[hosts]]\nname = \"gpu\"\nurl = \"http://gpu:11434\"\n",
+    )
+    .unwrap();
+    let names: Vec<&str> = config.hosts.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["laptop", "gpu"]);
+    assert_eq!(config.hosts[1].url, "http://gpu:11434");
+    assert!(Config::parse("").unwrap().hosts.is_empty());
+}
+
+#[test]
+fn test_parse_thinking_hint() {
+    let config = Config::parse("").unwrap();
+    assert_eq!(config.thinking_hint_after, DEFAULT_THINKING_HINT_AFTER);
+    assert_eq!(config.thinking_hint, DEFAULT_THINKING_HINT);
+    let config = Config::parse("thinking_hint_after = 3\nthinking_hint = \"hang on\"").unwrap();
+    assert_eq!(config.thinking_hint_after, 3);
+    assert_eq!(config.thinking_hint, "hang on");
+}
+
+#[test]
+fn test_parse_confirm_quit() {
+    let config = Config::parse("").unwrap();
+    assert!(!config.confirm_quit);
+    assert_eq!(config.confirm_quit_after, DEFAULT_CONFIRM_QUIT_AFTER);
+    let config = Config::parse("confirm_quit = true\nconfirm_quit_after = 500").unwrap();
+    assert!(config.confirm_quit);
+    assert_eq!(config.confirm_quit_after, 500);
+}
+
+#[test]
+fn test_trim_response_defaults_to_on() {
+    assert!(Config::default().trim_response);
+    assert!(Config::parse("").unwrap().trim_response);
+    assert!(!Config::parse("trim_response = false").unwrap().trim_response);
+}
+
+/// Tests that `send_key` defaults to Enter and rejects unknown values.
+#[test]
+fn test_parse_send_key() {
+    assert_eq!(Config::parse("").unwrap().send_key, SendKey::Enter);
+    let config = Config::parse("send_key = \"ctrl-enter\"").unwrap();
+    assert_eq!(config.send_key, SendKey::CtrlEnter);
+    assert!(Config::parse("send_key = \"shift-enter\"").is_err());
+
+    // Shift+Enter never sends, so it cannot clash with either setting.
+    for send_key in [SendKey::Enter, SendKey::CtrlEnter] {
+        assert!(!send_key.sends(false, true));
+        assert!(!send_key.sends(true, true));
+    }
+}
+
+#[test]
+fn test_parse_prompt_templates() {
+    let config = Config::parse(
+        "prompt_template = \"Be brief. {prompt}\"\n[model_templates]\n\"llama3:8b\" = \"{prompt} In German.\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.prompt_template.as_deref(), Some("Be brief. {prompt}"));
+    assert_eq!(config.model_templates["llama3:8b"], "{prompt} In German.");
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_validate_reports_missing_placeholder() {
+    let config = Config::parse("[model_templates]\nmistral = \"Be brief.\"\n").unwrap();
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("mistral"));
+}
+
+#[test]
+fn test_validate_reports_invalid_timestamp_format() {
+    assert_eq!(Config::default().timestamp_format, "%Y-%m-%d %H:%M");
+    let config = Config::parse("timestamp_format = \"%d.%m. %Q\"\n").unwrap();
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("timestamp_format"), "{}", err);
+}
+
+#[test]
+fn test_apply_template_replaces_every_placeholder() {
+    assert_eq!(apply_template(Some("{prompt} / {prompt}"), "x"), "x / x");
+    assert_eq!(apply_template(Some("{prompt}"), ""), "");
+}
@@ -0,0 +1,75 @@
+//! Unit tests for the Diff module (src/diff.rs)
+//!
+//! These tests verify the word diff used to compare a regenerated answer
+//! with the answer it replaced.
+//!
+//! ## Test Coverage
+//!
+//! - **Word Diff**: Insertions, deletions and unchanged text
+//! - **Line Breaks**: Multi-line answers and whitespace handling
+//! - **Rendering**: Colors of added and removed text
+
+use lazyllama::diff::{diff_words, DiffOp};
+use lazyllama::ui::diff_lines;
+use ratatui::style::{Color, Modifier};
+
+/// Reassembles one side of a diff.
+fn side(ops: &[DiffOp], new: bool) -> String {
+    ops.iter()
+        .filter_map(|op| match op {
+            DiffOp::Same(t) => Some(t.as_str()),
+            DiffOp::Added(t) if new => Some(t.as_str()),
+            DiffOp::Removed(t) if !new => Some(t.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff_identical_text() {
+    assert_eq!(diff_words("same text", "same text"), vec![DiffOp::Same("same text".to_string())]);
+}
+
+#[test]
+fn test_diff_empty_sides() {
+    assert_eq!(diff_words("", "new"), vec![DiffOp::Added("new".to_string())]);
+    assert_eq!(diff_words("old", ""), vec![DiffOp::Removed("old".to_string())]);
+    assert!(diff_words("", "").is_empty());
+}
+
+#[test]
+fn test_diff_insertion() {
+    let ops = diff_words("Rust is fast", "Rust is very fast");
+    assert_eq!(
+        ops,
+        vec![
+            DiffOp::Same("Rust is ".to_string()),
+            DiffOp::Added("very ".to_string()),
+            DiffOp::Same("fast".to_string()),
+        ]
+    );
+}
+
+/// Tests that both versions can be reconstructed from a multi-line diff.
+#[test]
+fn test_diff_reconstructs_both_sides() {
+    let old = "First line.\nThe answer is 41.\n\nBye";
+    let new = "First line.\nThe answer is 42!\nBye now";
+    let ops = diff_words(old, new);
+    assert_eq!(side(&ops, false), old);
+    assert_eq!(side(&ops, true), new);
+}
+
+#[test]
+fn test_diff_lines_styles() {
+    let lines = diff_lines("a b\nc", "a x\nc");
+    let spans: Vec<_> = lines.iter().flat_map(|l| l.spans.iter()).collect();
+    let added = spans.iter().find(|s| s.content.contains('x')).unwrap();
+    assert_eq!(added.style.fg, Some(Color::Green));
+    let removed = spans.iter().find(|s| s.content.contains('b')).unwrap();
+    assert_eq!(removed.style.fg, Some(Color::Red));
+    assert!(removed.style.add_modifier.contains(Modifier::CROSSED_OUT));
+    // A blank line and the header come before the diff itself.
+    assert!(lines[0].spans.is_empty());
+    assert_eq!(lines.last().unwrap().spans[0].content, "c");
+}
@@ -10,6 +10,7 @@
 //! - **Special Keys**: Arrow keys, Page Up/Down, Enter, Backspace
 //! - **Character Input**: Normal character input without modifiers
 //! - **Navigation Keys**: Home, End, Left, Right arrow keys
+//! - **Editing Dispatch**: Word motion and deletion keys applied to an `App`
 //!
 //! ## Test Strategy
 //!
@@ -20,6 +21,7 @@
 
 use std::time::Duration;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, KeyEventKind};
+use lazyllama::app::App;
 
 /// Helper function for creating KeyEvent structures for testing.
 /// 
@@ -165,6 +167,38 @@ fn test_ctrl_combinations() {
     assert!(ctrl_delete.modifiers.contains(KeyModifiers::CONTROL));
 }
 
+/// Tests that the Ctrl word-editing keys reach the word motions through
+/// `App::handle_editing_key`, the dispatch the event loop uses.
+#[test]
+fn test_ctrl_word_keys_dispatch() {
+    let mut app = App::builder().build();
+    app.input = "Hello World".to_string();
+    app.cursor_pos = 11;
+
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Left, KeyModifiers::CONTROL)));
+    assert_eq!(app.cursor_pos, 6);
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Left, KeyModifiers::CONTROL)));
+    assert_eq!(app.cursor_pos, 0);
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Right, KeyModifiers::CONTROL)));
+    assert_eq!(app.cursor_pos, 5);
+
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Delete, KeyModifiers::CONTROL)));
+    assert_eq!(app.input, "Hello");
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Backspace, KeyModifiers::CONTROL)));
+    assert_eq!((app.input.as_str(), app.cursor_pos), ("", 0));
+
+    // Kitty reports Ctrl+Backspace as Ctrl+H.
+    app.input = "one two".to_string();
+    app.cursor_pos = 7;
+    assert!(app.handle_editing_key(create_key_event(KeyCode::Char('h'), KeyModifiers::CONTROL)));
+    assert_eq!(app.input, "one ");
+
+    // Typed characters and shortcuts are left to the event loop.
+    assert!(!app.handle_editing_key(create_key_event(KeyCode::Char('h'), KeyModifiers::empty())));
+    assert!(!app.handle_editing_key(create_key_event(KeyCode::Up, KeyModifiers::empty())));
+    assert_eq!(app.input, "one ");
+}
+
 /// Tests basic navigation keys for cursor and text movement.
 /// 
 /// This test validates that the application correctly processes navigation
@@ -23,7 +23,13 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::Text,
 };
-use lazyllama::ui::{parse_history, process_styled_text, BANNER};
+use lazyllama::config::{CodeBlockStyle, Labels, TextDirection};
+use lazyllama::ui::{
+    detect_language, input_cursor_line, input_height, input_overflow, input_scroll_x,
+    answer_start_line, format_session_duration, input_text, last_answer, link_definitions, parse_history, parse_history_layout, parse_history_with,
+    process_styled_text, split_ai_label, split_exchanges, split_key_value, stream_progress,
+    truncate_middle, Emphasis, RenderOptions, BANNER, DEFAULT_DIVIDER_WIDTH, MAX_INPUT_LINES,
+};
 
 #[test]
 fn test_parse_history_simple_conversation() {
@@ -107,6 +113,183 @@ fn test_parse_history_with_code_block() {
     assert_eq!(footer_line.spans[0].style.fg, Some(Color::Yellow));
 }
 
+/// Tests that a trailing code fence that is not closed yet, as while an
+/// answer streams, is framed without a bottom border.
+#[test]
+fn test_parse_history_open_trailing_fence() {
+    let history = "YOU: Code?\n\nAI: Sure:\n```rust\nfn main() {\n    let x";
+    let rendered: Vec<String> = parse_history(history)
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+    let header = rendered.iter().position(|l| l == " ┌── rust ──").expect("framed header");
+    assert_eq!(rendered[header + 1], " │ fn main() {");
+    assert_eq!(rendered[header + 2], " │     let x");
+    assert_eq!(rendered.len(), header + 3);
+
+    let closed = format!("{}\n}}\n```\n", history);
+    let rendered: Vec<String> = parse_history(&closed)
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+    assert_eq!(rendered[header + 1], " │ fn main() {");
+    assert!(rendered.iter().any(|l| l.contains('└')));
+}
+
+/// Tests that the "all bold" emphasis makes plain spans bold and keeps
+/// colors, and that cycling returns to normal.
+#[test]
+fn test_emphasis_all_bold() {
+    let history = "YOU: Hello\n\nAI: Plain answer";
+    let options = RenderOptions {
+        emphasis: Emphasis::Bold,
+        ..RenderOptions::default()
+    };
+    let normal = parse_history(history);
+    let bold = parse_history_with(history, &options);
+
+    let plain = |text: &Text<'_>| {
+        text.lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .find(|s| s.content.contains("Plain answer"))
+            .unwrap()
+            .style
+    };
+    assert!(!plain(&normal).add_modifier.contains(Modifier::BOLD));
+    assert!(plain(&bold).add_modifier.contains(Modifier::BOLD));
+    assert_eq!(plain(&bold).fg, plain(&normal).fg);
+    for span in bold.lines.iter().flat_map(|l| l.spans.iter()) {
+        assert!(span.style.add_modifier.contains(Modifier::BOLD));
+    }
+
+    assert_eq!(Emphasis::Normal.next().next().next(), Emphasis::Normal);
+}
+
+/// Tests that the minimal code block style draws a gutter and a dim label
+/// but no top or bottom border.
+#[test]
+fn test_parse_history_minimal_code_block() {
+    let history = "AI: Code:\n```rust\nfn main() {}\n```\nDone";
+    let options = RenderOptions {
+        code_block_style: CodeBlockStyle::Minimal,
+        ..RenderOptions::default()
+    };
+    let rendered: Vec<String> = parse_history_with(history, &options)
+        .lines
+        .iter()
+        .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+
+    assert!(rendered.iter().all(|l| !l.contains('┌') && !l.contains('└')));
+    assert_eq!(rendered[1], "▎ rust");
+    assert_eq!(rendered[2], "▎ fn main() {}");
+    let bordered = parse_history_with(history, &RenderOptions::default());
+    assert_eq!(rendered.len() + 1, bordered.lines.len());
+}
+
+/// Tests that numbered turns put `#1`, `#2`, `#3` in front of the first
+/// line of each exchange.
+#[test]
+fn test_parse_history_numbered_turns() {
+    let history = "\nYOU: One\n\nAI: A\n---\n\nYOU: Two\n\nAI: B\n---\n\nYOU: Three\n";
+    let options = RenderOptions {
+        number_turns: true,
+        ..RenderOptions::default()
+    };
+    let (text, offsets) = parse_history_layout(history, &options);
+    let numbers: Vec<String> = text
+        .lines
+        .iter()
+        .filter_map(|line| line.spans.first())
+        .filter(|span| span.content.starts_with('#'))
+        .map(|span| span.content.trim().to_string())
+        .collect();
+    assert_eq!(numbers, ["#1", "#2", "#3"]);
+    assert_eq!(offsets.len(), 3);
+    let first_of_third: String = text.lines[offsets[2]..]
+        .iter()
+        .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect::<String>())
+        .find(|l| !l.trim().is_empty())
+        .unwrap();
+    assert!(first_of_third.starts_with("#3 ") && first_of_third.contains("Three"));
+}
+
+/// Tests that a turn written in a right-to-left script is right-aligned,
+/// except for its code blocks, while English turns and dividers stay left
+/// and the direction setting overrides detection.
+#[test]
+fn test_parse_history_right_to_left() {
+    use ratatui::layout::Alignment;
+    let history = "\nYOU: מה שלומך?\n\nAI: Fine, thanks.\nAnd you?\n---\n\nYOU: Hi\n\nAI [m]: مرحبا بك\nكيف حالك\n```\nx\n```\nشكرا\n";
+    let aligned = |direction| {
+        let options = RenderOptions {
+            text_direction: direction,
+            ..RenderOptions::default()
+        };
+        let (text, _) = parse_history_layout(history, &options);
+        text.lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect::<String>())
+            .zip(text.lines.iter().map(|l| l.alignment == Some(Alignment::Right)))
+            .filter(|(content, _)| !content.trim().is_empty())
+            .collect::<Vec<_>>()
+    };
+    let lines = aligned(TextDirection::Auto);
+    let right = |needle: &str| lines.iter().find(|(c, _)| c.contains(needle)).unwrap().1;
+    assert!(right("YOU: מה שלומך?"));
+    assert!(!right("Fine"));
+    assert!(!right("And you?"));
+    assert!(!right("YOU: Hi"));
+    assert!(right("مرحبا بك"));
+    assert!(right("كيف حالك"));
+    assert!(right("شكرا"));
+    // Code stays left-to-right, as does the divider.
+    assert!(!right("│ x"));
+    assert!(!right("──"));
+
+    assert!(aligned(TextDirection::Ltr).iter().all(|(_, right)| !right));
+    assert!(aligned(TextDirection::Rtl).iter().any(|(c, right)| c.contains("Fine") && *right));
+}
+
+/// Tests session duration formatting below and beyond an hour.
+#[test]
+fn test_format_session_duration() {
+    use std::time::Duration;
+    assert_eq!(format_session_duration(Duration::ZERO), "0:00");
+    assert_eq!(format_session_duration(Duration::from_secs(59 * 60 + 59)), "59:59");
+    assert_eq!(format_session_duration(Duration::from_secs(3600)), "1:00:00");
+    assert_eq!(format_session_duration(Duration::from_secs(27 * 3600 + 3 * 60 + 9)), "27:03:09");
+}
+
+/// Tests that the answer start of the last exchange is found, also with
+/// numbered turns and icons, and that an unanswered prompt has none.
+#[test]
+fn test_answer_start_line() {
+    let labels = Labels {
+        user_icon: "🧑".to_string(),
+        ai_icon: "🦙".to_string(),
+    };
+    for number_turns in [false, true] {
+        let options = RenderOptions {
+            number_turns,
+            labels: labels.clone(),
+            ..RenderOptions::default()
+        };
+        let history = "\nYOU: One\n\nAI: A\n---\n\nYOU: Two\nlines\n\nAI [m]: B\nmore\n";
+        let (text, offsets) = parse_history_layout(history, &options);
+        let line = answer_start_line(&text, &offsets, &labels).unwrap();
+        assert!(line > offsets[1]);
+        let content: String = text.lines[line].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(content.contains('B'), "{}", content);
+
+        let (text, offsets) = parse_history_layout("\nYOU: One\n", &options);
+        assert_eq!(answer_start_line(&text, &offsets, &labels), None);
+    }
+}
+
 /// Tests conversation parsing with multiple code blocks in sequence.
 /// 
 /// This test validates the parser's ability to handle conversations containing
@@ -174,6 +357,30 @@ fn test_parse_history_code_without_language() {
     assert!(header_line.spans[0].content.contains("code"));
 }
 
+/// Tests that unlabeled code blocks get a detected language, and that
+/// ambiguous snippets are left alone.
+#[test]
+fn test_detect_language() {
+    let rust = "use std::fs;\n\nfn read(path: &str) -> String {\n    fs::read_to_string(path).unwrap()\n}";
+    assert_eq!(detect_language(rust), Some("rust"));
+    let python = "def greet(name):\n    if name is None:\n        return\n    print(f\"Hi {name}\")";
+    assert_eq!(detect_language(python), Some("python"));
+    let json = "{\n  \"model\": \"llama3\",\n  \"stream\": false\n}";
+    assert_eq!(detect_language(json), Some("json"));
+    let js = "const add = (a, b) => a + b;\nconsole.log(add(1, 2));";
+    assert_eq!(detect_language(js), Some("javascript"));
+    let bash = "#!/bin/bash\nset -e";
+    assert_eq!(detect_language(bash), Some("bash"));
+
+    assert_eq!(detect_language("echo \"hello\""), None);
+    assert_eq!(detect_language("x = 1"), None);
+    assert_eq!(detect_language("{ a: 1 }"), None);
+
+    let history = format!("AI: Code:\n```\n{}\n```", rust);
+    let parsed = parse_history_with(&history, &RenderOptions::default());
+    assert!(parsed.lines.iter().any(|l| l.spans[0].content.contains("┌── rust")));
+}
+
 #[test]
 fn test_process_styled_text_headers() {
     let text = "### Header test\nRegular text";
@@ -313,4 +520,455 @@ fn test_long_lines_in_history() {
     // Sollte lange Zeilen handhaben ohne zu crashen
     assert!(parsed.lines.len() >= 2);
     assert!(parsed.lines[0].spans[1].content.len() > 900);
-}
\ No newline at end of file
+}
+
+/// Flattens a rendered line into its visible text.
+fn line_text(line: &ratatui::text::Line) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+#[test]
+fn test_split_ai_label() {
+    assert_eq!(split_ai_label("AI: Hello"), Some((None, " Hello")));
+    assert_eq!(split_ai_label("AI [codellama:7b]: Hello"), Some((Some("codellama:7b"), " Hello")));
+    assert_eq!(split_ai_label("AI [broken label"), None);
+    assert_eq!(split_ai_label("YOU: AI: Hello"), None);
+}
+
+/// Tests rendering of a history whose turns were produced by different models.
+///
+/// Only the turn whose model differs from the currently selected one should
+/// carry a visible `[model]` tag, styled in dim gray.
+#[test]
+fn test_parse_history_mixed_model_turns() {
+    let history = "YOU: One\n\nAI [llama3]: First\n---\nYOU: Two\n\nAI [codellama]: Second\n---\n";
+    let options = RenderOptions { current_model: Some("llama3".to_string()), ..Default::default() };
+    let parsed = parse_history_with(history, &options);
+
+    let ai_lines: Vec<String> = parsed
+        .lines
+        .iter()
+        .map(line_text)
+        .filter(|text| text.starts_with("AI"))
+        .collect();
+    assert_eq!(ai_lines, vec!["AI:  First", "AI [codellama]:  Second"]);
+
+    let tag = parsed
+        .lines
+        .iter()
+        .flat_map(|line| line.spans.iter())
+        .find(|span| span.content == " [codellama]")
+        .expect("Should render the model tag");
+    assert_eq!(tag.style.fg, Some(Color::DarkGray));
+}
+
+#[test]
+fn test_parse_history_shows_tags_without_current_model() {
+    let parsed = parse_history("AI [llama3]: Hi");
+    assert_eq!(line_text(&parsed.lines[0]), "AI [llama3]:  Hi");
+}
+
+/// Returns the rendered lines that are turn dividers.
+fn dividers<'a>(text: &'a Text<'a>) -> Vec<&'a ratatui::text::Line<'a>> {
+    text.lines
+        .iter()
+        .filter(|line| {
+            let text = line_text(line);
+            !text.is_empty() && text.chars().all(|c| c == '─')
+        })
+        .collect()
+}
+
+/// Tests that turn separators render as dividers only between turns.
+///
+/// Two completed turns end with two `---` separators, but only the one
+/// between them becomes a divider; the trailing separator is not drawn.
+#[test]
+fn test_turn_divider_between_two_turns() {
+    let history = "\nYOU: One\n\nAI: First\n---\n\nYOU: Two\n\nAI: Second\n---\n";
+    let parsed = parse_history(history);
+    let found = dividers(&parsed);
+    assert_eq!(found.len(), 1);
+    assert_eq!(line_text(found[0]).chars().count(), DEFAULT_DIVIDER_WIDTH as usize);
+    assert_eq!(found[0].spans[0].style.fg, Some(Color::DarkGray));
+    assert!(!parsed.lines.iter().any(|line| line_text(line) == "---"));
+}
+
+#[test]
+fn test_turn_divider_uses_render_width() {
+    let history = "YOU: One\nAI: First\n---\nYOU: Two";
+    let options = RenderOptions { width: Some(12), ..Default::default() };
+    let parsed = parse_history_with(history, &options);
+    let found = dividers(&parsed);
+    assert_eq!(found.len(), 1);
+    assert_eq!(line_text(found[0]), "─".repeat(12));
+}
+
+const THREE_TURNS: &str = "\nYOU: First question\nmore\n\nAI: One\n---\n\nYOU: Show code\n\nAI: ```\n---\n```\n---\n\nYOU: Third\n\nAI: Three\n---\n";
+
+/// Tests that separators inside code fences do not split an exchange.
+#[test]
+fn test_split_exchanges_is_fence_aware() {
+    let exchanges = split_exchanges(THREE_TURNS);
+    assert_eq!(exchanges.len(), 3);
+    assert!(exchanges[1].contains("```\n---\n```"));
+    assert_eq!(exchanges.concat(), THREE_TURNS);
+    assert_eq!(lazyllama::ui::exchange_title(exchanges[0]), "First question");
+}
+
+#[test]
+fn test_split_exchanges_keeps_exchange_in_progress() {
+    let exchanges = split_exchanges("YOU: a\nAI: b\n---\nYOU: c\nAI: ");
+    assert_eq!(exchanges.len(), 2);
+    assert_eq!(exchanges[1], "YOU: c\nAI: ");
+    assert!(split_exchanges("\n\n").is_empty());
+}
+
+/// Tests that a collapsed exchange renders as one `▸` title line followed
+/// by its divider, while the other exchanges stay expanded.
+#[test]
+fn test_collapsed_exchange_renders_title() {
+    let options = RenderOptions {
+        collapsed: [0].into_iter().collect(),
+        ..Default::default()
+    };
+    let (parsed, offsets) = parse_history_layout(THREE_TURNS, &options);
+    let lines: Vec<String> = parsed.lines.iter().map(line_text).collect();
+    assert_eq!(lines[0], "▸ First question");
+    assert!(lines[1].starts_with('─'));
+    assert!(!lines.iter().any(|l| l == "more"));
+    assert!(lines.iter().any(|l| l == "YOU: Third"));
+    assert_eq!(offsets.len(), 3);
+    assert_eq!(offsets[1], 2);
+}
+
+#[test]
+fn test_focused_exchange_is_highlighted() {
+    let options = RenderOptions {
+        focused_exchange: Some(2),
+        ..Default::default()
+    };
+    let (parsed, offsets) = parse_history_layout(THREE_TURNS, &options);
+    let focused = &parsed.lines[offsets[2]];
+    assert!(focused.style.add_modifier.contains(Modifier::REVERSED));
+    assert!(!parsed.lines[offsets[1]].style.add_modifier.contains(Modifier::REVERSED));
+}
+
+/// Tests the timing footer for cold loads, warm models and missing data.
+#[test]
+fn test_format_turn_footer() {
+    use lazyllama::client::GenerationTimings;
+    use lazyllama::ui::format_turn_footer;
+    use std::time::Duration;
+
+    let cold = GenerationTimings {
+        load: Some(Duration::from_millis(2100)),
+        prompt_eval: Some(Duration::from_millis(300)),
+        eval: Some(Duration::from_millis(1400)),
+        eval_count: Some(25),
+    };
+    let wall = Duration::from_millis(3900);
+    assert_eq!(
+        format_turn_footer(Some(&cold), wall),
+        "load 2.1s · prompt 0.3s · gen 1.4s @ 18 tok/s"
+    );
+
+    let warm = GenerationTimings {
+        eval: Some(Duration::from_secs(2)),
+        eval_count: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(format_turn_footer(Some(&warm), wall), "gen 2.0s @ 50 tok/s");
+
+    let no_tokens = GenerationTimings {
+        eval: Some(Duration::ZERO),
+        eval_count: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(format_turn_footer(Some(&no_tokens), wall), "gen 0.0s");
+    assert_eq!(format_turn_footer(Some(&GenerationTimings::default()), wall), "3.9s");
+    assert_eq!(format_turn_footer(None, wall), "3.9s");
+}
+
+#[test]
+fn test_footer_line_is_dimmed() {
+    let parsed = parse_history("AI: Hi\n⏱ 1.2s\n---\nYOU: next");
+    let footer = parsed.lines.iter().find(|l| line_text(l).starts_with("⏱")).unwrap();
+    assert_eq!(footer.spans[0].style.fg, Some(Color::DarkGray));
+}
+
+/// Tests that a well-formed Markdown image renders as a placeholder.
+#[test]
+fn test_markdown_image_placeholder() {
+    let parsed = parse_history("Look: ![A llama](https://example.com/llama.png) nice");
+    let line = &parsed.lines[0];
+    assert_eq!(line_text(line), "Look: 🖼 A llama (https://example.com/llama.png) nice");
+    let image = line.spans.iter().find(|s| s.content.starts_with("🖼")).unwrap();
+    assert_eq!(image.style.fg, Some(Color::LightBlue));
+}
+
+#[test]
+fn test_malformed_markdown_image_stays_plain() {
+    for raw in ["![alt](no closing", "![alt] (url)", "![alt](url with spaces)", "!alt](url)"] {
+        let parsed = parse_history(raw);
+        assert_eq!(line_text(&parsed.lines[0]), raw);
+    }
+    let parsed = parse_history("![](a.png)");
+    assert_eq!(line_text(&parsed.lines[0]), "🖼 image (a.png)");
+}
+
+/// Tests that `~~strikethrough~~` is crossed out and `==highlight==` gets a
+/// background, nested too, and that the markers are split off cleanly next
+/// to other markup such as `**bold**`.
+#[test]
+fn test_strikethrough_and_highlight() {
+    let parsed = parse_history("Use **bold** and ~~old~~ or ==new== now");
+    let line = &parsed.lines[0];
+    assert_eq!(line_text(line), "Use **bold** and old or new now");
+    let contents: Vec<&str> = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(contents, ["Use **bold** and ", "old", " or ", "new", " now"]);
+    assert!(line.spans[1].style.add_modifier.contains(Modifier::CROSSED_OUT));
+    assert!(!line.spans[0].style.add_modifier.contains(Modifier::CROSSED_OUT));
+    assert_eq!(line.spans[3].style.bg, Some(Color::Yellow));
+    assert_eq!(line.spans[4].style, Style::default());
+
+    let parsed = parse_history("~~was ==this==~~");
+    let inner = parsed.lines[0].spans.iter().find(|s| s.content == "this").unwrap();
+    assert!(inner.style.add_modifier.contains(Modifier::CROSSED_OUT));
+    assert_eq!(inner.style.bg, Some(Color::Yellow));
+
+    let parsed = parse_history("(==new==), yes");
+    assert_eq!(parsed.lines[0].spans[1].content, "new");
+    assert_eq!(parsed.lines[0].spans[1].style.bg, Some(Color::Yellow));
+}
+
+/// Tests that comparisons written without spaces are not highlighted.
+#[test]
+fn test_comparisons_are_not_highlighted() {
+    for raw in ["a==b and c==d", "if x==1 || y==2 {", "a ==b and c==d"] {
+        let parsed = parse_history(raw);
+        assert_eq!(line_text(&parsed.lines[0]), raw);
+        assert!(parsed.lines[0].spans.iter().all(|s| s.style.bg.is_none()), "{}", raw);
+    }
+}
+
+/// Tests that unterminated markers and comparisons stay plain text.
+#[test]
+fn test_unterminated_strikethrough_stays_plain() {
+    for raw in ["~~open ended", "if a == b and c == d", "==x", "~~ spaced ~~", "a ~~~~ b"] {
+        let parsed = parse_history(raw);
+        assert_eq!(line_text(&parsed.lines[0]), raw);
+        assert_eq!(parsed.lines[0].spans.len(), 1, "{}", raw);
+    }
+}
+
+fn line_texts(text: &Text) -> Vec<String> {
+    text.lines.iter().map(line_text).collect()
+}
+
+/// Tests that multi-line input renders one line per line break with the
+/// cursor drawn on the right line.
+#[test]
+fn test_input_text_multi_line_cursor() {
+    let text = input_text("ab\ncd", 1, true);
+    assert_eq!(line_texts(&text), vec!["ab", "cd"]);
+    assert_eq!(text.lines[0].spans[1].content, "b");
+    assert!(text.lines[0].spans[1].style.add_modifier.contains(Modifier::REVERSED));
+
+    // A cursor on the line break is a reversed space at the end of the line.
+    let text = input_text("ab\ncd", 2, true);
+    assert_eq!(line_texts(&text), vec!["ab ", "cd"]);
+
+    let text = input_text("ab\n", 3, true);
+    assert_eq!(line_texts(&text), vec!["ab", " "]);
+    assert_eq!(input_cursor_line("ab\n", 3), 1);
+
+    let text = input_text("ab\ncd", 5, false);
+    assert_eq!(line_texts(&text), vec!["ab", "cd"]);
+}
+
+#[test]
+fn test_input_height_is_capped() {
+    assert_eq!(input_height(""), 3);
+    assert_eq!(input_height("a\nb"), 4);
+    assert_eq!(input_height(&"\n".repeat(20)), MAX_INPUT_LINES as u16 + 2);
+}
+
+/// Tests that a reference-style link is resolved against a definition on a
+/// later line, and that the definition line is dimmed.
+#[test]
+fn test_reference_link_resolved_across_lines() {
+    let history = "Read the [Ollama docs][1] and [Rust][].\n\n[1]: https://ollama.com\n[rust]: https://rust-lang.org";
+    let parsed = parse_history(history);
+    let line = &parsed.lines[0];
+    assert_eq!(line_text(line), "Read the Ollama docs and Rust.");
+    let link = line.spans.iter().find(|s| s.content == "Ollama docs").unwrap();
+    assert!(link.style.add_modifier.contains(Modifier::UNDERLINED));
+    assert!(line.spans.iter().any(|s| s.content == "Rust"));
+
+    assert_eq!(line_text(&parsed.lines[2]), "[1]: https://ollama.com");
+    assert_eq!(parsed.lines[2].spans[0].style.fg, Some(Color::DarkGray));
+
+    let defs = link_definitions(history);
+    assert_eq!(defs.len(), 2);
+    assert_eq!(defs["rust"], "https://rust-lang.org");
+}
+
+#[test]
+fn test_unresolved_reference_link_stays_plain() {
+    let parsed = parse_history("See [this][missing] and [1]: not a definition here");
+    assert_eq!(
+        line_text(&parsed.lines[0]),
+        "See [this][missing] and [1]: not a definition here"
+    );
+}
+
+/// Tests that continuation lines of a multi-line prompt keep their
+/// indentation behind a gutter and stop at the AI turn.
+#[test]
+fn test_multi_line_prompt_keeps_indentation() {
+    let history = "YOU: fn main() {\n    let x = 1;\n\n}\n\nAI: Looks fine.\n  indented answer";
+    let parsed = parse_history(history);
+    let lines: Vec<String> = parsed.lines.iter().map(line_text).collect();
+    assert_eq!(lines[1], "│     let x = 1;");
+    assert_eq!(parsed.lines[1].spans[0].style.fg, Some(Color::Magenta));
+    assert_eq!(lines[2], "");
+    assert_eq!(lines[3], "│ }");
+    assert_eq!(lines[6], "  indented answer");
+}
+
+
+/// Tests that the input scrolls horizontally with the cursor and reports
+/// hidden text on either side.
+#[test]
+fn test_input_horizontal_scroll_and_overflow() {
+    let input = "abcdefghij";
+    assert_eq!(input_scroll_x(input, 0, 5), 0);
+    assert_eq!(input_overflow(input, 0, 5), (false, true));
+
+    // A cursor at the end needs one extra cell.
+    assert_eq!(input_scroll_x(input, 10, 5), 6);
+    assert_eq!(input_overflow(input, 6, 5), (true, false));
+
+    assert_eq!(input_scroll_x(input, 7, 5), 3);
+    assert_eq!(input_overflow(input, 3, 5), (true, true));
+
+    // Only the cursor's own line counts for the offset.
+    assert_eq!(input_scroll_x("abcdefghij\nab", 13, 5), 0);
+    assert_eq!(input_overflow("", 0, 5), (false, false));
+}
+
+#[test]
+fn test_stream_progress_counts_words_and_chars() {
+    assert_eq!(stream_progress("   ").as_deref(), Some("· 0 words · 3 chars"));
+    assert_eq!(stream_progress("one").as_deref(), Some("· 1 word · 3 chars"));
+    let long = "word ".repeat(300);
+    assert_eq!(stream_progress(&long).as_deref(), Some("· 300 words · 1.5k chars"));
+}
+
+/// Tests that configured label icons are rendered in front of the labels
+/// and that the text after the labels is unchanged.
+#[test]
+fn test_label_icons() {
+    let options = RenderOptions {
+        labels: Labels {
+            user_icon: "🧑".to_string(),
+            ai_icon: "🦙".to_string(),
+        },
+        ..Default::default()
+    };
+    let parsed = parse_history_with("YOU: Hi\nAI [llama3]: Hello", &options);
+    assert_eq!(line_text(&parsed.lines[0]), "🧑 YOU: Hi");
+    let ai_line = line_text(&parsed.lines[1]);
+    assert!(ai_line.starts_with("🦙 AI [llama3]:") && ai_line.ends_with(" Hello"));
+
+    let plain = parse_history("YOU: Hi");
+    assert_eq!(line_text(&plain.lines[0]), "YOU: Hi");
+}
+
+/// Tests that the last answer skips empty answers and drops the label,
+/// footer and separator.
+#[test]
+fn test_last_answer() {
+    let history = "\nYOU: One\n\nAI [m]: First\nanswer\n⏱ 1.0s\n---\n\nYOU: Two\n\nAI [m]: \n⏱ 0.1s\n---\n";
+    assert_eq!(last_answer(history).as_deref(), Some("First\nanswer"));
+    assert_eq!(last_answer("YOU: pending"), None);
+}
+
+
+/// Tests that compact mode collapses consecutive blank lines to one and
+/// drops the blank lines around turn labels, while the default keeps them.
+#[test]
+fn test_compact_collapses_blank_lines() {
+    let history = "\nYOU: Hi\n\nAI: First\n\n\n\nSecond\n⏱ 1s\n---\n\nYOU: Again\n\nAI: Sure\n";
+
+    let normal = line_texts(&parse_history(history));
+    assert!(normal.windows(2).any(|w| w[0].is_empty() && w[1].is_empty()));
+
+    let options = RenderOptions { compact: true, ..Default::default() };
+    let (text, offsets) = parse_history_layout(history, &options);
+    let compact = line_texts(&text);
+    assert!(!compact.windows(2).any(|w| w[0].is_empty() && w[1].is_empty()));
+    assert!(compact[0].starts_with("YOU:"));
+    assert!(compact[1].starts_with("AI:"));
+    let second = compact.iter().position(|l| l == "Second").unwrap();
+    assert_eq!(compact[second - 1], "");
+    assert!(compact[offsets[1]].starts_with("YOU: Again"));
+    assert!(compact[offsets[1] + 1].starts_with("AI:"));
+}
+
+/// Tests that over-long model names are shortened in the middle, keeping
+/// the family and the tag, and never exceed the width.
+#[test]
+fn test_truncate_middle_keeps_family_and_tag() {
+    let name = "hf.co/bartowski/Meta-Llama-3.1-8B-Instruct-GGUF:Q4_K_M";
+    let short = truncate_middle(name, 24);
+    assert_eq!(short, "hf.co/bartowski/…:Q4_K_M");
+    assert_eq!(short.chars().count(), 24);
+
+    assert_eq!(truncate_middle("mistral", 7), "mistral");
+    assert_eq!(truncate_middle("mistral", 6), "mis…al");
+    assert_eq!(truncate_middle("mistral", 1), "…");
+    assert_eq!(truncate_middle("mistral", 0), "");
+}
+
+/// Tests that short `key: value` lines get a bold key while sentences
+/// with a colon, URLs and the turn labels are left alone.
+#[test]
+fn test_process_styled_text_key_value_lines() {
+    let text = "AI: Options:\ntemperature: 0.8\n- num_ctx: context size\n\
+                The answer is quite simple: restart it.\nDocs at https://ollama.com/library";
+    let mut result = Text::default();
+    process_styled_text(text, &mut result);
+
+    assert_eq!(result.lines[0].spans[0].content, "AI: ");
+    assert_eq!(result.lines[0].spans[0].style.fg, Some(Color::Cyan));
+
+    let key_value = &result.lines[1];
+    assert_eq!(key_value.spans[0].content, "temperature:");
+    assert_eq!(key_value.spans[0].style.fg, Some(Color::Yellow));
+    assert!(key_value.spans[0].style.add_modifier.contains(Modifier::BOLD));
+    assert_eq!(key_value.spans[1].content, " 0.8");
+    assert_eq!(key_value.spans[1].style, Style::default());
+    assert_eq!(result.lines[2].spans[0].content, "- num_ctx:");
+
+    for sentence in &result.lines[3..] {
+        assert_eq!(sentence.spans.len(), 1);
+        assert_eq!(sentence.spans[0].style, Style::default());
+    }
+    assert_eq!(split_key_value("10:30: meeting"), None);
+    assert_eq!(split_key_value("note:"), None);
+}
+
+/// Tests that a definition-list term is bold and its `: definition` lines
+/// are indented.
+#[test]
+fn test_process_styled_text_definition_list() {
+    let mut result = Text::default();
+    process_styled_text("num_predict\n: Maximum tokens to generate\nplain", &mut result);
+
+    assert_eq!(result.lines[0].spans[0].content, "num_predict");
+    assert!(result.lines[0].spans[0].style.add_modifier.contains(Modifier::BOLD));
+    assert_eq!(line_texts(&result)[1], "    Maximum tokens to generate");
+    assert_eq!(result.lines[2].spans[0].style, Style::default());
+}
@@ -22,7 +22,13 @@ use std::collections::HashMap;
 use chrono::Local;
 use tempfile::TempDir;
 use std::fs;
-use lazyllama::utils::{save_history_to_file, save_model_histories};
+use lazyllama::config::LogFormat;
+use lazyllama::ui::{exchange_parts, split_exchanges};
+use lazyllama::utils::{
+    archive_conversation_in, format_log, history_from_log, save_history_to_file_in, save_model_histories_in, delete_logs_for_model_in, history_from_export, normalize_for_export, load_bookmarks_in, save_bookmarks_in, list_logs_grouped_in, load_mru_order_in,
+    export_code_blocks, load_drafts_in, open_in_file_manager, pipe_through, prune_old_logs_in, read_attachment, save_drafts_in, save_history_to_file,
+    save_model_histories, save_mru_order_in, Draft,
+};
 
 #[test]
 fn test_save_history_to_file_empty_string() {
@@ -275,4 +281,243 @@ fn test_full_file_creation_cycle() {
     let read_result = fs::read_to_string(&test_file);
     assert!(read_result.is_ok());
     assert_eq!(read_result.unwrap(), test_history);
-}
\ No newline at end of file
+}
+
+/// Tests that archiving never overwrites an earlier archive, even within
+/// the same second.
+#[test]
+fn test_archive_conversation_in_unique_files() {
+    let dir = TempDir::new().unwrap();
+    let first = archive_conversation_in(dir.path(), "llama3:8b", "YOU: 1").unwrap().unwrap();
+    let second = archive_conversation_in(dir.path(), "llama3:8b", "YOU: 2").unwrap().unwrap();
+
+    assert_ne!(first, second);
+    assert!(first.file_name().unwrap().to_str().unwrap().starts_with("llama3_8b_"));
+    assert_eq!(fs::read_to_string(&first).unwrap(), "YOU: 1");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "YOU: 2");
+}
+
+/// Tests that saved logs contain no internal markers, keep separators in
+/// code blocks, and turn back into the original history when reloaded.
+#[test]
+fn test_normalize_for_export() {
+    let history = "┆ [request debug] model: m, temperature: server default\n┆ Hi\n\
+        \nYOU: Hi\n\nAI [m]: ```yaml\n---\nkey: 1\n```\n---\n\nYOU: Again\n\nAI [m]: Ok\n---\n";
+    let saved = normalize_for_export(history);
+
+    assert!(!saved.contains('┆'));
+    assert_eq!(saved.matches("\n----\n").count(), 2);
+    // Only the YAML document marker in the code block keeps its dashes.
+    assert_eq!(saved.lines().filter(|l| *l == "---").count(), 1);
+    assert!(saved.contains("```yaml\n---\nkey: 1"));
+    assert_eq!(history_from_export(&saved), lazyllama::ui::strip_request_debug(history));
+
+    let dir = TempDir::new().unwrap();
+    let path = archive_conversation_in(dir.path(), "m", history).unwrap().unwrap();
+    assert_eq!(fs::read_to_string(path).unwrap(), saved);
+}
+
+#[test]
+fn test_archive_conversation_in_empty_history() {
+    let dir = TempDir::new().unwrap();
+    assert!(archive_conversation_in(dir.path(), "m", "").unwrap().is_none());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+/// Tests that the recent-models file round-trips and that a missing file
+/// reads as an empty list.
+#[test]
+fn test_mru_order_round_trip() {
+    let dir = TempDir::new().unwrap();
+    assert!(load_mru_order_in(dir.path()).is_empty());
+
+    let order = vec!["qwen2.5-coder:7b".to_string(), "llama3:8b".to_string()];
+    save_mru_order_in(dir.path(), &order).unwrap();
+    assert_eq!(load_mru_order_in(dir.path()), order);
+}
+
+
+/// Tests that logs are grouped by model, counters and the combined `chat`
+/// logs included, and that deleting removes only one model's files.
+#[test]
+fn test_list_and_delete_logs_grouped() {
+    let dir = TempDir::new().unwrap();
+    let first = archive_conversation_in(dir.path(), "llama3:8b", "abc").unwrap().unwrap();
+    let second = archive_conversation_in(dir.path(), "llama3:8b", "de").unwrap().unwrap();
+    archive_conversation_in(dir.path(), "mistral", "xyz").unwrap();
+    fs::write(dir.path().join("chat_2026-01-01_10-00-00.txt"), "chat").unwrap();
+    save_mru_order_in(dir.path(), &["mistral".to_string()]).unwrap();
+
+    let groups = list_logs_grouped_in(dir.path());
+    let mut models: Vec<&String> = groups.keys().collect();
+    models.sort();
+    assert_eq!(models, ["chat", "llama3_8b", "mistral"]);
+    let llama = &groups["llama3_8b"];
+    assert_eq!(llama.iter().map(|f| &f.path).collect::<Vec<_>>(), [&first, &second]);
+    assert_eq!(llama.iter().map(|f| f.size).sum::<u64>(), 5);
+
+    assert_eq!(delete_logs_for_model_in(dir.path(), "llama3:8b").unwrap(), 2);
+    assert!(!first.exists());
+    let groups = list_logs_grouped_in(dir.path());
+    assert!(!groups.contains_key("llama3_8b"));
+    assert_eq!(groups["mistral"].len(), 1);
+    assert_eq!(delete_logs_for_model_in(dir.path(), "llama3:8b").unwrap(), 0);
+}
+
+/// Tests that a missing directory is reported instead of being handed to
+/// the file manager.
+#[test]
+fn test_open_in_file_manager_missing_dir() {
+    let dir = TempDir::new().unwrap();
+    let err = open_in_file_manager(&dir.path().join("missing")).unwrap_err();
+    assert!(err.to_string().contains("is not a directory"));
+}
+
+/// Tests that piping returns the command's output and reports failing and
+/// hanging commands.
+#[cfg(unix)]
+#[test]
+fn test_pipe_through() {
+    let timeout = std::time::Duration::from_secs(5);
+    assert_eq!(pipe_through("tr a-z A-Z", "fn main() {}\n", timeout).unwrap(), "FN MAIN() {}\n");
+    // Output larger than a pipe buffer before all input is read.
+    let input = "x".repeat(200_000);
+    assert_eq!(pipe_through("cat", &input, timeout).unwrap().len(), input.len());
+
+    let err = pipe_through("echo oops >&2; exit 3", "", timeout).unwrap_err();
+    assert!(err.to_string().contains("oops"), "{}", err);
+    let err = pipe_through("sleep 5", "", std::time::Duration::from_millis(100)).unwrap_err();
+    assert!(err.to_string().contains("did not finish"), "{}", err);
+}
+
+/// Tests that pruning keeps the newest combined logs and leaves per-model
+/// logs alone.
+#[test]
+fn test_prune_old_logs() {
+    let dir = TempDir::new().unwrap();
+    let chats: Vec<String> = (1..=5)
+        .map(|day| format!("chat_2026-02-0{}_10-00-00.txt", day))
+        .collect();
+    for name in chats.iter().chain(["llama3_2026-02-01_10-00-00.txt".to_string()].iter()) {
+        fs::write(dir.path().join(name), "YOU: Hi\n").unwrap();
+    }
+
+    assert_eq!(prune_old_logs_in(dir.path(), 3).unwrap(), 2);
+    for (i, name) in chats.iter().enumerate() {
+        assert_eq!(dir.path().join(name).exists(), i >= 2, "{}", name);
+    }
+    assert!(dir.path().join("llama3_2026-02-01_10-00-00.txt").exists());
+    assert_eq!(prune_old_logs_in(dir.path(), 3).unwrap(), 0);
+}
+
+/// Tests the shape of each log format, that the files get the format's
+/// extension, and that every format reads back into the same exchanges.
+#[test]
+fn test_log_formats() {
+    let history = "\nYOU: Hi\n\nAI [llama3]: Hello\n\n```rust\nfn main() {}\n```\n---\n\nYOU: Bye\n\nAI [llama3]: See you\n⏱ 1.0s\n---\n";
+
+    let txt = format_log(history, Some("llama3"), LogFormat::Txt);
+    assert_eq!(txt, normalize_for_export(history));
+    let md = format_log(history, Some("llama3"), LogFormat::Md);
+    assert!(md.starts_with("YOU: Hi\n") && md.ends_with("---\n"), "{}", md);
+    assert!(md.contains("```rust\nfn main() {}\n```"));
+    let json: serde_json::Value = serde_json::from_str(&format_log(history, Some("llama3"), LogFormat::Json)).unwrap();
+    assert_eq!(json["model"], "llama3");
+    assert_eq!(json["exchanges"][1]["prompt"], "Bye");
+    assert_eq!(json["exchanges"][1]["model"], "llama3");
+    assert_eq!(json["exchanges"][1]["answer"], "See you");
+    assert!(json["exchanges"][0]["answer"].as_str().unwrap().ends_with("```"));
+
+    let dir = TempDir::new().unwrap();
+    for format in [LogFormat::Txt, LogFormat::Md, LogFormat::Json] {
+        let path = save_history_to_file_in(dir.path(), history, format).unwrap().unwrap();
+        assert_eq!(path.extension().unwrap(), format.extension());
+        let restored = history_from_log(&path, &fs::read_to_string(&path).unwrap());
+        let prompts: Vec<&str> = split_exchanges(&restored).into_iter().filter_map(exchange_parts).map(|(p, _)| p).collect();
+        assert_eq!(prompts, ["Hi", "Bye"], "{:?}", format);
+        fs::remove_file(path).unwrap();
+    }
+    let histories = HashMap::from([("llama3:8b".to_string(), history.to_string())]);
+    save_model_histories_in(dir.path(), &histories, LogFormat::Json).unwrap();
+    let logs = list_logs_grouped_in(dir.path());
+    assert_eq!(logs["llama3_8b"][0].path.extension().unwrap(), "json");
+}
+
+#[test]
+fn test_bookmarks_round_trip() {
+    let dir = TempDir::new().unwrap();
+    assert!(load_bookmarks_in(dir.path()).is_empty());
+    let bookmarks = HashMap::from([("llama3:8b".to_string(), 42), ("b".to_string(), 0)]);
+    save_bookmarks_in(dir.path(), &bookmarks).unwrap();
+    assert_eq!(load_bookmarks_in(dir.path()), bookmarks);
+
+    fs::write(dir.path().join("bookmarks.txt"), "ok\t3\nbroken\nbad\tx\n").unwrap();
+    assert_eq!(load_bookmarks_in(dir.path()), HashMap::from([("ok".to_string(), 3)]));
+}
+
+/// Tests that large attachments are cut after the last whole line that
+/// fits and that binary files are rejected.
+#[test]
+fn test_read_attachment_caps_size() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("notes.txt");
+    fs::write(&path, "first\nsecond ä\nthird\n").unwrap();
+
+    let whole = read_attachment(&path, 100).unwrap();
+    assert_eq!(whole.content, "first\nsecond ä\nthird\n");
+    assert!(!whole.truncated);
+
+    // The limit falls inside "ä", two lines in.
+    let cut = read_attachment(&path, 14).unwrap();
+    assert_eq!(cut.content, "first\n");
+    assert_eq!(cut.size, 22);
+    assert!(cut.truncated);
+
+    let binary = dir.path().join("image.png");
+    fs::write(&binary, [0x89, 0x50, 0xff, 0xfe]).unwrap();
+    assert!(read_attachment(&binary, 100).unwrap_err().to_string().contains("not a text file"));
+    assert!(read_attachment(dir.path(), 100).is_err());
+}
+
+/// Tests that multi-line drafts survive a save and load, that empty ones
+/// are dropped and that saving none removes the file.
+#[test]
+fn test_drafts_round_trip() {
+    let dir = TempDir::new().unwrap();
+    assert!(load_drafts_in(dir.path()).is_empty());
+
+    let draft = Draft { text: "Line \"one\"\n  two = 2".to_string(), cursor: 7 };
+    let drafts = HashMap::from([
+        ("llama3:8b".to_string(), draft.clone()),
+        ("empty".to_string(), Draft::default()),
+    ]);
+    save_drafts_in(dir.path(), &drafts).unwrap();
+    assert_eq!(load_drafts_in(dir.path()), HashMap::from([("llama3:8b".to_string(), draft)]));
+
+    fs::write(dir.path().join("drafts.toml"), "[m]\ntext = \"ab\"\ncursor = 9\n").unwrap();
+    assert_eq!(load_drafts_in(dir.path())["m"].cursor, 2);
+
+    save_drafts_in(dir.path(), &HashMap::new()).unwrap();
+    assert!(!dir.path().join("drafts.toml").exists());
+}
+
+/// Tests that code blocks are exported under their inferred names, with
+/// an index for taken names and a fallback name for anonymous blocks.
+#[test]
+fn test_export_code_blocks() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.rs"), "existing").unwrap();
+    let answer = "Here you go:\n\n```rust // src/lib.rs\npub fn f() {}\n```\n\n```rust\n// main.rs\nfn main() {}\n```\n\n```toml\n[package]\n```\n\n```\n// ../../escape.txt\nx\n```\n";
+
+    let paths = export_code_blocks(answer, dir.path()).unwrap();
+    let names: Vec<_> = paths
+        .iter()
+        .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["src/lib.rs", "main_2.rs", "block_3.toml", "block_4.txt"]);
+    assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "pub fn f() {}\n");
+    assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "// main.rs\nfn main() {}\n");
+    assert_eq!(fs::read_to_string(dir.path().join("main.rs")).unwrap(), "existing");
+
+    assert!(export_code_blocks("no code", dir.path()).unwrap().is_empty());
+}